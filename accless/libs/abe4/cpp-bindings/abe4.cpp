@@ -93,6 +93,83 @@ std::string keygenPartial(const std::string &gid,
     return partial_usk_b64;
 }
 
+namespace {
+KeyVerification parseKeyVerification(char *result, const char *ffi_name) {
+    if (!result) {
+        std::cerr << "accless(abe4): FFI call to " << ffi_name
+                  << " failed. See Rust logs for details." << std::endl;
+        throw std::runtime_error(std::string("accless(abe4): ") + ffi_name +
+                                 " FFI call failed");
+    }
+
+    auto result_json = nlohmann::json::parse(result);
+    free_string(result);
+
+    return {result_json["valid"].get<bool>(),
+            result_json["error"].get<std::string>()};
+}
+} // namespace
+
+KeyVerification verifyPartialUsk(const std::string &gid,
+                                 const std::string &partial_mpk_b64,
+                                 const std::string &partial_usk_b64,
+                                 const std::vector<UserAttribute> &user_attrs) {
+    nlohmann::json user_attrs_json = nlohmann::json::array();
+    for (const auto &attr : user_attrs) {
+        user_attrs_json.push_back({{"authority", attr.authority},
+                                   {"label", attr.label},
+                                   {"attribute", attr.attribute}});
+    }
+
+    char *result = verify_partial_usk_abe4(
+        gid.c_str(), partial_mpk_b64.c_str(), partial_usk_b64.c_str(),
+        user_attrs_json.dump().c_str());
+    return parseKeyVerification(result, "verify_partial_usk_abe4");
+}
+
+KeyVerification verifyUsk(const std::string &gid, const std::string &mpk_b64,
+                          const std::string &usk_b64,
+                          const std::vector<UserAttribute> &user_attrs) {
+    nlohmann::json user_attrs_json = nlohmann::json::array();
+    for (const auto &attr : user_attrs) {
+        user_attrs_json.push_back({{"authority", attr.authority},
+                                   {"label", attr.label},
+                                   {"attribute", attr.attribute}});
+    }
+
+    char *result =
+        verify_usk_abe4(gid.c_str(), mpk_b64.c_str(), usk_b64.c_str(),
+                        user_attrs_json.dump().c_str());
+    return parseKeyVerification(result, "verify_usk_abe4");
+}
+
+std::string delegateUsk(const std::string &gid, const std::string &mpk_b64,
+                        const std::string &usk_b64,
+                        const std::vector<UserAttribute> &user_attrs) {
+    nlohmann::json user_attrs_json = nlohmann::json::array();
+    for (const auto &attr : user_attrs) {
+        user_attrs_json.push_back({{"authority", attr.authority},
+                                   {"label", attr.label},
+                                   {"attribute", attr.attribute}});
+    }
+
+    char *result =
+        delegate_usk_abe4(gid.c_str(), mpk_b64.c_str(), usk_b64.c_str(),
+                          user_attrs_json.dump().c_str());
+    if (!result) {
+        std::cerr << "accless(abe4): FFI call to delegate_usk_abe4 failed. See "
+                     "Rust logs for details."
+                  << std::endl;
+        throw std::runtime_error(
+            "accless(abe4): delegate_usk_abe4 FFI call failed");
+    }
+
+    std::string delegated_usk_b64(result);
+    free_string(result);
+
+    return delegated_usk_b64;
+}
+
 EncryptOutput encrypt(const std::string &mpk, const std::string &policy) {
     char *result = encrypt_abe4(mpk.c_str(), policy.c_str());
     if (!result) {
@@ -127,6 +204,49 @@ std::optional<std::string> decrypt(const std::string &usk,
     return gt_b64;
 }
 
+namespace {
+// Throws an Error with the thread-local error message if status is not OK.
+void checkStatus(Abe4Status status, const std::string &fn) {
+    if (status == ABE4_STATUS_OK) {
+        return;
+    }
+
+    const char *message = abe4_last_error_message();
+    throw Error(status, "accless(abe4): " + fn + " failed: " +
+                            (message ? message : "unknown error"));
+}
+
+// Copies a buffer returned by the C API and releases it.
+std::vector<uint8_t> takeBuffer(Abe4Buffer buffer) {
+    std::vector<uint8_t> bytes(buffer.data, buffer.data + buffer.len);
+    abe4_buffer_free(buffer);
+    return bytes;
+}
+} // namespace
+
+Mpk Mpk::fromBase64(const std::string &mpk_b64) {
+    std::vector<uint8_t> bytes = accless::base64::decode(mpk_b64);
+    Abe4Mpk *handle = nullptr;
+    checkStatus(abe4_mpk_from_bytes(bytes.data(), bytes.size(), &handle),
+                "abe4_mpk_from_bytes");
+    return Mpk(handle);
+}
+
+Usk Usk::fromBase64(const std::string &usk_b64) {
+    std::vector<uint8_t> bytes = accless::base64::decode(usk_b64);
+    Abe4Usk *handle = nullptr;
+    checkStatus(abe4_usk_from_bytes(bytes.data(), bytes.size(), &handle),
+                "abe4_usk_from_bytes");
+    return Usk(handle);
+}
+
+Policy Policy::parse(const std::string &policy) {
+    Abe4Policy *handle = nullptr;
+    checkStatus(abe4_policy_parse(policy.c_str(), &handle),
+                "abe4_policy_parse");
+    return Policy(handle);
+}
+
 namespace hybrid {
 
 EncryptOutput encrypt(const std::string &mpk, const std::string &policy,
@@ -174,6 +294,154 @@ decrypt(const std::string &usk, const std::string &gid,
     return accless::base64::decode(plaintext_b64);
 }
 
+EncryptOutput encrypt(const Mpk &mpk, const Policy &policy,
+                      const std::vector<uint8_t> &plaintext,
+                      const std::vector<uint8_t> &aad) {
+    Abe4Buffer abe_ct{nullptr, 0};
+    Abe4Buffer sym_ct{nullptr, 0};
+    checkStatus(abe4_encrypt_hybrid(mpk.get(), policy.get(), plaintext.data(),
+                                    plaintext.size(), aad.data(), aad.size(),
+                                    &abe_ct, &sym_ct),
+                "abe4_encrypt_hybrid");
+
+    return {accless::base64::encode(takeBuffer(abe_ct)),
+            accless::base64::encode(takeBuffer(sym_ct))};
+}
+
+std::optional<std::vector<uint8_t>>
+decrypt(const Usk &usk, const std::string &gid, const Policy &policy,
+        const std::string &abe_ct, const std::string &sym_ct,
+        const std::vector<uint8_t> &aad) {
+    std::vector<uint8_t> abe_ct_bytes = accless::base64::decode(abe_ct);
+    std::vector<uint8_t> sym_ct_bytes = accless::base64::decode(sym_ct);
+
+    Abe4Buffer plaintext{nullptr, 0};
+    Abe4Status status = abe4_decrypt_hybrid(
+        usk.get(), gid.c_str(), policy.get(), abe_ct_bytes.data(),
+        abe_ct_bytes.size(), sym_ct_bytes.data(), sym_ct_bytes.size(),
+        aad.data(), aad.size(), &plaintext);
+    // Access-control outcomes are not errors, other failures throw.
+    if (status == ABE4_STATUS_DECRYPTION_FAILED ||
+        status == ABE4_STATUS_UNSATISFIED_POLICY ||
+        status == ABE4_STATUS_MISSING_AUTHORITY_KEY) {
+        return std::nullopt;
+    }
+    checkStatus(status, "abe4_decrypt_hybrid");
+
+    return takeBuffer(plaintext);
+}
+
+std::string encryptContainer(const std::string &mpk, const std::string &policy,
+                             const std::vector<uint8_t> &plaintext,
+                             const std::vector<uint8_t> &aad,
+                             CipherSuite suite) {
+    std::string plaintext_b64 = accless::base64::encode(plaintext);
+    std::string aad_b64 = accless::base64::encode(aad);
+
+    char *result = encrypt_hybrid_container_with_suite_abe4(
+        mpk.c_str(), policy.c_str(), static_cast<uint8_t>(suite),
+        plaintext_b64.c_str(), aad_b64.c_str());
+    if (!result) {
+        std::cerr << "accless(abe4): FFI call to "
+                     "encrypt_hybrid_container_with_suite_abe4 failed. See "
+                     "Rust logs for details."
+                  << std::endl;
+        throw std::runtime_error("accless(abe4): "
+                                 "encrypt_hybrid_container_with_suite_abe4 FFI "
+                                 "call failed");
+    }
+
+    std::string container_b64(result);
+    free_string(result);
+
+    return container_b64;
+}
+
+std::optional<std::vector<uint8_t>>
+decryptContainer(const std::string &usk, const std::string &gid,
+                 const std::string &container) {
+    char *result = decrypt_hybrid_container_abe4(usk.c_str(), gid.c_str(),
+                                                 container.c_str());
+    if (!result) {
+        std::cerr << "accless(abe4): FFI call to decrypt_hybrid_container_abe4 "
+                     "failed. See Rust logs for details."
+                  << std::endl;
+        return std::nullopt;
+    }
+
+    std::string plaintext_b64(result);
+    free_string(result);
+
+    return accless::base64::decode(plaintext_b64);
+}
+
+std::optional<ContainerInfo> containerInfo(const std::string &container) {
+    char *result = hybrid_container_info_abe4(container.c_str());
+    if (!result) {
+        std::cerr << "accless(abe4): FFI call to hybrid_container_info_abe4 "
+                     "failed. See Rust logs for details."
+                  << std::endl;
+        return std::nullopt;
+    }
+
+    auto result_json = nlohmann::json::parse(result);
+    free_string(result);
+
+    return ContainerInfo{
+        result_json["version"].get<uint8_t>(),
+        static_cast<CipherSuite>(result_json["suite"].get<uint8_t>()),
+        result_json["policy"].get<std::string>(),
+        result_json["authorities"].get<std::vector<std::string>>(),
+    };
+}
+
+EncryptOutput encryptWrapped(const std::string &mpk, const std::string &policy,
+                             const std::vector<uint8_t> &plaintext,
+                             const std::vector<uint8_t> &aad) {
+    std::string plaintext_b64 = accless::base64::encode(plaintext);
+    std::string aad_b64 = accless::base64::encode(aad);
+
+    char *result = encrypt_hybrid_wrapped_abe4(
+        mpk.c_str(), policy.c_str(), plaintext_b64.c_str(), aad_b64.c_str());
+    if (!result) {
+        std::cerr << "accless(abe4): FFI call to encrypt_hybrid_wrapped_abe4 "
+                     "failed. See Rust logs for details."
+                  << std::endl;
+        throw std::runtime_error(
+            "accless(abe4): encrypt_hybrid_wrapped_abe4 FFI call failed");
+    }
+
+    auto result_json = nlohmann::json::parse(result);
+    free_string(result);
+
+    return {result_json["abe_ct"], result_json["sym_ct"]};
+}
+
+EncryptOutput reencapsulate(const std::string &mpk, const std::string &usk,
+                            const std::string &gid, const std::string &policy,
+                            const std::string &abe_ct,
+                            const std::string &sym_ct,
+                            const std::string &new_policy,
+                            const std::vector<uint8_t> &aad) {
+    std::string aad_b64 = accless::base64::encode(aad);
+
+    char *result = reencapsulate_hybrid_abe4(
+        mpk.c_str(), usk.c_str(), gid.c_str(), policy.c_str(), abe_ct.c_str(),
+        sym_ct.c_str(), new_policy.c_str(), aad_b64.c_str());
+    if (!result) {
+        std::cerr << "accless(abe4): FFI call to reencapsulate_hybrid_abe4 "
+                     "failed. See Rust logs for details."
+                  << std::endl;
+        throw std::runtime_error(
+            "accless(abe4): reencapsulate_hybrid_abe4 FFI call failed");
+    }
+
+    auto result_json = nlohmann::json::parse(result);
+    free_string(result);
+
+    return {result_json["abe_ct"], result_json["sym_ct"]};
+}
+
 } // namespace hybrid
 
 std::map<std::string, std::vector<uint8_t>>
@@ -277,4 +545,32 @@ std::vector<std::string> getPolicyAuthorities(const std::string &policy) {
     return result_json.get<std::vector<std::string>>();
 }
 
+PolicyEvaluation evaluatePolicy(const std::string &policy,
+                                const std::vector<UserAttribute> &user_attrs) {
+    nlohmann::json user_attrs_json = nlohmann::json::array();
+    for (const auto &attr : user_attrs) {
+        user_attrs_json.push_back({{"authority", attr.authority},
+                                   {"label", attr.label},
+                                   {"attribute", attr.attribute}});
+    }
+
+    char *result =
+        evaluate_policy_abe4(policy.c_str(), user_attrs_json.dump().c_str());
+    if (!result) {
+        std::cerr << "accless(abe4): FFI call to evaluate_policy_abe4 "
+                     "failed. See Rust "
+                     "logs for details."
+                  << std::endl;
+        throw std::runtime_error(
+            "accless(abe4): evaluate_policy_abe4 FFI call failed");
+    }
+
+    auto result_json = nlohmann::json::parse(result);
+    free_string(result);
+
+    return {result_json["satisfied"].get<bool>(),
+            result_json["literals"].get<std::vector<std::string>>(),
+            result_json["missing"].get<std::vector<std::string>>()};
+}
+
 } // namespace accless::abe4
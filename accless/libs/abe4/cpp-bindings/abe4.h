@@ -1,8 +1,12 @@
 #pragma once
 
+#include "abe4_capi.h"
+
 #include <cstdint>
 #include <map>
+#include <memory>
 #include <optional>
+#include <stdexcept>
 #include <string>
 #include <vector>
 
@@ -18,12 +22,40 @@ char *setup_partial_abe4(const char *auth_id_cstr);
 char *keygen_partial_abe4(const char *gid_cstr,
                           const char *partial_msk_b64_cstr,
                           const char *user_attrs_json);
+char *verify_partial_usk_abe4(const char *gid_cstr,
+                              const char *partial_mpk_b64_cstr,
+                              const char *partial_usk_b64_cstr,
+                              const char *user_attrs_json);
+char *verify_usk_abe4(const char *gid_cstr, const char *mpk_b64_cstr,
+                      const char *usk_b64_cstr, const char *user_attrs_json);
+char *delegate_usk_abe4(const char *gid_cstr, const char *mpk_b64_cstr,
+                        const char *usk_b64_cstr, const char *user_attrs_json);
 char *policy_authorities_abe4(const char *policy_str);
+char *evaluate_policy_abe4(const char *policy_str, const char *user_attrs_json);
 char *encrypt_hybrid_abe4(const char *mpk_b64, const char *policy_str,
                           const char *plaintext_b64, const char *aad_b64);
 char *decrypt_hybrid_abe4(const char *usk_b64, const char *gid,
                           const char *policy_str, const char *abe_ct_b64,
                           const char *sym_ct_b64, const char *aad_b64);
+char *encrypt_hybrid_container_abe4(const char *mpk_b64, const char *policy_str,
+                                    const char *plaintext_b64,
+                                    const char *aad_b64);
+char *encrypt_hybrid_container_with_suite_abe4(const char *mpk_b64,
+                                               const char *policy_str,
+                                               uint8_t suite_id,
+                                               const char *plaintext_b64,
+                                               const char *aad_b64);
+char *decrypt_hybrid_container_abe4(const char *usk_b64, const char *gid,
+                                    const char *container_b64);
+char *hybrid_container_info_abe4(const char *container_b64);
+char *encrypt_hybrid_wrapped_abe4(const char *mpk_b64, const char *policy_str,
+                                  const char *plaintext_b64,
+                                  const char *aad_b64);
+char *reencapsulate_hybrid_abe4(const char *mpk_b64, const char *usk_b64,
+                                const char *gid, const char *policy_str,
+                                const char *abe_ct_b64, const char *sym_ct_b64,
+                                const char *new_policy_str,
+                                const char *aad_b64);
 
 } // extern "C"
 
@@ -102,6 +134,73 @@ std::string keygenPartial(const std::string &gid,
                           const std::string &partial_msk_b64,
                           const std::vector<UserAttribute> &user_attrs);
 
+struct KeyVerification {
+    bool valid;
+    // If not valid, the reason why.
+    std::string error;
+};
+
+/**
+ * @brief Verifies a partial User Secret Key (USK) against the partial Master
+ * Public Key (MPK) of the authority that issued it.
+ *
+ * This lets the recipient of a partial USK check that it is well-formed for
+ * its GID and attributes, instead of finding out when a decryption fails.
+ *
+ * @param gid The group ID the partial USK is expected to be issued for.
+ * @param partial_mpk_b64 A base64 encoded string representing the partial
+ * Master Public Key.
+ * @param partial_usk_b64 A base64 encoded string representing the partial
+ * User Secret Key.
+ * @param user_attrs The attributes the key is expected to hold, in the order
+ * they were given to `keygenPartial`.
+ * @return A KeyVerification with the result of the verification.
+ * @throws std::runtime_error if an argument is malformed.
+ */
+KeyVerification verifyPartialUsk(const std::string &gid,
+                                 const std::string &partial_mpk_b64,
+                                 const std::string &partial_usk_b64,
+                                 const std::vector<UserAttribute> &user_attrs);
+
+/**
+ * @brief Verifies a User Secret Key (USK) against the Master Public Key (MPK)
+ * of the authorities that issued it.
+ *
+ * On top of verifying each partial key, this checks that all the partial keys
+ * were issued for the same GID.
+ *
+ * @param gid The group ID the USK is expected to be issued for.
+ * @param mpk_b64 A base64 encoded string representing the Master Public Key.
+ * @param usk_b64 A base64 encoded string representing the User Secret Key.
+ * @param user_attrs The attributes the key is expected to hold, in the order
+ * they were given to `keygen`.
+ * @return A KeyVerification with the result of the verification.
+ * @throws std::runtime_error if an argument is malformed.
+ */
+KeyVerification verifyUsk(const std::string &gid, const std::string &mpk_b64,
+                          const std::string &usk_b64,
+                          const std::vector<UserAttribute> &user_attrs);
+
+/**
+ * @brief Derives a User Secret Key (USK) for a subset of the attributes of
+ * another one.
+ *
+ * The delegated key is re-randomised, so a function can hand it to the
+ * functions it chains to without revealing its own key, nor contacting the
+ * authorities again.
+ *
+ * @param gid The group ID the USK was issued for.
+ * @param mpk_b64 A base64 encoded string representing the Master Public Key.
+ * @param usk_b64 A base64 encoded string representing the User Secret Key.
+ * @param user_attrs The attributes to keep. For each of their labels, they must
+ * hold every value of the label that the key holds.
+ * @return A base64 encoded string representing the delegated User Secret Key.
+ * @throws std::runtime_error if the FFI call fails.
+ */
+std::string delegateUsk(const std::string &gid, const std::string &mpk_b64,
+                        const std::string &usk_b64,
+                        const std::vector<UserAttribute> &user_attrs);
+
 /**
  * @brief Encrypts a message using the Master Public Key (MPK) and a policy.
  *
@@ -214,7 +313,136 @@ std::string packFullKey(const std::vector<std::string> &authorities,
  */
 std::vector<std::string> getPolicyAuthorities(const std::string &policy);
 
+struct PolicyEvaluation {
+    bool satisfied;
+    // If satisfied, the literals that would be used to decrypt.
+    std::vector<std::string> literals;
+    // If not satisfied, the sub-expressions of the policy that are missing.
+    std::vector<std::string> missing;
+};
+
+/**
+ * @brief Checks whether a set of user attributes satisfies a policy.
+ *
+ * This helper does not run any cryptographic operation, so it is cheap to call
+ * before attempting a decryption. If the policy is not satisfied, the result
+ * contains the sub-expressions of the policy that are missing.
+ *
+ * @param policy A policy string.
+ * @param user_attrs A vector of UserAttribute objects.
+ * @return A PolicyEvaluation with the result of the evaluation.
+ */
+PolicyEvaluation evaluatePolicy(const std::string &policy,
+                                const std::vector<UserAttribute> &user_attrs);
+
+/**
+ * @brief Error thrown by the handle-based API, carrying the status code and
+ * the message returned by `abe4_last_error_message`.
+ */
+class Error : public std::runtime_error {
+  public:
+    Error(Abe4Status status, const std::string &what)
+        : std::runtime_error(what), status_(status) {}
+
+    Abe4Status status() const { return status_; }
+
+  private:
+    Abe4Status status_;
+};
+
+/**
+ * @brief Parsed Master Public Key (MPK).
+ *
+ * Owns an opaque handle to a deserialized MPK, so that it can be re-used
+ * across many encryptions without paying the deserialization cost each time.
+ */
+class Mpk {
+  public:
+    /**
+     * @brief Deserializes an MPK.
+     *
+     * @param mpk_b64 Base64-encoded master public key.
+     * @return The parsed MPK.
+     * @throws Error on error.
+     */
+    static Mpk fromBase64(const std::string &mpk_b64);
+
+    const Abe4Mpk *get() const { return handle_.get(); }
+
+  private:
+    struct Deleter {
+        void operator()(Abe4Mpk *mpk) const { abe4_mpk_free(mpk); }
+    };
+
+    explicit Mpk(Abe4Mpk *handle) : handle_(handle) {}
+
+    std::unique_ptr<Abe4Mpk, Deleter> handle_;
+};
+
+/**
+ * @brief Parsed User Secret Key (USK).
+ *
+ * Owns an opaque handle to a deserialized USK. The key is zeroized when the
+ * handle is released.
+ */
+class Usk {
+  public:
+    /**
+     * @brief Deserializes a USK.
+     *
+     * @param usk_b64 Base64-encoded user secret key.
+     * @return The parsed USK.
+     * @throws Error on error.
+     */
+    static Usk fromBase64(const std::string &usk_b64);
+
+    const Abe4Usk *get() const { return handle_.get(); }
+
+  private:
+    struct Deleter {
+        void operator()(Abe4Usk *usk) const { abe4_usk_free(usk); }
+    };
+
+    explicit Usk(Abe4Usk *handle) : handle_(handle) {}
+
+    std::unique_ptr<Abe4Usk, Deleter> handle_;
+};
+
+/**
+ * @brief Parsed policy.
+ */
+class Policy {
+  public:
+    /**
+     * @brief Parses a policy string.
+     *
+     * @param policy A policy string.
+     * @return The parsed policy.
+     * @throws Error on error.
+     */
+    static Policy parse(const std::string &policy);
+
+    const Abe4Policy *get() const { return handle_.get(); }
+
+  private:
+    struct Deleter {
+        void operator()(Abe4Policy *policy) const { abe4_policy_free(policy); }
+    };
+
+    explicit Policy(Abe4Policy *handle) : handle_(handle) {}
+
+    std::unique_ptr<Abe4Policy, Deleter> handle_;
+};
+
 namespace hybrid {
+// Symmetric cipher suites. Must be kept in sync with `CipherSuite` in Rust.
+enum class CipherSuite : uint8_t {
+    Aes128Gcm = 1,
+    Aes256Gcm = 2,
+    Aes256GcmSiv = 3,
+    ChaCha20Poly1305 = 4,
+};
+
 struct EncryptOutput {
     std::string abe_ciphertext;
     std::string sym_ciphertext;
@@ -255,6 +483,142 @@ std::optional<std::vector<uint8_t>>
 decrypt(const std::string &usk, const std::string &gid,
         const std::string &policy, const std::string &abe_ct,
         const std::string &sym_ct, const std::vector<uint8_t> &aad);
+
+/**
+ * @brief Encrypts plaintext using pre-parsed key and policy handles.
+ *
+ * Same as the string-based overload, but does not deserialize the MPK or parse
+ * the policy on every call.
+ *
+ * @param mpk Parsed master public key.
+ * @param policy Parsed policy.
+ * @param plaintext Plaintext bytes to encrypt.
+ * @param aad Associated data bound to the symmetric encryption.
+ * @return EncryptOutput containing the base64-encoded ABE and symmetric
+ * ciphertexts.
+ * @throws Error on error.
+ */
+EncryptOutput encrypt(const Mpk &mpk, const Policy &policy,
+                      const std::vector<uint8_t> &plaintext,
+                      const std::vector<uint8_t> &aad);
+
+/**
+ * @brief Decrypts a hybrid ciphertext using pre-parsed key and policy
+ * handles.
+ *
+ * Same as the string-based overload, but does not deserialize the USK or parse
+ * the policy on every call.
+ *
+ * @param usk Parsed user secret key.
+ * @param gid Group identifier.
+ * @param policy Parsed policy.
+ * @param abe_ct Base64-encoded ABE ciphertext.
+ * @param sym_ct Base64-encoded symmetric ciphertext.
+ * @param aad Associated data bound to the symmetric encryption.
+ * @return Optional plaintext bytes on success, or std::nullopt if the key
+ * does not satisfy the policy or the ciphertext fails to authenticate.
+ * @throws Error if an argument is malformed, or if the key was issued for a
+ * different gid.
+ */
+std::optional<std::vector<uint8_t>>
+decrypt(const Usk &usk, const std::string &gid, const Policy &policy,
+        const std::string &abe_ct, const std::string &sym_ct,
+        const std::vector<uint8_t> &aad);
+
+struct ContainerInfo {
+    uint8_t version;
+    CipherSuite suite;
+    std::string policy;
+    std::vector<std::string> authorities;
+};
+
+/**
+ * @brief Encrypts plaintext into a self-describing hybrid container.
+ *
+ * The container holds the policy, the referenced authorities, the AAD, and
+ * both ciphertext components, so it can be decrypted with the user secret key
+ * alone.
+ *
+ * @param mpk Base64-encoded master public key.
+ * @param policy Policy string.
+ * @param plaintext Plaintext bytes to encrypt.
+ * @param aad Associated data bound to the symmetric encryption.
+ * @param suite Symmetric cipher suite used to encrypt the plaintext.
+ * @return The base64-encoded container.
+ * @throws std::runtime_error on error.
+ */
+std::string encryptContainer(const std::string &mpk, const std::string &policy,
+                             const std::vector<uint8_t> &plaintext,
+                             const std::vector<uint8_t> &aad,
+                             CipherSuite suite = CipherSuite::Aes128Gcm);
+
+/**
+ * @brief Decrypts a hybrid container.
+ *
+ * @param usk Base64-encoded user secret key.
+ * @param gid Group identifier.
+ * @param container Base64-encoded container.
+ * @return Optional plaintext bytes on success, or std::nullopt on failure.
+ */
+std::optional<std::vector<uint8_t>>
+decryptContainer(const std::string &usk, const std::string &gid,
+                 const std::string &container);
+
+/**
+ * @brief Reads the metadata of a hybrid container without decrypting it.
+ *
+ * @param container Base64-encoded container.
+ * @return Optional ContainerInfo, or std::nullopt if the container is
+ * malformed.
+ */
+std::optional<ContainerInfo> containerInfo(const std::string &container);
+
+/**
+ * @brief Encrypts plaintext under a random data key, wrapped under the CP-ABE
+ * key, so that it can later be re-encapsulated to another policy without
+ * re-encrypting the payload.
+ *
+ * The ciphertext is decrypted with `decrypt`, like any other hybrid
+ * ciphertext.
+ *
+ * @param mpk Base64-encoded master public key.
+ * @param policy Policy string.
+ * @param plaintext Plaintext bytes to encrypt.
+ * @param aad Associated data bound to the symmetric encryption.
+ * @return EncryptOutput containing the base64-encoded ABE and symmetric
+ * ciphertexts.
+ * @throws std::runtime_error on error.
+ */
+EncryptOutput encryptWrapped(const std::string &mpk, const std::string &policy,
+                             const std::vector<uint8_t> &plaintext,
+                             const std::vector<uint8_t> &aad);
+
+/**
+ * @brief Re-encapsulates a hybrid ciphertext to a new policy.
+ *
+ * This is not an owner-side policy update: the caller must hold a key that
+ * satisfies the current policy. For a ciphertext from `encryptWrapped`, only
+ * the header of the symmetric ciphertext changes. The data key is kept, and
+ * the payload is only authenticated with `aad`, not with the new policy.
+ *
+ * @param mpk Base64-encoded master public key.
+ * @param usk Base64-encoded user secret key that satisfies `policy`.
+ * @param gid Group identifier of `usk`.
+ * @param policy Policy string the ciphertext is encrypted under.
+ * @param abe_ct Base64-encoded ABE ciphertext.
+ * @param sym_ct Base64-encoded symmetric ciphertext.
+ * @param new_policy Policy string to move the ciphertext to.
+ * @param aad Associated data bound to the symmetric encryption.
+ * @return EncryptOutput containing the base64-encoded ABE and symmetric
+ * ciphertexts under the new policy.
+ * @throws std::runtime_error on error.
+ */
+EncryptOutput reencapsulate(const std::string &mpk, const std::string &usk,
+                            const std::string &gid, const std::string &policy,
+                            const std::string &abe_ct,
+                            const std::string &sym_ct,
+                            const std::string &new_policy,
+                            const std::vector<uint8_t> &aad);
 } // namespace hybrid
 
 } // namespace accless::abe4
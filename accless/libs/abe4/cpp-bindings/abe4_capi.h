@@ -0,0 +1,216 @@
+#ifndef ACCLESS_ABE4_CAPI_H
+#define ACCLESS_ABE4_CAPI_H
+
+// Generated by cbindgen from src/capi.rs. Do not edit by hand.
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Status code returned by every function in the handle-based C API.
+typedef enum Abe4Status {
+  // The call succeeded.
+  ABE4_STATUS_OK = 0,
+  // A required pointer argument was null.
+  ABE4_STATUS_NULL_POINTER = 1,
+  // A string argument was not valid UTF-8.
+  ABE4_STATUS_INVALID_UTF8 = 2,
+  // A key could not be deserialized, or does not verify.
+  ABE4_STATUS_INVALID_KEY = 3,
+  // A policy could not be parsed.
+  ABE4_STATUS_INVALID_POLICY = 4,
+  // A ciphertext could not be deserialized.
+  ABE4_STATUS_INVALID_CIPHERTEXT = 5,
+  // Encryption failed.
+  ABE4_STATUS_ENCRYPTION_FAILED = 6,
+  // Decryption failed, e.g. the ciphertext has been tampered with or was
+  // encrypted with a different AAD.
+  ABE4_STATUS_DECRYPTION_FAILED = 7,
+  // The library panicked. This is a bug.
+  ABE4_STATUS_PANIC = 8,
+  // The user attributes in the key do not satisfy the policy.
+  ABE4_STATUS_UNSATISFIED_POLICY = 9,
+  // No key was given for an authority that the policy references.
+  ABE4_STATUS_MISSING_AUTHORITY_KEY = 10,
+  // The user secret key was issued for a different global identifier.
+  ABE4_STATUS_GID_MISMATCH = 11,
+} Abe4Status;
+
+// Opaque handle to a master public key.
+typedef struct Abe4Mpk Abe4Mpk;
+
+// Opaque handle to a parsed policy.
+typedef struct Abe4Policy Abe4Policy;
+
+// Opaque handle to a user secret key.
+typedef struct Abe4Usk Abe4Usk;
+
+// Byte buffer allocated by this library. Must be released with
+// `abe4_buffer_free`.
+typedef struct Abe4Buffer {
+  uint8_t *data;
+  size_t len;
+} Abe4Buffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// # Description
+//
+// Returns the message of the last error raised on the calling thread, or
+// null if no call has failed yet.
+//
+// # Returns
+//
+// A NUL-terminated string owned by the library. It is valid until the next
+// call into the library from the same thread, and must not be freed.
+const char *abe4_last_error_message(void);
+
+// # Description
+//
+// Deserializes a master public key from its compressed byte encoding.
+//
+// # Arguments
+//
+// * `bytes`: Pointer to the serialized MPK.
+// * `len`: Length of `bytes`.
+// * `out`: On success, set to a new handle that must be released with
+//   `abe4_mpk_free`.
+//
+// # Safety
+//
+// `bytes` must point to `len` readable bytes, and `out` to writable memory.
+enum Abe4Status abe4_mpk_from_bytes(const uint8_t *bytes, size_t len, struct Abe4Mpk **out);
+
+// Releases a master public key handle. Passing null is a no-op.
+//
+// # Safety
+//
+// `mpk` must be null or a handle returned by `abe4_mpk_from_bytes` that has
+// not been freed yet.
+void abe4_mpk_free(struct Abe4Mpk *mpk);
+
+// # Description
+//
+// Deserializes a user secret key from its compressed byte encoding.
+//
+// # Arguments
+//
+// * `bytes`: Pointer to the serialized USK.
+// * `len`: Length of `bytes`.
+// * `out`: On success, set to a new handle that must be released with
+//   `abe4_usk_free`.
+//
+// # Safety
+//
+// `bytes` must point to `len` readable bytes, and `out` to writable memory.
+enum Abe4Status abe4_usk_from_bytes(const uint8_t *bytes, size_t len, struct Abe4Usk **out);
+
+// Releases a user secret key handle, zeroizing the key. Passing null is a
+// no-op.
+//
+// # Safety
+//
+// `usk` must be null or a handle returned by `abe4_usk_from_bytes` that has
+// not been freed yet.
+void abe4_usk_free(struct Abe4Usk *usk);
+
+// # Description
+//
+// Parses a policy string.
+//
+// # Arguments
+//
+// * `policy`: NUL-terminated policy string.
+// * `out`: On success, set to a new handle that must be released with
+//   `abe4_policy_free`.
+//
+// # Safety
+//
+// `policy` must be a NUL-terminated string, and `out` must point to writable
+// memory.
+enum Abe4Status abe4_policy_parse(const char *policy, struct Abe4Policy **out);
+
+// Releases a policy handle. Passing null is a no-op.
+//
+// # Safety
+//
+// `policy` must be null or a handle returned by `abe4_policy_parse` that has
+// not been freed yet.
+void abe4_policy_free(struct Abe4Policy *policy);
+
+// Releases a buffer returned by this library. Passing an empty buffer is a
+// no-op.
+//
+// # Safety
+//
+// `buffer` must have been returned by this library and not freed yet.
+void abe4_buffer_free(struct Abe4Buffer buffer);
+
+// # Description
+//
+// Hybrid CP-ABE encryption, see `encrypt_hybrid`.
+//
+// # Arguments
+//
+// * `mpk`: Master public key handle.
+// * `policy`: Policy handle.
+// * `plaintext`, `plaintext_len`: Plaintext to encrypt.
+// * `aad`, `aad_len`: AAD to bind to the symmetric encryption. May be empty.
+// * `abe_ct`: On success, set to the compressed CP-ABE ciphertext.
+// * `sym_ct`: On success, set to the symmetric ciphertext.
+//
+// Both output buffers must be released with `abe4_buffer_free`.
+//
+// # Safety
+//
+// Handles must be live, byte pointers must point to at least as many
+// readable bytes as their length, and output pointers to writable memory.
+enum Abe4Status abe4_encrypt_hybrid(const struct Abe4Mpk *mpk,
+                                    const struct Abe4Policy *policy,
+                                    const uint8_t *plaintext,
+                                    size_t plaintext_len,
+                                    const uint8_t *aad,
+                                    size_t aad_len,
+                                    struct Abe4Buffer *abe_ct,
+                                    struct Abe4Buffer *sym_ct);
+
+// # Description
+//
+// Hybrid CP-ABE decryption, see `decrypt_hybrid`.
+//
+// # Arguments
+//
+// * `usk`: User secret key handle.
+// * `gid`: NUL-terminated global identifier of the user.
+// * `policy`: Policy handle.
+// * `abe_ct`, `abe_ct_len`: Compressed CP-ABE ciphertext.
+// * `sym_ct`, `sym_ct_len`: Symmetric ciphertext.
+// * `aad`, `aad_len`: AAD bound at encryption time. May be empty.
+// * `plaintext`: On success, set to the plaintext. Must be released with
+//   `abe4_buffer_free`.
+//
+// # Safety
+//
+// Handles must be live, `gid` must be NUL-terminated, byte pointers must
+// point to at least as many readable bytes as their length, and `plaintext`
+// to writable memory.
+enum Abe4Status abe4_decrypt_hybrid(const struct Abe4Usk *usk,
+                                    const char *gid,
+                                    const struct Abe4Policy *policy,
+                                    const uint8_t *abe_ct,
+                                    size_t abe_ct_len,
+                                    const uint8_t *sym_ct,
+                                    size_t sym_ct_len,
+                                    const uint8_t *aad,
+                                    size_t aad_len,
+                                    struct Abe4Buffer *plaintext);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ACCLESS_ABE4_CAPI_H */
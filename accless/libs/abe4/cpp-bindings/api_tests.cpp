@@ -227,6 +227,56 @@ TEST_F(Abe4ApiTest, HybridRoundTripOk) {
     assert_hybrid_round_trip(user_attrs, policy, plaintext, aad);
 }
 
+TEST_F(Abe4ApiTest, HybridHandlesRoundTrip) {
+    std::vector<accless::abe4::UserAttribute> user_attrs = {{"A", "a", "0"}};
+    std::string policy_str = "A.a:0";
+    std::string plaintext = "hybrid plaintext payload";
+    std::vector<uint8_t> plaintext_bytes(plaintext.begin(), plaintext.end());
+    std::vector<uint8_t> aad_bytes = {1, 2, 3};
+
+    auto auths = gather_authorities(user_attrs, policy_str);
+    accless::abe4::SetupOutput setup_output = accless::abe4::setup(auths);
+    std::string gid = "test_gid";
+    std::string usk_b64 =
+        accless::abe4::keygen(gid, setup_output.msk, user_attrs);
+
+    auto mpk = accless::abe4::Mpk::fromBase64(setup_output.mpk);
+    auto usk = accless::abe4::Usk::fromBase64(usk_b64);
+    auto policy = accless::abe4::Policy::parse(policy_str);
+
+    // Handles are re-used across calls.
+    for (int i = 0; i < 2; i++) {
+        auto hybrid_ct = accless::abe4::hybrid::encrypt(
+            mpk, policy, plaintext_bytes, aad_bytes);
+        auto decrypted = accless::abe4::hybrid::decrypt(
+            usk, gid, policy, hybrid_ct.abe_ciphertext,
+            hybrid_ct.sym_ciphertext, aad_bytes);
+        ASSERT_TRUE(decrypted.has_value());
+        EXPECT_EQ(plaintext_bytes, *decrypted);
+
+        // Handle-based and string-based APIs are interchangeable.
+        auto decrypted_str = accless::abe4::hybrid::decrypt(
+            usk_b64, gid, policy_str, hybrid_ct.abe_ciphertext,
+            hybrid_ct.sym_ciphertext, aad_bytes);
+        ASSERT_TRUE(decrypted_str.has_value());
+        EXPECT_EQ(plaintext_bytes, *decrypted_str);
+    }
+}
+
+TEST_F(Abe4ApiTest, HybridHandlesReportErrors) {
+    try {
+        accless::abe4::Policy::parse("A.a:0 &");
+        FAIL() << "expected accless::abe4::Error";
+    } catch (const accless::abe4::Error &e) {
+        EXPECT_EQ(ABE4_STATUS_INVALID_POLICY, e.status());
+        EXPECT_NE(std::string(e.what()).find("abe4_policy_parse"),
+                  std::string::npos);
+    }
+
+    EXPECT_THROW(accless::abe4::Mpk::fromBase64("AAAA"),
+                 accless::abe4::Error);
+}
+
 TEST_F(Abe4ApiTest, HybridDecryptFailsForUnauthorizedUser) {
     std::vector<accless::abe4::UserAttribute> user_attrs = {};
     std::string policy = "A.a:0";
@@ -282,3 +332,86 @@ TEST_F(Abe4ApiTest, HybridRejectsModifiedAad) {
         hybrid_ct.sym_ciphertext, wrong_aad_bytes);
     ASSERT_FALSE(tampered.has_value());
 }
+
+TEST_F(Abe4ApiTest, HybridContainerRoundTripOk) {
+    std::vector<accless::abe4::UserAttribute> user_attrs = {
+        {"A", "a", "0"},
+        {"B", "b", "1"},
+    };
+    std::string policy = "A.a:0 & !B.b:0";
+    std::string plaintext = "hybrid plaintext payload";
+    std::string aad = "hybrid aad data";
+
+    auto auths = gather_authorities(user_attrs, policy);
+    accless::abe4::SetupOutput setup_output = accless::abe4::setup(auths);
+    std::string gid = "test_gid";
+    std::string usk_b64 =
+        accless::abe4::keygen(gid, setup_output.msk, user_attrs);
+
+    std::vector<uint8_t> plaintext_bytes(plaintext.begin(), plaintext.end());
+    std::vector<uint8_t> aad_bytes(aad.begin(), aad.end());
+    std::string container = accless::abe4::hybrid::encryptContainer(
+        setup_output.mpk, policy, plaintext_bytes, aad_bytes,
+        accless::abe4::hybrid::CipherSuite::ChaCha20Poly1305);
+
+    auto info = accless::abe4::hybrid::containerInfo(container);
+    ASSERT_TRUE(info.has_value());
+    EXPECT_EQ(info->version, 4);
+    EXPECT_EQ(info->suite,
+              accless::abe4::hybrid::CipherSuite::ChaCha20Poly1305);
+    EXPECT_EQ(info->policy, "(A.a:0 & !B.b:0)");
+    EXPECT_EQ(info->authorities, std::vector<std::string>({"A", "B"}));
+
+    auto decrypted =
+        accless::abe4::hybrid::decryptContainer(usk_b64, gid, container);
+    ASSERT_TRUE(decrypted.has_value());
+    std::string decrypted_str(decrypted->begin(), decrypted->end());
+    EXPECT_EQ(plaintext, decrypted_str);
+
+    ASSERT_FALSE(accless::abe4::hybrid::containerInfo("bm90IGEgY29udGFpbmVy")
+                     .has_value());
+}
+
+TEST_F(Abe4ApiTest, HybridReencapsulateOk) {
+    std::vector<accless::abe4::UserAttribute> holder_attrs = {{"A", "a", "0"}};
+    std::vector<accless::abe4::UserAttribute> user_attrs = {{"A", "a", "1"}};
+    std::string policy = "A.a:0";
+    std::string new_policy = "A.a:0 | A.a:1";
+    std::string plaintext = "hybrid plaintext payload";
+    std::string aad = "hybrid aad data";
+
+    auto auths = gather_authorities(user_attrs, new_policy);
+    accless::abe4::SetupOutput setup_output = accless::abe4::setup(auths);
+    std::string gid = "test_gid";
+    std::string holder_gid = "holder_gid";
+    std::string usk_b64 =
+        accless::abe4::keygen(gid, setup_output.msk, user_attrs);
+    std::string holder_usk_b64 =
+        accless::abe4::keygen(holder_gid, setup_output.msk, holder_attrs);
+
+    std::vector<uint8_t> plaintext_bytes(plaintext.begin(), plaintext.end());
+    std::vector<uint8_t> aad_bytes(aad.begin(), aad.end());
+    auto hybrid_ct = accless::abe4::hybrid::encryptWrapped(
+        setup_output.mpk, policy, plaintext_bytes, aad_bytes);
+    ASSERT_FALSE(accless::abe4::hybrid::decrypt(
+                     usk_b64, gid, policy, hybrid_ct.abe_ciphertext,
+                     hybrid_ct.sym_ciphertext, aad_bytes)
+                     .has_value());
+
+    auto new_ct = accless::abe4::hybrid::reencapsulate(
+        setup_output.mpk, holder_usk_b64, holder_gid, policy,
+        hybrid_ct.abe_ciphertext, hybrid_ct.sym_ciphertext, new_policy,
+        aad_bytes);
+    auto decrypted = accless::abe4::hybrid::decrypt(
+        usk_b64, gid, new_policy, new_ct.abe_ciphertext, new_ct.sym_ciphertext,
+        aad_bytes);
+    ASSERT_TRUE(decrypted.has_value());
+    EXPECT_EQ(plaintext_bytes, *decrypted);
+
+    // Only a key that satisfies the current policy can re-encapsulate it.
+    EXPECT_THROW(accless::abe4::hybrid::reencapsulate(
+                     setup_output.mpk, usk_b64, gid, policy,
+                     hybrid_ct.abe_ciphertext, hybrid_ct.sym_ciphertext,
+                     new_policy, aad_bytes),
+                 std::runtime_error);
+}
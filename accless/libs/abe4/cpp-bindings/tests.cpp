@@ -166,3 +166,86 @@ TEST(Abe4Test, EndToEndSingleAuthorityPartial) {
     ASSERT_TRUE(decrypted_gt.has_value());
     EXPECT_EQ(decrypted_gt.value(), encrypt_output.gt);
 }
+
+TEST(Abe4Test, VerifyPartialUsk) {
+    std::string auth_id = "TEST_AUTH_ID";
+    std::string gid = "test_gid";
+    std::vector<accless::abe4::UserAttribute> user_attrs = {
+        {auth_id, "wf", "foo"}, {auth_id, "node", "bar"}};
+
+    accless::abe4::SetupOutput partial_setup_output =
+        accless::abe4::setupPartial(auth_id);
+    std::string partial_usk_b64 =
+        accless::abe4::keygenPartial(gid, partial_setup_output.msk, user_attrs);
+
+    auto verification = accless::abe4::verifyPartialUsk(
+        gid, partial_setup_output.mpk, partial_usk_b64, user_attrs);
+    EXPECT_TRUE(verification.valid);
+    EXPECT_TRUE(verification.error.empty());
+
+    std::string mpk =
+        accless::abe4::packFullKey({auth_id}, {partial_setup_output.mpk});
+    std::string usk = accless::abe4::packFullKey({auth_id}, {partial_usk_b64});
+    EXPECT_TRUE(accless::abe4::verifyUsk(gid, mpk, usk, user_attrs).valid);
+
+    // The key was issued for another GID, or for other attributes.
+    verification = accless::abe4::verifyPartialUsk(
+        "other_gid", partial_setup_output.mpk, partial_usk_b64, user_attrs);
+    EXPECT_FALSE(verification.valid);
+    EXPECT_FALSE(verification.error.empty());
+    EXPECT_FALSE(accless::abe4::verifyUsk(gid, mpk, usk,
+                                          {{auth_id, "wf", "baz"}})
+                     .valid);
+}
+
+TEST(Abe4Test, DelegateUsk) {
+    std::string gid = "test_gid";
+    std::vector<std::string> auths = {"A", "B"};
+    std::vector<accless::abe4::UserAttribute> user_attrs = {
+        {"A", "wf", "foo"}, {"B", "node", "bar"}};
+
+    accless::abe4::SetupOutput setup_output = accless::abe4::setup(auths);
+    std::string usk = accless::abe4::keygen(gid, setup_output.msk, user_attrs);
+    std::string delegated = accless::abe4::delegateUsk(
+        gid, setup_output.mpk, usk, {{"A", "wf", "foo"}});
+    EXPECT_TRUE(accless::abe4::verifyUsk(gid, setup_output.mpk, delegated,
+                                         {{"A", "wf", "foo"}})
+                    .valid);
+
+    std::string policy = "A.wf:foo";
+    accless::abe4::EncryptOutput encrypt_output =
+        accless::abe4::encrypt(setup_output.mpk, policy);
+    std::optional<std::string> decrypted_gt = accless::abe4::decrypt(
+        delegated, gid, policy, encrypt_output.ciphertext);
+    ASSERT_TRUE(decrypted_gt.has_value());
+    EXPECT_EQ(decrypted_gt.value(), encrypt_output.gt);
+
+    // The delegated key no longer holds B's attribute.
+    policy = "A.wf:foo & B.node:bar";
+    encrypt_output = accless::abe4::encrypt(setup_output.mpk, policy);
+    EXPECT_FALSE(accless::abe4::decrypt(delegated, gid, policy,
+                                        encrypt_output.ciphertext)
+                     .has_value());
+
+    // Keys cannot be widened.
+    EXPECT_THROW(accless::abe4::delegateUsk(gid, setup_output.mpk, delegated,
+                                            {{"B", "node", "bar"}}),
+                 std::runtime_error);
+}
+
+TEST(Abe4Test, EvaluatePolicy) {
+    std::string policy = "A.a:0 & (B.b:0 | !C.c:0)";
+
+    auto evaluation = accless::abe4::evaluatePolicy(
+        policy, {{"A", "a", "0"}, {"C", "c", "1"}});
+    ASSERT_TRUE(evaluation.satisfied);
+    EXPECT_EQ(evaluation.literals,
+              std::vector<std::string>({"A.a:0", "!C.c:0"}));
+    EXPECT_TRUE(evaluation.missing.empty());
+
+    evaluation = accless::abe4::evaluatePolicy(policy, {{"C", "c", "0"}});
+    ASSERT_FALSE(evaluation.satisfied);
+    EXPECT_TRUE(evaluation.literals.empty());
+    EXPECT_EQ(evaluation.missing,
+              std::vector<std::string>({"A.a:0", "(B.b:0 | !C.c:0)"}));
+}
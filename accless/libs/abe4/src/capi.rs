@@ -0,0 +1,647 @@
+//! Handle-based C API.
+//!
+//! Unlike the string-based FFI functions in `lib.rs`, which take base64 and
+//! JSON strings and deserialize their inputs on every call, this API hands out
+//! opaque handles to already-parsed keys and policies. Callers parse a key or
+//! a policy once, and re-use the handle across many encrypt/decrypt calls.
+//!
+//! Every function returns an `Abe4Status`. On failure, a human-readable
+//! message describing the error is available from the calling thread via
+//! `abe4_last_error_message`, until the next failing call on that thread.
+//!
+//! The C header for this API (`cpp-bindings/abe4_capi.h`) is generated with
+//! `cbindgen`, see `cbindgen.toml`.
+
+use crate::{
+    error::Abe4Error,
+    hybrid::{decrypt_hybrid, encrypt_hybrid},
+    policy::Policy,
+    scheme::types::{Ciphertext, MPK, USK},
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    panic::{AssertUnwindSafe, catch_unwind},
+    ptr, slice,
+};
+
+/// Status code returned by every function in the handle-based C API.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Abe4Status {
+    /// The call succeeded.
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// A string argument was not valid UTF-8.
+    InvalidUtf8 = 2,
+    /// A key could not be deserialized, or does not verify.
+    InvalidKey = 3,
+    /// A policy could not be parsed.
+    InvalidPolicy = 4,
+    /// A ciphertext could not be deserialized.
+    InvalidCiphertext = 5,
+    /// Encryption failed.
+    EncryptionFailed = 6,
+    /// Decryption failed, e.g. the ciphertext has been tampered with or was
+    /// encrypted with a different AAD.
+    DecryptionFailed = 7,
+    /// The library panicked. This is a bug.
+    Panic = 8,
+    /// The user attributes in the key do not satisfy the policy.
+    UnsatisfiedPolicy = 9,
+    /// No key was given for an authority that the policy references.
+    MissingAuthorityKey = 10,
+    /// The user secret key was issued for a different global identifier.
+    GidMismatch = 11,
+}
+
+/// Opaque handle to a master public key.
+pub struct Abe4Mpk(MPK);
+
+/// Opaque handle to a user secret key.
+pub struct Abe4Usk(USK);
+
+/// Opaque handle to a parsed policy.
+pub struct Abe4Policy(Policy);
+
+/// Byte buffer allocated by this library. Must be released with
+/// `abe4_buffer_free`.
+#[repr(C)]
+pub struct Abe4Buffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl Abe4Buffer {
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        let mut bytes = bytes.into_boxed_slice();
+        let buffer = Abe4Buffer {
+            data: bytes.as_mut_ptr(),
+            len: bytes.len(),
+        };
+        std::mem::forget(bytes);
+        buffer
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+struct CApiError {
+    status: Abe4Status,
+    message: String,
+}
+
+impl CApiError {
+    fn new(status: Abe4Status, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    /// Maps an `Abe4Error` to the matching status code, falling back to
+    /// `fallback` for errors that have no dedicated code.
+    fn from_abe4(e: Abe4Error, fallback: Abe4Status) -> Self {
+        let status = match e {
+            Abe4Error::PolicySyntax(_) => Abe4Status::InvalidPolicy,
+            Abe4Error::UnsatisfiedPolicy { .. } => Abe4Status::UnsatisfiedPolicy,
+            Abe4Error::MissingAuthorityKey(_) => Abe4Status::MissingAuthorityKey,
+            Abe4Error::GidMismatch { .. } => Abe4Status::GidMismatch,
+            Abe4Error::InvalidKey(_) => Abe4Status::InvalidKey,
+            Abe4Error::InvalidInput(_) | Abe4Error::Serialization(_) | Abe4Error::Aead(_) => {
+                fallback
+            }
+        };
+        Self::new(status, e.to_string())
+    }
+}
+
+type CApiResult<T> = Result<T, CApiError>;
+
+fn set_last_error(message: &str) {
+    // Interior NUL bytes can not be represented in a C string.
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Runs `f`, turning errors and panics into a status code and a thread-local
+/// error message.
+fn run(f: impl FnOnce() -> CApiResult<()>) -> Abe4Status {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => Abe4Status::Ok,
+        Ok(Err(e)) => {
+            set_last_error(&e.message);
+            e.status
+        }
+        Err(_) => {
+            set_last_error("abe4 panicked");
+            Abe4Status::Panic
+        }
+    }
+}
+
+fn non_null<T>(p: *const T, name: &str) -> CApiResult<()> {
+    if p.is_null() {
+        return Err(CApiError::new(
+            Abe4Status::NullPointer,
+            format!("{name} must not be null"),
+        ));
+    }
+    Ok(())
+}
+
+/// # Safety
+///
+/// If non-null, `p` must point to a NUL-terminated string that outlives the
+/// returned reference.
+unsafe fn str_arg<'a>(p: *const c_char, name: &str) -> CApiResult<&'a str> {
+    non_null(p, name)?;
+    unsafe { CStr::from_ptr(p) }.to_str().map_err(|e| {
+        CApiError::new(
+            Abe4Status::InvalidUtf8,
+            format!("{name} is not valid UTF-8: {e}"),
+        )
+    })
+}
+
+/// # Safety
+///
+/// If `len` is non-zero, `p` must point to `len` readable bytes that outlive
+/// the returned slice.
+unsafe fn bytes_arg<'a>(p: *const u8, len: usize, name: &str) -> CApiResult<&'a [u8]> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    non_null(p, name)?;
+    Ok(unsafe { slice::from_raw_parts(p, len) })
+}
+
+/// # Safety
+///
+/// If non-null, `p` must point to a live `T`.
+unsafe fn ref_arg<'a, T>(p: *const T, name: &str) -> CApiResult<&'a T> {
+    non_null(p, name)?;
+    Ok(unsafe { &*p })
+}
+
+/// # Description
+///
+/// Returns the message of the last error raised on the calling thread, or
+/// null if no call has failed yet.
+///
+/// # Returns
+///
+/// A NUL-terminated string owned by the library. It is valid until the next
+/// call into the library from the same thread, and must not be freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn abe4_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| match last.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// # Description
+///
+/// Deserializes a master public key from its compressed byte encoding.
+///
+/// # Arguments
+///
+/// * `bytes`: Pointer to the serialized MPK.
+/// * `len`: Length of `bytes`.
+/// * `out`: On success, set to a new handle that must be released with
+///   `abe4_mpk_free`.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes, and `out` to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abe4_mpk_from_bytes(
+    bytes: *const u8,
+    len: usize,
+    out: *mut *mut Abe4Mpk,
+) -> Abe4Status {
+    run(|| {
+        non_null(out, "out")?;
+        let bytes = unsafe { bytes_arg(bytes, len, "bytes") }?;
+        let mpk = MPK::deserialize_compressed(bytes).map_err(|e| {
+            CApiError::new(
+                Abe4Status::InvalidKey,
+                format!("failed to deserialize MPK: {e}"),
+            )
+        })?;
+        unsafe { *out = Box::into_raw(Box::new(Abe4Mpk(mpk))) };
+        Ok(())
+    })
+}
+
+/// Releases a master public key handle. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `mpk` must be null or a handle returned by `abe4_mpk_from_bytes` that has
+/// not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abe4_mpk_free(mpk: *mut Abe4Mpk) {
+    if !mpk.is_null() {
+        drop(unsafe { Box::from_raw(mpk) });
+    }
+}
+
+/// # Description
+///
+/// Deserializes a user secret key from its compressed byte encoding.
+///
+/// # Arguments
+///
+/// * `bytes`: Pointer to the serialized USK.
+/// * `len`: Length of `bytes`.
+/// * `out`: On success, set to a new handle that must be released with
+///   `abe4_usk_free`.
+///
+/// # Safety
+///
+/// `bytes` must point to `len` readable bytes, and `out` to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abe4_usk_from_bytes(
+    bytes: *const u8,
+    len: usize,
+    out: *mut *mut Abe4Usk,
+) -> Abe4Status {
+    run(|| {
+        non_null(out, "out")?;
+        let bytes = unsafe { bytes_arg(bytes, len, "bytes") }?;
+        let usk = USK::deserialize_compressed(bytes).map_err(|e| {
+            CApiError::new(
+                Abe4Status::InvalidKey,
+                format!("failed to deserialize USK: {e}"),
+            )
+        })?;
+        unsafe { *out = Box::into_raw(Box::new(Abe4Usk(usk))) };
+        Ok(())
+    })
+}
+
+/// Releases a user secret key handle, zeroizing the key. Passing null is a
+/// no-op.
+///
+/// # Safety
+///
+/// `usk` must be null or a handle returned by `abe4_usk_from_bytes` that has
+/// not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abe4_usk_free(usk: *mut Abe4Usk) {
+    if !usk.is_null() {
+        drop(unsafe { Box::from_raw(usk) });
+    }
+}
+
+/// # Description
+///
+/// Parses a policy string.
+///
+/// # Arguments
+///
+/// * `policy`: NUL-terminated policy string.
+/// * `out`: On success, set to a new handle that must be released with
+///   `abe4_policy_free`.
+///
+/// # Safety
+///
+/// `policy` must be a NUL-terminated string, and `out` must point to writable
+/// memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abe4_policy_parse(
+    policy: *const c_char,
+    out: *mut *mut Abe4Policy,
+) -> Abe4Status {
+    run(|| {
+        non_null(out, "out")?;
+        let policy_str = unsafe { str_arg(policy, "policy") }?;
+        let policy = Policy::parse(policy_str).map_err(|e| {
+            CApiError::new(
+                Abe4Status::InvalidPolicy,
+                format!("failed to parse policy: {e}"),
+            )
+        })?;
+        unsafe { *out = Box::into_raw(Box::new(Abe4Policy(policy))) };
+        Ok(())
+    })
+}
+
+/// Releases a policy handle. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `policy` must be null or a handle returned by `abe4_policy_parse` that has
+/// not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abe4_policy_free(policy: *mut Abe4Policy) {
+    if !policy.is_null() {
+        drop(unsafe { Box::from_raw(policy) });
+    }
+}
+
+/// Releases a buffer returned by this library. Passing an empty buffer is a
+/// no-op.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by this library and not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abe4_buffer_free(buffer: Abe4Buffer) {
+    if !buffer.data.is_null() {
+        drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(buffer.data, buffer.len)) });
+    }
+}
+
+/// # Description
+///
+/// Hybrid CP-ABE encryption, see `encrypt_hybrid`.
+///
+/// # Arguments
+///
+/// * `mpk`: Master public key handle.
+/// * `policy`: Policy handle.
+/// * `plaintext`, `plaintext_len`: Plaintext to encrypt.
+/// * `aad`, `aad_len`: AAD to bind to the symmetric encryption. May be empty.
+/// * `abe_ct`: On success, set to the compressed CP-ABE ciphertext.
+/// * `sym_ct`: On success, set to the symmetric ciphertext.
+///
+/// Both output buffers must be released with `abe4_buffer_free`.
+///
+/// # Safety
+///
+/// Handles must be live, byte pointers must point to at least as many
+/// readable bytes as their length, and output pointers to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abe4_encrypt_hybrid(
+    mpk: *const Abe4Mpk,
+    policy: *const Abe4Policy,
+    plaintext: *const u8,
+    plaintext_len: usize,
+    aad: *const u8,
+    aad_len: usize,
+    abe_ct: *mut Abe4Buffer,
+    sym_ct: *mut Abe4Buffer,
+) -> Abe4Status {
+    run(|| {
+        non_null(abe_ct, "abe_ct")?;
+        non_null(sym_ct, "sym_ct")?;
+        let mpk = unsafe { ref_arg(mpk, "mpk") }?;
+        let policy = unsafe { ref_arg(policy, "policy") }?;
+        let plaintext = unsafe { bytes_arg(plaintext, plaintext_len, "plaintext") }?;
+        let aad = unsafe { bytes_arg(aad, aad_len, "aad") }?;
+
+        let mut rng = ark_std::rand::thread_rng();
+        let ct = encrypt_hybrid(&mut rng, &mpk.0, &policy.0, plaintext, aad)
+            .map_err(|e| CApiError::from_abe4(e, Abe4Status::EncryptionFailed))?;
+        let mut abe_ct_bytes = Vec::new();
+        ct.abe_ct
+            .serialize_compressed(&mut abe_ct_bytes)
+            .map_err(|e| {
+                CApiError::new(
+                    Abe4Status::EncryptionFailed,
+                    format!("failed to serialize ABE ciphertext: {e}"),
+                )
+            })?;
+
+        unsafe {
+            *abe_ct = Abe4Buffer::from_vec(abe_ct_bytes);
+            *sym_ct = Abe4Buffer::from_vec(ct.sym_ct);
+        }
+        Ok(())
+    })
+}
+
+/// # Description
+///
+/// Hybrid CP-ABE decryption, see `decrypt_hybrid`.
+///
+/// # Arguments
+///
+/// * `usk`: User secret key handle.
+/// * `gid`: NUL-terminated global identifier of the user.
+/// * `policy`: Policy handle.
+/// * `abe_ct`, `abe_ct_len`: Compressed CP-ABE ciphertext.
+/// * `sym_ct`, `sym_ct_len`: Symmetric ciphertext.
+/// * `aad`, `aad_len`: AAD bound at encryption time. May be empty.
+/// * `plaintext`: On success, set to the plaintext. Must be released with
+///   `abe4_buffer_free`.
+///
+/// # Safety
+///
+/// Handles must be live, `gid` must be NUL-terminated, byte pointers must
+/// point to at least as many readable bytes as their length, and `plaintext`
+/// to writable memory.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn abe4_decrypt_hybrid(
+    usk: *const Abe4Usk,
+    gid: *const c_char,
+    policy: *const Abe4Policy,
+    abe_ct: *const u8,
+    abe_ct_len: usize,
+    sym_ct: *const u8,
+    sym_ct_len: usize,
+    aad: *const u8,
+    aad_len: usize,
+    plaintext: *mut Abe4Buffer,
+) -> Abe4Status {
+    run(|| {
+        non_null(plaintext, "plaintext")?;
+        let usk = unsafe { ref_arg(usk, "usk") }?;
+        let gid = unsafe { str_arg(gid, "gid") }?;
+        let policy = unsafe { ref_arg(policy, "policy") }?;
+        let abe_ct = unsafe { bytes_arg(abe_ct, abe_ct_len, "abe_ct") }?;
+        let sym_ct = unsafe { bytes_arg(sym_ct, sym_ct_len, "sym_ct") }?;
+        let aad = unsafe { bytes_arg(aad, aad_len, "aad") }?;
+
+        let abe_ct = Ciphertext::deserialize_compressed(abe_ct).map_err(|e| {
+            CApiError::new(
+                Abe4Status::InvalidCiphertext,
+                format!("failed to deserialize ABE ciphertext: {e}"),
+            )
+        })?;
+        let pt = decrypt_hybrid(&usk.0, gid, &policy.0, &abe_ct, sym_ct, aad)
+            .map_err(|e| CApiError::from_abe4(e, Abe4Status::DecryptionFailed))?;
+
+        unsafe { *plaintext = Abe4Buffer::from_vec(pt) };
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iota::Iota, keygen, policy::UserAttribute, setup};
+    use ark_std::rand::{SeedableRng, rngs::StdRng};
+
+    const EMPTY: Abe4Buffer = Abe4Buffer {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+
+    fn serialize<T: CanonicalSerialize>(t: &T) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        t.serialize_compressed(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn last_error() -> String {
+        let message = abe4_last_error_message();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_capi_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let gid = "user1";
+        let (msk, mpk) = setup(&mut rng, &vec!["A"]);
+        let user_attrs = vec![UserAttribute::new("A", "a", "0")];
+        let iota = Iota::new(&user_attrs);
+        let usk = keygen(&mut rng, gid, &msk, &user_attrs, &iota).unwrap();
+        let mpk_bytes = serialize(&mpk);
+        let usk_bytes = serialize(&usk);
+        let policy_str = CString::new("A.a:0").unwrap();
+        let gid_str = CString::new(gid).unwrap();
+        let plaintext = b"hello handles";
+        let aad = b"aad";
+
+        unsafe {
+            let mut mpk = ptr::null_mut();
+            let mut usk = ptr::null_mut();
+            let mut policy = ptr::null_mut();
+            assert_eq!(
+                abe4_mpk_from_bytes(mpk_bytes.as_ptr(), mpk_bytes.len(), &mut mpk),
+                Abe4Status::Ok
+            );
+            assert_eq!(
+                abe4_usk_from_bytes(usk_bytes.as_ptr(), usk_bytes.len(), &mut usk),
+                Abe4Status::Ok
+            );
+            assert_eq!(
+                abe4_policy_parse(policy_str.as_ptr(), &mut policy),
+                Abe4Status::Ok
+            );
+
+            let (mut abe_ct, mut sym_ct) = (EMPTY, EMPTY);
+            assert_eq!(
+                abe4_encrypt_hybrid(
+                    mpk,
+                    policy,
+                    plaintext.as_ptr(),
+                    plaintext.len(),
+                    aad.as_ptr(),
+                    aad.len(),
+                    &mut abe_ct,
+                    &mut sym_ct,
+                ),
+                Abe4Status::Ok
+            );
+
+            // The same handles can be used for many decryptions.
+            for _ in 0..2 {
+                let mut pt = EMPTY;
+                assert_eq!(
+                    abe4_decrypt_hybrid(
+                        usk,
+                        gid_str.as_ptr(),
+                        policy,
+                        abe_ct.data,
+                        abe_ct.len,
+                        sym_ct.data,
+                        sym_ct.len,
+                        aad.as_ptr(),
+                        aad.len(),
+                        &mut pt,
+                    ),
+                    Abe4Status::Ok
+                );
+                assert_eq!(slice::from_raw_parts(pt.data, pt.len), plaintext);
+                abe4_buffer_free(pt);
+            }
+
+            // Decrypting with the wrong AAD fails, and sets the error message.
+            let mut pt = EMPTY;
+            assert_eq!(
+                abe4_decrypt_hybrid(
+                    usk,
+                    gid_str.as_ptr(),
+                    policy,
+                    abe_ct.data,
+                    abe_ct.len,
+                    sym_ct.data,
+                    sym_ct.len,
+                    ptr::null(),
+                    0,
+                    &mut pt,
+                ),
+                Abe4Status::DecryptionFailed
+            );
+            assert!(pt.data.is_null());
+            assert!(!last_error().is_empty());
+
+            // Decrypting with another user's GID reports the mismatch.
+            let other_gid = CString::new("user2").unwrap();
+            assert_eq!(
+                abe4_decrypt_hybrid(
+                    usk,
+                    other_gid.as_ptr(),
+                    policy,
+                    abe_ct.data,
+                    abe_ct.len,
+                    sym_ct.data,
+                    sym_ct.len,
+                    aad.as_ptr(),
+                    aad.len(),
+                    &mut pt,
+                ),
+                Abe4Status::GidMismatch
+            );
+            assert!(pt.data.is_null());
+
+            abe4_buffer_free(abe_ct);
+            abe4_buffer_free(sym_ct);
+            abe4_policy_free(policy);
+            abe4_usk_free(usk);
+            abe4_mpk_free(mpk);
+        }
+    }
+
+    #[test]
+    fn test_capi_errors() {
+        unsafe {
+            let mut mpk = ptr::null_mut();
+            assert_eq!(
+                abe4_mpk_from_bytes(ptr::null(), 4, &mut mpk),
+                Abe4Status::NullPointer
+            );
+            assert_eq!(last_error(), "bytes must not be null");
+
+            let garbage = [1u8, 2, 3];
+            assert_eq!(
+                abe4_mpk_from_bytes(garbage.as_ptr(), garbage.len(), &mut mpk),
+                Abe4Status::InvalidKey
+            );
+            assert!(mpk.is_null());
+
+            let mut policy = ptr::null_mut();
+            let bad_policy = CString::new("A.a:0 &").unwrap();
+            assert_eq!(
+                abe4_policy_parse(bad_policy.as_ptr(), &mut policy),
+                Abe4Status::InvalidPolicy
+            );
+            assert!(policy.is_null());
+            assert!(last_error().starts_with("failed to parse policy"));
+        }
+    }
+}
@@ -1,12 +1,115 @@
 use crate::hashing::swift_ec::SwiftConfig;
-use ark_bls12_381::Bls12_381;
-// Use the regular group assignment: G is G and H is H
+// Use the regular group assignment of BLS12-381 by default: G is G and H is H
 pub use ark_bls12_381::{
     Fq, Fq12 as Gt, Fr as ScalarField, G1Affine as GAffine, G1Projective as G, G2Projective as H,
     g1::Config as G1Config,
 };
-use ark_ec::pairing::{Pairing, PairingOutput};
-use ark_ff::MontFp;
+use ark_ec::{CurveGroup, hashing::HashToCurve, pairing::Pairing};
+use ark_ff::{Field, MontFp, PrimeField};
+use ark_std::{fmt::Debug, marker::PhantomData};
+
+/// # Description
+///
+/// Pairing backend used by the scheme.
+///
+/// The scheme hashes identifiers and labels into the group `G`, and pairs
+/// elements of `G` with elements of `H`. Which source group of the pairing
+/// plays the role of `G` (and `H`) is up to each implementation, see
+/// `Standard` and `Flipped`.
+///
+/// Keys and ciphertexts are parameterised by a `Curve`, and default to
+/// `DefaultCurve`.
+pub trait Curve: 'static + Clone + Copy + Debug + PartialEq + Eq + Send + Sync {
+    /// Scalar field of both source groups.
+    type ScalarField: PrimeField;
+    /// Group we hash into.
+    type G: CurveGroup<ScalarField = Self::ScalarField>;
+    /// The other source group.
+    type H: CurveGroup<ScalarField = Self::ScalarField>;
+    /// Target group of the pairing, where the encapsulated keys live.
+    type Gt: Field;
+    /// Hasher into `G`, for a fixed domain. See `HashToGroup`.
+    type Hasher: HashToCurve<Self::G> + Send + Sync;
+
+    /// Computes the pairing of `p` and `q`.
+    fn pairing(p: Self::G, q: Self::H) -> Self::Gt;
+
+    /// Computes the product of the pairings of each `(p, q)` pair, sharing
+    /// the final exponentiation.
+    fn multi_pairing(p: Vec<Self::G>, q: Vec<Self::H>) -> Self::Gt;
+
+    /// Hashes `msg` to an element of `G`, using `domain` for domain separation.
+    fn hash_to_g(domain: &[u8], msg: &[u8]) -> <Self::G as CurveGroup>::Affine {
+        Self::Hasher::new(domain).unwrap().hash(msg).unwrap()
+    }
+}
+
+/// Pairing backend where `G` is the first source group of `E`, and `H` the
+/// second one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Standard<E>(PhantomData<fn() -> E>);
+
+/// Pairing backend where `G` is the second source group of `E`, and `H` the
+/// first one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Flipped<E>(PhantomData<fn() -> E>);
+
+/// BLS12-381 with the regular group assignment. This is the default curve.
+pub type Bls12_381 = Standard<ark_bls12_381::Bls12_381>;
+/// BLS12-381 with `G` and `H` swapped.
+pub type Bls12_381Flipped = Flipped<ark_bls12_381::Bls12_381>;
+/// BLS12-377 with the regular group assignment.
+pub type Bls12_377 = Standard<ark_bls12_377::Bls12_377>;
+
+/// Curve used when none is specified.
+pub type DefaultCurve = Bls12_381;
+
+impl<E> Curve for Standard<E>
+where
+    E: Pairing,
+    E::G1: HashToGroup,
+{
+    type ScalarField = E::ScalarField;
+    type G = E::G1;
+    type H = E::G2;
+    type Gt = E::TargetField;
+    type Hasher = <E::G1 as HashToGroup>::Hasher;
+
+    fn pairing(p: Self::G, q: Self::H) -> Self::Gt {
+        E::pairing(p, q).0
+    }
+
+    fn multi_pairing(p: Vec<Self::G>, q: Vec<Self::H>) -> Self::Gt {
+        E::multi_pairing(p, q).0
+    }
+}
+
+impl<E> Curve for Flipped<E>
+where
+    E: Pairing,
+    E::G2: HashToGroup,
+{
+    type ScalarField = E::ScalarField;
+    type G = E::G2;
+    type H = E::G1;
+    type Gt = E::TargetField;
+    type Hasher = <E::G2 as HashToGroup>::Hasher;
+
+    fn pairing(p: Self::G, q: Self::H) -> Self::Gt {
+        E::pairing(q, p).0
+    }
+
+    fn multi_pairing(p: Vec<Self::G>, q: Vec<Self::H>) -> Self::Gt {
+        E::multi_pairing(q, p).0
+    }
+}
+
+/// Groups we know how to hash into. See the `hashing` module.
+pub trait HashToGroup: CurveGroup {
+    /// Hasher into the group for a fixed domain. Building one is not free,
+    /// so callers that hash many messages should keep it around.
+    type Hasher: HashToCurve<Self> + Send + Sync;
+}
 
 impl SwiftConfig for G1Config {
     const SQRT_MINUS3: Fq = MontFp!(
@@ -14,25 +117,18 @@ impl SwiftConfig for G1Config {
     );
 }
 
-pub fn pairing(
-    p: impl Into<<Bls12_381 as Pairing>::G1Prepared>,
-    q: impl Into<<Bls12_381 as Pairing>::G2Prepared>,
-) -> PairingOutput<Bls12_381> {
-    Bls12_381::pairing(p, q)
+impl SwiftConfig for ark_bls12_381::g2::Config {
+    const SQRT_MINUS3: ark_bls12_381::Fq2 =
+        ark_bls12_381::Fq2::new(G1Config::SQRT_MINUS3, MontFp!("0"));
 }
 
-// Flip the groups, i.e. each G is actually H and each H is actually G
-// pub use ark_bls12_381::{
-//     g2::Config as G1Config, Fq12 as Gt, Fr as ScalarField, G2Affine as
-// GAffine,     G2Projective as G, G1Projective as H
-// };
-
-// use ark_bls12_381::Bls12_381;
-// use ark_ec::pairing::{Pairing, PairingOutput};
+impl SwiftConfig for ark_bls12_377::g1::Config {
+    const SQRT_MINUS3: ark_bls12_377::Fq = MontFp!(
+        "161899296529825438817116726281274954529690589441420998956274574525425071876602923759626918821891"
+    );
+}
 
-// pub fn pairing(
-//         p: impl Into<<Bls12_381 as Pairing>::G2Prepared>,
-//         q: impl Into<<Bls12_381 as Pairing>::G1Prepared>)
-//     -> PairingOutput<Bls12_381> {
-//     Bls12_381::pairing(q, p)
-// }
+impl SwiftConfig for ark_bls12_377::g2::Config {
+    const SQRT_MINUS3: ark_bls12_377::Fq2 =
+        ark_bls12_377::Fq2::new(ark_bls12_377::g1::Config::SQRT_MINUS3, MontFp!("0"));
+}
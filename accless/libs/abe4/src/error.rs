@@ -0,0 +1,107 @@
+use crate::policy::PolicyDiagnostic;
+use std::fmt;
+
+/// # Description
+///
+/// Error type returned by the public API of this crate.
+///
+/// Each variant corresponds to a class of failure that a caller may want to
+/// react to differently: a malformed policy is a bug in the caller, an
+/// unsatisfied policy is an expected outcome of access control, and an AEAD
+/// failure may indicate tampering.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Abe4Error {
+    /// The policy (or user attribute) is not well-formed. The diagnostic
+    /// points at the offending token, see `PolicyDiagnostic::render`.
+    PolicySyntax(Box<PolicyDiagnostic>),
+    /// The user's attributes do not satisfy the policy. Contains the
+    /// sub-expressions of the policy that are still missing.
+    UnsatisfiedPolicy { missing: Vec<String> },
+    /// No key was given for an authority that is needed for the operation.
+    MissingAuthorityKey(String),
+    /// The user secret key was generated for a different global identifier.
+    GidMismatch { expected: String, found: String },
+    /// The inputs are inconsistent with each other, e.g. an `Iota` built from
+    /// other attributes than the ones in the user secret key.
+    InvalidInput(String),
+    /// A key is not consistent with the public key of its authority, or with
+    /// the GID and attributes it claims to be issued for.
+    InvalidKey(String),
+    /// A key, ciphertext or container could not be (de)serialized.
+    Serialization(String),
+    /// The symmetric layer failed to encrypt or to authenticate a ciphertext.
+    Aead(String),
+}
+
+/// Result type returned by the public API of this crate.
+pub type Result<T, E = Abe4Error> = std::result::Result<T, E>;
+
+impl fmt::Display for Abe4Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Abe4Error::PolicySyntax(diagnostic) => {
+                write!(f, "policy syntax error: {diagnostic}")
+            }
+            Abe4Error::UnsatisfiedPolicy { missing } => write!(
+                f,
+                "user attributes do not satisfy the policy (missing={})",
+                missing.join(", ")
+            ),
+            Abe4Error::MissingAuthorityKey(auth) => {
+                write!(f, "no key given for authority '{auth}'")
+            }
+            Abe4Error::GidMismatch { expected, found } => write!(
+                f,
+                "user secret key was issued for gid '{found}', but gid '{expected}' was given"
+            ),
+            Abe4Error::InvalidInput(reason) => write!(f, "invalid input: {reason}"),
+            Abe4Error::InvalidKey(reason) => write!(f, "invalid key: {reason}"),
+            Abe4Error::Serialization(reason) => write!(f, "serialization error: {reason}"),
+            Abe4Error::Aead(reason) => write!(f, "AEAD error: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Abe4Error {}
+
+impl From<PolicyDiagnostic> for Abe4Error {
+    fn from(diagnostic: PolicyDiagnostic) -> Self {
+        Abe4Error::PolicySyntax(Box::new(diagnostic))
+    }
+}
+
+impl From<ark_serialize::SerializationError> for Abe4Error {
+    fn from(e: ark_serialize::SerializationError) -> Self {
+        Abe4Error::Serialization(e.to_string())
+    }
+}
+
+// I/O only happens when reading or writing serialized streams.
+impl From<std::io::Error> for Abe4Error {
+    fn from(e: std::io::Error) -> Self {
+        Abe4Error::Serialization(e.to_string())
+    }
+}
+
+#[test]
+fn test_error_display() {
+    assert_eq!(
+        Abe4Error::MissingAuthorityKey(String::from("A")).to_string(),
+        "no key given for authority 'A'"
+    );
+    assert_eq!(
+        Abe4Error::GidMismatch {
+            expected: String::from("alice"),
+            found: String::from("bob"),
+        }
+        .to_string(),
+        "user secret key was issued for gid 'bob', but gid 'alice' was given"
+    );
+    assert_eq!(
+        Abe4Error::UnsatisfiedPolicy {
+            missing: vec![String::from("A.a:0"), String::from("(B.b:0 | B.c:0)")],
+        }
+        .to_string(),
+        "user attributes do not satisfy the policy (missing=A.a:0, (B.b:0 | B.c:0))"
+    );
+}
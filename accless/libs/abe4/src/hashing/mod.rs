@@ -1,8 +1,9 @@
-use crate::curve::{G1Config, GAffine, ScalarField};
-use ark_ec::{hashing::HashToCurve, short_weierstrass::Projective};
+use crate::curve::{Curve, HashToGroup};
+use ark_ec::{CurveGroup, hashing::HashToCurve, short_weierstrass::Projective};
 use ark_ff::field_hashers::{DefaultFieldHasher, HashToField};
 use sha2::{Digest, Sha256};
-use swift_ec::SwiftECMap;
+use std::{collections::HashMap, sync::RwLock};
+use swift_ec::{SwiftConfig, SwiftECMap};
 use swift_hasher::SwiftMapToCurveBasedHasher;
 
 pub mod swift_ec;
@@ -15,7 +16,7 @@ const XATTR_DOMAIN: &str = "XAT";
 const HASH_SIGN_POS: &str = "POS";
 const HASH_SIGN_NEG: &str = "NEG";
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum HashSign {
     Pos,
     Neg,
@@ -27,39 +28,125 @@ fn sha256(data: impl AsRef<[u8]>) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
-pub fn hash_gid(gid: &str) -> GAffine {
-    let domain = GID_DOMAIN.as_bytes();
-    let g_mapper = SwiftMapToCurveBasedHasher::<
-        Projective<G1Config>,
+impl<P: SwiftConfig> HashToGroup for Projective<P> {
+    type Hasher = SwiftMapToCurveBasedHasher<
+        Projective<P>,
         DefaultFieldHasher<Sha256, DEFAULT_FIELD_HASHER_SEC_PARAM>,
-        SwiftECMap<G1Config>,
-    >::new(domain)
-    .unwrap();
-    g_mapper.hash(gid.as_bytes()).unwrap()
+        SwiftECMap<P>,
+    >;
 }
 
-pub fn hash_attr(attr: &str) -> ScalarField {
-    let domain = XATTR_DOMAIN.as_bytes();
-    let hasher = <DefaultFieldHasher<Sha256> as HashToField<ScalarField>>::new(domain);
-    hasher.hash_to_field(attr.as_bytes(), 1)[0]
+type Point<C> = <<C as Curve>::G as CurveGroup>::Affine;
+/// Authority, label, sign and index of a hashed label.
+type LblKey = (String, String, HashSign, u64);
+
+/// # Description
+///
+/// Hashes identifiers, labels and attributes into the groups of the scheme,
+/// re-using the hash-to-curve mappers across calls.
+///
+/// Label points only depend on `(auth, lbl, sign, i)`, and attribute scalars
+/// on the attribute, so both are memoised: hashing the same label twice only
+/// pays for the hash-to-curve work once. The memo tables are never evicted,
+/// and grow with the number of distinct labels and attributes seen.
+///
+/// The context is `Sync`, so it can be shared by the parallel parts of the
+/// scheme.
+pub struct HashingContext<C: Curve> {
+    gid_hasher: C::Hasher,
+    lbl_hasher: C::Hasher,
+    attr_hasher: DefaultFieldHasher<Sha256>,
+    lbl_cache: RwLock<HashMap<LblKey, Point<C>>>,
+    attr_cache: RwLock<HashMap<String, C::ScalarField>>,
 }
 
-pub fn hash_lbl(auth_id: &str, lbl: &str, sign: HashSign, i: u64) -> GAffine {
-    let domain = AUTH_ID_DOMAIN.as_bytes();
-    let g_mapper = SwiftMapToCurveBasedHasher::<
-        Projective<G1Config>,
-        DefaultFieldHasher<Sha256, DEFAULT_FIELD_HASHER_SEC_PARAM>,
-        SwiftECMap<G1Config>,
-    >::new(domain)
-    .unwrap();
-    let sign = match sign {
-        HashSign::Pos => HASH_SIGN_POS,
-        HashSign::Neg => HASH_SIGN_NEG,
-    };
-    let mut input = Vec::new();
-    input.extend_from_slice(&sha256(auth_id));
-    input.extend_from_slice(&sha256(lbl));
-    input.extend_from_slice(&sha256(sign));
-    input.extend_from_slice(&i.to_be_bytes());
-    g_mapper.hash(input.as_slice()).unwrap()
+impl<C: Curve> HashingContext<C> {
+    pub fn new() -> Self {
+        Self {
+            gid_hasher: C::Hasher::new(GID_DOMAIN.as_bytes()).unwrap(),
+            lbl_hasher: C::Hasher::new(AUTH_ID_DOMAIN.as_bytes()).unwrap(),
+            attr_hasher: <DefaultFieldHasher<Sha256> as HashToField<C::ScalarField>>::new(
+                XATTR_DOMAIN.as_bytes(),
+            ),
+            lbl_cache: RwLock::new(HashMap::new()),
+            attr_cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Hashes a user's global identifier. These are not memoised, as a
+    /// key or a ciphertext only ever involves one.
+    pub fn hash_gid(&self, gid: &str) -> Point<C> {
+        self.gid_hasher.hash(gid.as_bytes()).unwrap()
+    }
+
+    pub fn hash_attr(&self, attr: &str) -> C::ScalarField {
+        if let Some(x) = self.attr_cache.read().unwrap().get(attr) {
+            return *x;
+        }
+        let x = self.attr_hasher.hash_to_field(attr.as_bytes(), 1)[0];
+        self.attr_cache.write().unwrap().insert(attr.to_string(), x);
+        x
+    }
+
+    pub fn hash_lbl(&self, auth_id: &str, lbl: &str, sign: HashSign, i: u64) -> Point<C> {
+        let key = (auth_id.to_string(), lbl.to_string(), sign, i);
+        if let Some(p) = self.lbl_cache.read().unwrap().get(&key) {
+            return *p;
+        }
+        let sign_str = match sign {
+            HashSign::Pos => HASH_SIGN_POS,
+            HashSign::Neg => HASH_SIGN_NEG,
+        };
+        let mut input = Vec::new();
+        input.extend_from_slice(&sha256(auth_id));
+        input.extend_from_slice(&sha256(lbl));
+        input.extend_from_slice(&sha256(sign_str));
+        input.extend_from_slice(&i.to_be_bytes());
+        let p = self.lbl_hasher.hash(input.as_slice()).unwrap();
+        self.lbl_cache.write().unwrap().insert(key, p);
+        p
+    }
+
+    /// Number of label points hashed so far.
+    #[cfg(test)]
+    fn num_cached_lbls(&self) -> usize {
+        self.lbl_cache.read().unwrap().len()
+    }
+}
+
+impl<C: Curve> Default for HashingContext<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_hashing_context_matches_hash_to_g() {
+    use crate::curve::{Bls12_381Flipped, DefaultCurve};
+
+    fn check<C: Curve>() {
+        let ctx = HashingContext::<C>::new();
+        assert_eq!(
+            ctx.hash_gid("gid"),
+            C::hash_to_g(GID_DOMAIN.as_bytes(), b"gid")
+        );
+        let p = ctx.hash_lbl("A", "a", HashSign::Pos, 0);
+        assert_eq!(ctx.hash_lbl("A", "a", HashSign::Pos, 0), p);
+        assert_ne!(ctx.hash_lbl("A", "a", HashSign::Neg, 0), p);
+        assert_ne!(ctx.hash_lbl("A", "a", HashSign::Pos, 1), p);
+        assert_ne!(ctx.hash_lbl("B", "a", HashSign::Pos, 0), p);
+        assert_eq!(ctx.num_cached_lbls(), 4);
+
+        // A fresh context hashes to the same points.
+        assert_eq!(
+            HashingContext::<C>::new().hash_lbl("A", "a", HashSign::Pos, 0),
+            p
+        );
+        assert_eq!(
+            ctx.hash_attr("x"),
+            HashingContext::<C>::new().hash_attr("x")
+        );
+    }
+    check::<DefaultCurve>();
+    check::<Bls12_381Flipped>();
 }
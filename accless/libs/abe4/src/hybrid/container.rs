@@ -0,0 +1,288 @@
+//! Self-describing, versioned on-disk format for hybrid ciphertexts.
+//!
+//! A container holds everything that is needed to decrypt a hybrid ciphertext
+//! besides the user secret key. All integers are big-endian, and all variable
+//! length fields are prefixed with their length:
+//!
+//! ```text
+//! magic        4 bytes   "A4HC"
+//! version      u8
+//! suite        u8        see `CipherSuite`
+//! policy       u32 len   canonical encoding of the policy, see
+//!                        `Policy::to_bytes`
+//! authorities  u16 count, then u16 len + UTF-8 bytes for each authority
+//! aad          u32 len   associated data bound to the symmetric ciphertext
+//! abe_ct       u32 len   compressed `Ciphertext`
+//! sym_ct       u32 len   symmetric ciphertext
+//! ```
+//!
+//! The cipher suite is also recorded in the first byte of `sym_ct`, and the
+//! two must match.
+
+use crate::{
+    Ciphertext, MPK, Policy, USK,
+    error::{Abe4Error, Result},
+    hybrid::{CipherSuite, HybridCiphertext, decrypt_hybrid, encrypt_hybrid_with_suite},
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{CryptoRng, RngCore};
+use log::error;
+
+const CONTAINER_MAGIC: &[u8; 4] = b"A4HC";
+/// Current version of the container format.
+pub const CONTAINER_VERSION: u8 = 4;
+
+/// Hybrid ciphertext together with the metadata needed to decrypt it.
+#[derive(Clone)]
+pub struct HybridContainer {
+    /// Format version the container was read from, or will be written with.
+    pub version: u8,
+    /// Symmetric cipher suite used for `sym_ct`.
+    pub suite: CipherSuite,
+    /// String representation of the policy used for encryption. The container
+    /// stores the canonical encoding of the policy, this is only for display.
+    pub policy: String,
+    /// Authorities referenced in the policy, sorted and de-duplicated.
+    pub authorities: Vec<String>,
+    /// Associated data bound to the symmetric ciphertext.
+    pub aad: Vec<u8>,
+    /// The hybrid ciphertext.
+    pub ciphertext: HybridCiphertext,
+}
+
+impl HybridContainer {
+    /// Wraps a hybrid ciphertext into a container.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy`: The policy used to encrypt `ciphertext`.
+    /// * `aad`: The associated data used to encrypt `ciphertext`.
+    /// * `ciphertext`: The hybrid ciphertext.
+    pub fn new(policy: &Policy, aad: &[u8], ciphertext: HybridCiphertext) -> Result<Self> {
+        Ok(Self {
+            version: CONTAINER_VERSION,
+            suite: ciphertext.suite()?,
+            policy: format!("{policy:?}"),
+            authorities: policy.authorities(),
+            aad: aad.to_vec(),
+            ciphertext,
+        })
+    }
+
+    /// Parses the policy stored in the container.
+    pub fn policy(&self) -> Result<Policy> {
+        Policy::parse(&self.policy)
+    }
+
+    /// Serializes the container.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut abe_ct_bytes = Vec::new();
+        self.ciphertext
+            .abe_ct
+            .serialize_compressed(&mut abe_ct_bytes)
+            .map_err(|e| {
+                let reason = format!("error serializing abe ciphertext (error={e:?})");
+                error!("to_bytes(): {reason}");
+                Abe4Error::Serialization(reason)
+            })?;
+
+        let mut out = Vec::new();
+        out.extend_from_slice(CONTAINER_MAGIC);
+        out.push(self.version);
+        out.push(self.suite.id());
+        write_u32_prefixed(&mut out, &self.policy()?.to_bytes())?;
+        let num_auths = u16::try_from(self.authorities.len()).map_err(too_long)?;
+        out.extend_from_slice(&num_auths.to_be_bytes());
+        for auth in &self.authorities {
+            let len = u16::try_from(auth.len()).map_err(too_long)?;
+            out.extend_from_slice(&len.to_be_bytes());
+            out.extend_from_slice(auth.as_bytes());
+        }
+        write_u32_prefixed(&mut out, &self.aad)?;
+        write_u32_prefixed(&mut out, &abe_ct_bytes)?;
+        write_u32_prefixed(&mut out, &self.ciphertext.sym_ct)?;
+
+        Ok(out)
+    }
+
+    /// Deserializes a container, checking that it is well-formed.
+    ///
+    /// This checks the magic number, the version, the cipher suite, that the
+    /// policy parses and references exactly the listed authorities, and that
+    /// the CP-ABE ciphertext is valid.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(CONTAINER_MAGIC.len())? != CONTAINER_MAGIC {
+            let reason = "bad magic number";
+            error!("from_bytes(): {reason}");
+            return Err(Abe4Error::Serialization(reason.into()));
+        }
+        let version = reader.u8()?;
+        if version != CONTAINER_VERSION {
+            let reason = format!("unsupported container version (version={version})");
+            error!("from_bytes(): {reason}");
+            return Err(Abe4Error::Serialization(reason));
+        }
+        let suite = CipherSuite::from_id(reader.u8()?)?;
+
+        let policy_len = reader.u32()? as usize;
+        let policy = Policy::from_bytes(reader.take(policy_len)?)?;
+
+        let num_auths = reader.u16()?;
+        let mut authorities = Vec::with_capacity(num_auths as usize);
+        for _ in 0..num_auths {
+            let len = reader.u16()? as usize;
+            authorities.push(reader.string(len)?);
+        }
+        if authorities != policy.authorities() {
+            let reason = "authorities do not match policy";
+            error!("from_bytes(): {reason}");
+            return Err(Abe4Error::Serialization(reason.into()));
+        }
+
+        let aad_len = reader.u32()? as usize;
+        let aad = reader.take(aad_len)?.to_vec();
+        let abe_ct_len = reader.u32()? as usize;
+        let abe_ct = Ciphertext::deserialize_compressed(reader.take(abe_ct_len)?).map_err(|e| {
+            let reason = format!("error deserializing abe ciphertext (error={e:?})");
+            error!("from_bytes(): {reason}");
+            Abe4Error::Serialization(reason)
+        })?;
+        let sym_ct_len = reader.u32()? as usize;
+        let sym_ct = reader.take(sym_ct_len)?.to_vec();
+        let ciphertext = HybridCiphertext::new(abe_ct, sym_ct);
+        if ciphertext.suite()? != suite {
+            let reason = "cipher suite does not match symmetric ciphertext";
+            error!("from_bytes(): {reason}");
+            return Err(Abe4Error::Serialization(reason.into()));
+        }
+
+        if reader.pos != bytes.len() {
+            let reason = "trailing bytes after container";
+            error!("from_bytes(): {reason}");
+            return Err(Abe4Error::Serialization(reason.into()));
+        }
+
+        Ok(Self {
+            version,
+            suite,
+            policy: format!("{policy:?}"),
+            authorities,
+            aad,
+            ciphertext,
+        })
+    }
+}
+
+fn too_long(e: std::num::TryFromIntError) -> Abe4Error {
+    let reason = format!("field too long for container (error={e})");
+    error!("to_bytes(): {reason}");
+    Abe4Error::Serialization(reason)
+}
+
+fn write_u32_prefixed(out: &mut Vec<u8>, bytes: &[u8]) -> Result<()> {
+    let len = u32::try_from(bytes.len()).map_err(too_long)?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len());
+        let Some(end) = end else {
+            let reason = "container is truncated";
+            error!("take(): {reason}");
+            return Err(Abe4Error::Serialization(reason.into()));
+        };
+        let out = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self, len: usize) -> Result<String> {
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|e| {
+            let reason = format!("invalid UTF-8 string (error={e})");
+            error!("string(): {reason}");
+            Abe4Error::Serialization(reason)
+        })
+    }
+}
+
+/// Hybrid encryption that returns a self-describing container.
+///
+/// # Arguments
+///
+/// * `rng`: A random number generator.
+/// * `mpk`: The master public key.
+/// * `policy`: The policy to encrypt under.
+/// * `suite`: The symmetric cipher suite.
+/// * `plaintext`: The data to encrypt.
+/// * `aad`: Associated data bound to the symmetric ciphertext. It is stored, in
+///   the clear, in the container.
+///
+/// # Returns
+///
+/// The container with the hybrid ciphertext.
+pub fn encrypt_hybrid_container<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    mpk: &MPK,
+    policy: &Policy,
+    suite: CipherSuite,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<HybridContainer> {
+    let ciphertext = encrypt_hybrid_with_suite(rng, mpk, policy, suite, plaintext, aad)?;
+    HybridContainer::new(policy, aad, ciphertext)
+}
+
+/// Decrypts a container, using the policy and AAD stored in it.
+///
+/// # Arguments
+///
+/// * `usk`: The user secret key.
+/// * `gid`: The global identifier of the user.
+/// * `container`: The container to decrypt.
+///
+/// # Returns
+///
+/// The plaintext, or an error if the user attributes do not satisfy the policy
+/// or the ciphertext has been tampered with.
+pub fn decrypt_hybrid_container(
+    usk: &USK,
+    gid: &str,
+    container: &HybridContainer,
+) -> Result<Vec<u8>> {
+    let policy = container.policy()?;
+    decrypt_hybrid(
+        usk,
+        gid,
+        &policy,
+        &container.ciphertext.abe_ct,
+        &container.ciphertext.sym_ct,
+        &container.aad,
+    )
+}
@@ -0,0 +1,530 @@
+//! This module implements the hybrid CP-ABE scheme that derives a CP-ABE scheme
+//! from a CP-ABE Key Encapsulation Mechanism and a symmetric encryption
+//! function. The symmetric encryption function is an AEAD chosen from the
+//! suites in `CipherSuite`, and defaults to AES-GCM-128.
+//!
+//! The associated data of the AEAD is a hash of the CP-ABE ciphertext and the
+//! canonical encoding of the policy (see `Policy::to_bytes`), followed by the
+//! caller's AAD. This binds the two halves of a hybrid ciphertext together, so
+//! neither the `abe_ct` nor the policy that travel alongside `sym_ct` can be
+//! swapped without detection. The version of this binding is recorded in
+//! `sym_ct`, and symmetric ciphertexts from before the binding, which only
+//! authenticate the caller's AAD, still decrypt.
+//!
+//! The hybrid scheme, and the formats built on top of it, always use the
+//! default curve (see `crate::curve`).
+
+use crate::{
+    Ciphertext, Gt, Iota, MPK, Policy, PreparedMpk, PreparedPolicy, PreparedUsk, Tau, USK, decrypt,
+    decrypt_many, decrypt_transformed, encrypt, encrypt_many,
+    error::{Abe4Error, Result},
+    scheme::types::{RetrievalKey, TransformKey, TransformedCiphertext},
+    transform,
+};
+use aes_gcm::aead::Payload;
+use ark_serialize::CanonicalSerialize;
+use ark_std::rand::{CryptoRng, RngCore};
+use log::error;
+use sha2::{Digest, Sha256};
+use suite::{NONCE_LEN, SymCipher};
+
+mod container;
+mod stream;
+mod suite;
+mod wrap;
+
+pub use container::{
+    CONTAINER_VERSION, HybridContainer, decrypt_hybrid_container, encrypt_hybrid_container,
+};
+pub use stream::{HybridDecryptReader, HybridEncryptWriter, STREAM_SEGMENT_SIZE};
+pub use suite::CipherSuite;
+use wrap::{WRAPPED_FLAG, is_wrapped, sym_decrypt_wrapped};
+pub use wrap::{encrypt_hybrid_wrapped, reencapsulate_hybrid};
+
+const ABE4_KDF_LABEL: &str = "accless-abe4";
+const ABE4_BINDING_LABEL: &[u8] = b"accless-abe4-binding";
+/// Version of the binding computed by `bind_aad`.
+const BINDING_VERSION: u8 = 1;
+/// Bit set in the first byte of a symmetric ciphertext, on top of the suite
+/// id, if the second byte is the version of its binding.
+const BOUND_FLAG: u8 = 0x20;
+/// Cipher suite of symmetric ciphertexts in the legacy layout, whose HKDF
+/// info string is the one of AES-GCM-128.
+const LEGACY_SUITE: CipherSuite = CipherSuite::Aes128Gcm;
+
+#[derive(Clone)]
+pub struct HybridCiphertext {
+    /// CP-ABE ciphertext.
+    pub abe_ct: Ciphertext,
+    /// Symmetric ciphertext CTsym = suite id | flags || binding version ||
+    /// nonce || AEAD ciphertext+tag, see `sym_encrypt_gt`.
+    pub sym_ct: Vec<u8>,
+}
+
+impl HybridCiphertext {
+    pub fn new(abe_ct: Ciphertext, sym_ct: Vec<u8>) -> Self {
+        Self { abe_ct, sym_ct }
+    }
+
+    /// Returns the cipher suite recorded in the symmetric ciphertext.
+    pub fn suite(&self) -> Result<CipherSuite> {
+        recorded_suite(&self.sym_ct)
+    }
+}
+
+/// Returns the cipher suite recorded in the first byte of `sym_ct`. For a
+/// symmetric ciphertext in the legacy layout, that byte is part of the nonce.
+fn recorded_suite(sym_ct: &[u8]) -> Result<CipherSuite> {
+    match sym_ct.first() {
+        Some(id) => CipherSuite::from_id(id & !(WRAPPED_FLAG | BOUND_FLAG)),
+        None => Err(Abe4Error::Serialization(String::from(
+            "ciphertext too short",
+        ))),
+    }
+}
+
+/// Computes the associated data for the symmetric layer of a hybrid
+/// ciphertext.
+///
+/// # Arguments
+///
+/// * `policy`: The policy the ciphertext is encrypted under.
+/// * `abe_ct`: The CP-ABE ciphertext.
+/// * `aad`: The caller-supplied associated data.
+///
+/// # Returns
+///
+/// SHA-256(label || version || len(policy) || policy || len(abe_ct) || abe_ct)
+/// || aad, where `version` is `BINDING_VERSION`, `policy` is the canonical
+/// encoding of the policy, and `abe_ct` is the compressed serialization of the
+/// CP-ABE ciphertext.
+fn bind_aad(policy: &Policy, abe_ct: &Ciphertext, aad: &[u8]) -> Result<Vec<u8>> {
+    let mut abe_ct_bytes = Vec::new();
+    abe_ct
+        .serialize_compressed(&mut abe_ct_bytes)
+        .map_err(|e| {
+            let reason = format!("error serializing abe ciphertext (error={e:?})");
+            error!("bind_aad(): {reason}");
+            Abe4Error::Serialization(reason)
+        })?;
+    let policy_bytes = policy.to_bytes();
+
+    let mut hasher = Sha256::new();
+    hasher.update(ABE4_BINDING_LABEL);
+    hasher.update([BINDING_VERSION]);
+    hasher.update((policy_bytes.len() as u64).to_be_bytes());
+    hasher.update(&policy_bytes);
+    hasher.update((abe_ct_bytes.len() as u64).to_be_bytes());
+    hasher.update(&abe_ct_bytes);
+
+    let mut bound = hasher.finalize().to_vec();
+    bound.extend_from_slice(aad);
+    Ok(bound)
+}
+
+/// Checks that a symmetric ciphertext is bound with a version of `bind_aad`
+/// that we support.
+fn check_binding_version(version: u8) -> Result<()> {
+    if version != BINDING_VERSION {
+        let reason = format!("unsupported binding version (version={version})");
+        error!("check_binding_version(): {reason}");
+        return Err(Abe4Error::Serialization(reason));
+    }
+    Ok(())
+}
+
+/// Encrypt `plaintext` under a key derived from `gt`.
+///
+/// # Arguments
+///
+/// * `bound_aad`: The associated data, as returned by `bind_aad`.
+///
+/// # Returns
+///
+/// A symmetrically encrypted CT as: suite id | `BOUND_FLAG` (1 byte) ||
+/// `BINDING_VERSION` (1 byte) || nonce (12 bytes) || ciphertext + tag.
+fn sym_encrypt_gt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    gt: &Gt,
+    suite: CipherSuite,
+    plaintext: &[u8],
+    bound_aad: &[u8],
+) -> Result<Vec<u8>> {
+    let cipher = SymCipher::from_gt(suite, gt, ABE4_KDF_LABEL)?;
+
+    // 96-bit random nonce.
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+
+    let payload = Payload {
+        msg: plaintext,
+        aad: bound_aad,
+    };
+    let mut ct = cipher.encrypt(&nonce, payload)?;
+
+    // CTsym = suite id | flag || binding version || nonce || ct.
+    let mut out = Vec::with_capacity(2 + NONCE_LEN + ct.len());
+    out.push(suite.id() | BOUND_FLAG);
+    out.push(BINDING_VERSION);
+    out.extend_from_slice(&nonce);
+    out.append(&mut ct);
+
+    Ok(out)
+}
+
+/// Whether `sym_ct` records the version of its binding, see `sym_encrypt_gt`.
+fn is_bound(sym_ct: &[u8]) -> bool {
+    sym_ct.first().is_some_and(|id| id & BOUND_FLAG != 0)
+}
+
+/// Decrypt `sym_ct` with a key derived from `gt`, using the cipher suite
+/// recorded in `sym_ct`.
+/// Expects sym_ct = suite id | `BOUND_FLAG` (1 byte) || binding version (1
+/// byte) || nonce (12 bytes) || ciphertext + tag, with `aad` as returned by
+/// `bind_aad`, or, for symmetric ciphertexts from before the binding, sym_ct
+/// = suite id (1 byte) || nonce (12 bytes) || ciphertext + tag, with the
+/// caller's AAD.
+fn sym_decrypt_gt(gt: &Gt, sym_ct: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    let header_len = if is_bound(sym_ct) { 2 } else { 1 };
+    if sym_ct.len() < header_len + NONCE_LEN {
+        let reason = "ciphertext too short";
+        error!("sym_decrypt_gt(): {reason}");
+        return Err(Abe4Error::Serialization(String::from(reason)));
+    }
+
+    let (header, rest) = sym_ct.split_at(header_len);
+    let suite = CipherSuite::from_id(header[0] & !BOUND_FLAG)?;
+    if let Some(version) = header.get(1) {
+        check_binding_version(*version)?;
+    }
+    let (nonce_bytes, ct_bytes) = rest.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce_bytes.try_into().map_err(|e| {
+        let reason = format!("ciphertext too short for nonce (error={e:?})");
+        error!("sym_decrypt_gt(): {reason}");
+        Abe4Error::Serialization(reason)
+    })?;
+
+    let cipher = SymCipher::from_gt(suite, gt, ABE4_KDF_LABEL)?;
+    let payload = Payload { msg: ct_bytes, aad };
+    cipher.decrypt(&nonce, payload)
+}
+
+/// Decrypt `sym_ct` in the legacy layout, from before the cipher suite was
+/// recorded in symmetric ciphertexts.
+/// Expects sym_ct = nonce (12 bytes) || AES-GCM-128 ciphertext + tag, with
+/// the caller's AAD as associated data.
+fn sym_decrypt_legacy(gt: &Gt, sym_ct: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if sym_ct.len() < NONCE_LEN {
+        let reason = "ciphertext too short";
+        error!("sym_decrypt_legacy(): {reason}");
+        return Err(Abe4Error::Serialization(String::from(reason)));
+    }
+
+    let (nonce_bytes, ct_bytes) = sym_ct.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce_bytes.try_into().map_err(|e| {
+        let reason = format!("ciphertext too short for nonce (error={e:?})");
+        error!("sym_decrypt_legacy(): {reason}");
+        Abe4Error::Serialization(reason)
+    })?;
+
+    let cipher = SymCipher::from_gt(LEGACY_SUITE, gt, ABE4_KDF_LABEL)?;
+    let payload = Payload { msg: ct_bytes, aad };
+    cipher.decrypt(&nonce, payload)
+}
+
+/// Decrypt the symmetric ciphertext of a hybrid ciphertext, in either the
+/// default or the key-wrapped layout. Unless `sym_ct` is from before the
+/// binding, `aad` is first bound to the policy and the CP-ABE ciphertext.
+///
+/// Symmetric ciphertexts in the legacy layout (see `sym_decrypt_legacy`) have
+/// no header to tell them apart, as they start with a random nonce, so we
+/// fall back to it when the current layouts fail to decrypt.
+fn sym_decrypt(
+    gt: &Gt,
+    sym_ct: &[u8],
+    policy: &Policy,
+    abe_ct: &Ciphertext,
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    sym_decrypt_with_suite(gt, sym_ct, policy, abe_ct, aad).map(|(_, plaintext)| plaintext)
+}
+
+/// Like `sym_decrypt`, but also returns the cipher suite that `sym_ct` was
+/// encrypted with: the recorded one, or `LEGACY_SUITE` for the legacy layout.
+fn sym_decrypt_with_suite(
+    gt: &Gt,
+    sym_ct: &[u8],
+    policy: &Policy,
+    abe_ct: &Ciphertext,
+    aad: &[u8],
+) -> Result<(CipherSuite, Vec<u8>)> {
+    let result = if is_wrapped(sym_ct) || is_bound(sym_ct) {
+        bind_aad(policy, abe_ct, aad).and_then(|bound_aad| {
+            if is_wrapped(sym_ct) {
+                sym_decrypt_wrapped(gt, sym_ct, &bound_aad, aad)
+            } else {
+                sym_decrypt_gt(gt, sym_ct, &bound_aad)
+            }
+        })
+    } else {
+        sym_decrypt_gt(gt, sym_ct, aad)
+    };
+    result
+        .and_then(|plaintext| Ok((recorded_suite(sym_ct)?, plaintext)))
+        .or_else(|e| {
+            sym_decrypt_legacy(gt, sym_ct, aad)
+                .map(|plaintext| (LEGACY_SUITE, plaintext))
+                .map_err(|_| e)
+        })
+}
+
+/// Hybrid CP-ABE + AES-GCM encryption.
+///
+/// This is the ABE.Encrypt from Appendix A.3, instantiated with:
+/// - The CP-ABE encryption method in `scheme` -> opt4 from Abe-Cubed.
+/// - A symmetric encryption scheme -> AES-GCM-128
+/// - KDF -> Rely on the HKDF crate.
+pub fn encrypt_hybrid<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    mpk: &MPK,
+    policy: &Policy,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<HybridCiphertext> {
+    encrypt_hybrid_with_suite(rng, mpk, policy, CipherSuite::default(), plaintext, aad)
+}
+
+/// Hybrid CP-ABE encryption with a custom symmetric cipher suite.
+///
+/// Same as `encrypt_hybrid`, but the payload is encrypted with `suite`. The
+/// suite is recorded in the symmetric ciphertext, so `decrypt_hybrid` picks it
+/// up automatically.
+///
+/// # Arguments
+///
+/// * `rng`: A random number generator.
+/// * `mpk`: The master public key.
+/// * `policy`: The policy to encrypt under.
+/// * `suite`: The symmetric cipher suite.
+/// * `plaintext`: The data to encrypt.
+/// * `aad`: Associated data bound to the symmetric ciphertext.
+pub fn encrypt_hybrid_with_suite<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    mpk: &MPK,
+    policy: &Policy,
+    suite: CipherSuite,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<HybridCiphertext> {
+    let tau = Tau::new(policy);
+
+    // Encapsulate: (CTA, K) where K = Gt
+    let (gt, abe_ct) = encrypt(&mut *rng, mpk, policy, &tau)?;
+
+    // Symmetric encryption under KDF(K), bound to CTA and the policy.
+    let bound_aad = bind_aad(policy, &abe_ct, aad)?;
+    let sym_ct = sym_encrypt_gt(rng, &gt, suite, plaintext, &bound_aad)?;
+
+    Ok(HybridCiphertext::new(abe_ct, sym_ct))
+}
+
+/// Hybrid CP-ABE decryption, using the cipher suite recorded in `sym_ct`.
+///
+/// This is the ABE.Decrypt from Appendix A.3:
+///  - K <- KEM.Decaps(...)
+///  - M <- SE.SymDecrypt_{KDF(K)}(CTsym)
+pub fn decrypt_hybrid(
+    usk: &USK,
+    gid: &str,
+    policy: &Policy,
+    abe_ct: &Ciphertext,
+    sym_ct: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let tau = Tau::new(policy);
+    let user_attrs = usk.get_user_attributes();
+    let iota = Iota::new(&user_attrs);
+
+    // KEM decapsulation step.
+    let gt = decrypt(usk, gid, &iota, &tau, policy, abe_ct).inspect_err(|e| {
+        error!("decrypt_hybrid(): CP-ABE decryption failed (error={e})");
+    })?;
+
+    // Symmetric decryption under KDF(K), bound to CTA and the policy.
+    sym_decrypt(&gt, sym_ct, policy, abe_ct, aad)
+}
+
+/// # Description
+///
+/// The CP-ABE half of `decrypt_hybrid`, run by an untrusted helper with the
+/// user's transformation key (see `transform_keygen`).
+///
+/// # Returns
+///
+/// The transformed CP-ABE ciphertext, to hand to the user together with
+/// `sym_ct`, or the errors of `decrypt_hybrid`.
+pub fn transform_hybrid(
+    tk: &TransformKey,
+    gid: &str,
+    policy: &Policy,
+    abe_ct: &Ciphertext,
+) -> Result<TransformedCiphertext> {
+    let tau = Tau::new(policy);
+    let user_attrs = tk.usk.get_user_attributes();
+    let iota = Iota::new(&user_attrs);
+
+    transform(tk, gid, &iota, &tau, policy, abe_ct).inspect_err(|e| {
+        error!("transform_hybrid(): CP-ABE transformation failed (error={e})");
+    })
+}
+
+/// # Description
+///
+/// Finishes a hybrid decryption started by `transform_hybrid`.
+///
+/// Recovering the symmetric key takes a single exponentiation with the
+/// retrieval key. A helper that transforms dishonestly yields a wrong key,
+/// which the AEAD of `sym_ct` rejects.
+///
+/// # Arguments
+///
+/// * `rk`: The retrieval key that matches the helper's transformation key.
+/// * `transformed`: The result of `transform_hybrid`.
+/// * `policy`: The policy the ciphertext is encrypted under.
+/// * `abe_ct`: The CP-ABE ciphertext, which `sym_ct` is bound to.
+/// * `sym_ct`: The symmetric ciphertext.
+/// * `aad`: Associated data bound to the symmetric ciphertext.
+pub fn decrypt_hybrid_transformed(
+    rk: &RetrievalKey,
+    transformed: &TransformedCiphertext,
+    policy: &Policy,
+    abe_ct: &Ciphertext,
+    sym_ct: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let gt = decrypt_transformed(rk, transformed);
+    sym_decrypt(&gt, sym_ct, policy, abe_ct, aad)
+}
+
+/// # Description
+///
+/// Hybrid encryption of many plaintexts under the same prepared policy.
+///
+/// Each plaintext gets its own CP-ABE encapsulation and nonce, so the result
+/// is the same as calling `encrypt_hybrid_with_suite` on each plaintext, but
+/// the policy-dependent setup of the CP-ABE layer is only done once.
+///
+/// # Arguments
+///
+/// * `rng`: A random number generator.
+/// * `mpk`: The prepared master public key.
+/// * `policy`: The policy to encrypt under, prepared with `mpk`.
+/// * `suite`: The symmetric cipher suite.
+/// * `plaintexts`: The data to encrypt.
+/// * `aad`: Associated data bound to every symmetric ciphertext.
+pub fn encrypt_hybrid_many<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    mpk: &PreparedMpk,
+    policy: &PreparedPolicy,
+    suite: CipherSuite,
+    plaintexts: &[&[u8]],
+    aad: &[u8],
+) -> Result<Vec<HybridCiphertext>> {
+    let keys = encrypt_many(&mut *rng, mpk, policy, plaintexts.len())?;
+    keys.into_iter()
+        .zip(plaintexts)
+        .map(|((gt, abe_ct), plaintext)| {
+            let bound_aad = bind_aad(policy.policy(), &abe_ct, aad)?;
+            let sym_ct = sym_encrypt_gt(rng, &gt, suite, plaintext, &bound_aad)?;
+            Ok(HybridCiphertext::new(abe_ct, sym_ct))
+        })
+        .collect()
+}
+
+/// # Description
+///
+/// Hybrid decryption of many ciphertexts encrypted under the same policy,
+/// e.g. with `encrypt_hybrid_many`.
+///
+/// # Returns
+///
+/// The plaintext of each ciphertext, in order, or the first error, as
+/// returned by `decrypt_hybrid`.
+pub fn decrypt_hybrid_many(
+    usk: &PreparedUsk,
+    policy: &PreparedPolicy,
+    cts: &[HybridCiphertext],
+    aad: &[u8],
+) -> Result<Vec<Vec<u8>>> {
+    let gts = decrypt_many(usk, policy, cts.iter().map(|ct| &ct.abe_ct)).inspect_err(|e| {
+        error!("decrypt_hybrid_many(): CP-ABE decryption failed (error={e})");
+    })?;
+    gts.iter()
+        .zip(cts)
+        .map(|(gt, ct)| sym_decrypt(gt, &ct.sym_ct, policy.policy(), &ct.abe_ct, aad))
+        .collect()
+}
+
+#[test]
+fn test_decrypt_legacy_layout() {
+    use crate::{UserAttribute, keygen, setup};
+
+    let mut rng = ark_std::test_rng();
+    let user_attrs = vec![UserAttribute::new("A", "a", "0")];
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &vec!["A"]);
+    let usk = keygen(&mut rng, "gid", &msk, &user_attrs, &iota).unwrap();
+    let policy = Policy::parse("A.a:0").unwrap();
+    let (gt, abe_ct) = encrypt(&mut rng, &mpk, &policy, &Tau::new(&policy)).unwrap();
+
+    // CTsym = nonce || AES-GCM-128 ciphertext + tag, under the raw AAD.
+    let nonce = [7u8; NONCE_LEN];
+    let cipher = SymCipher::from_gt(CipherSuite::Aes128Gcm, &gt, ABE4_KDF_LABEL).unwrap();
+    let payload = Payload {
+        msg: b"legacy payload",
+        aad: b"aad",
+    };
+    let mut sym_ct = nonce.to_vec();
+    sym_ct.extend(cipher.encrypt(&nonce, payload).unwrap());
+
+    let pt = decrypt_hybrid(&usk, "gid", &policy, &abe_ct, &sym_ct, b"aad").unwrap();
+    assert_eq!(pt, b"legacy payload");
+    assert!(decrypt_hybrid(&usk, "gid", &policy, &abe_ct, &sym_ct, b"other").is_err());
+}
+
+#[test]
+fn test_decrypt_binding_versions() {
+    use crate::{UserAttribute, keygen, setup};
+    use ark_std::rand::{SeedableRng, rngs::StdRng};
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let user_attrs = vec![UserAttribute::new("A", "a", "0")];
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &vec!["A"]);
+    let usk = keygen(&mut rng, "gid", &msk, &user_attrs, &iota).unwrap();
+    let policy = Policy::parse("A.a:0").unwrap();
+
+    // CTsym = suite id || nonce || ciphertext + tag, under the raw AAD, as
+    // written before the binding.
+    let (gt, abe_ct) = encrypt(&mut rng, &mpk, &policy, &Tau::new(&policy)).unwrap();
+    let suite = CipherSuite::ChaCha20Poly1305;
+    let nonce = [7u8; NONCE_LEN];
+    let cipher = SymCipher::from_gt(suite, &gt, ABE4_KDF_LABEL).unwrap();
+    let payload = Payload {
+        msg: b"unbound payload",
+        aad: b"aad",
+    };
+    let mut sym_ct = vec![suite.id()];
+    sym_ct.extend_from_slice(&nonce);
+    sym_ct.extend(cipher.encrypt(&nonce, payload).unwrap());
+    let pt = decrypt_hybrid(&usk, "gid", &policy, &abe_ct, &sym_ct, b"aad").unwrap();
+    assert_eq!(pt, b"unbound payload");
+
+    // The binding version is recorded, and unknown versions are rejected.
+    let ct = encrypt_hybrid(&mut rng, &mpk, &policy, b"bound payload", b"aad").unwrap();
+    assert_eq!(ct.sym_ct[0], CipherSuite::default().id() | BOUND_FLAG);
+    assert_eq!(ct.sym_ct[1], BINDING_VERSION);
+    let mut sym_ct = ct.sym_ct.clone();
+    sym_ct[1] = BINDING_VERSION + 1;
+    assert!(decrypt_hybrid(&usk, "gid", &policy, &ct.abe_ct, &sym_ct, b"aad").is_err());
+}
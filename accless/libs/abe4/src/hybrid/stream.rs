@@ -0,0 +1,389 @@
+//! Streaming hybrid encryption for payloads that do not fit in memory.
+//!
+//! We run a single KEM encapsulation, and then encrypt the payload in
+//! fixed-size segments following the STREAM construction from Hoang,
+//! Reyhanitabar, Rogaway and Vizár ("Online Authenticated-Encryption and its
+//! Nonce-Reuse Misuse-Resistance"). The nonce for each segment is:
+//!
+//! ```text
+//! nonce_prefix (7 bytes) || segment counter (u32, big-endian) || last flag (u8)
+//! ```
+//!
+//! so segments can not be reordered, dropped, or truncated without the
+//! decryption failing. A stream starts with a header that holds everything
+//! that is needed to decrypt it besides the user secret key and the AAD:
+//!
+//! ```text
+//! magic        4 bytes   "A4HS"
+//! version      u8
+//! suite        u8        see `CipherSuite`
+//! policy       u32 len   canonical encoding of the policy, see
+//!                        `Policy::to_bytes`
+//! abe_ct       u32 len   compressed `Ciphertext`
+//! nonce_prefix 7 bytes
+//! ```
+//!
+//! followed by the encrypted segments. Every segment but the last holds
+//! `STREAM_SEGMENT_SIZE` bytes of plaintext (plus the 16-byte tag). As in the
+//! one-shot scheme, the associated data of every segment binds the policy and
+//! the CP-ABE ciphertext in the header.
+
+use crate::{
+    Ciphertext, Gt, Iota, MPK, Policy, Tau, USK, decrypt, encrypt,
+    error::{Abe4Error, Result},
+    hybrid::{
+        CipherSuite, bind_aad,
+        suite::{NONCE_LEN, SymCipher},
+    },
+};
+use aes_gcm::aead::Payload;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::{CryptoRng, RngCore};
+use log::error;
+use std::io::{self, Read, Write};
+
+const STREAM_MAGIC: &[u8; 4] = b"A4HS";
+const STREAM_VERSION: u8 = 3;
+const ABE4_STREAM_KDF_LABEL: &str = "accless-abe4-stream";
+const NONCE_PREFIX_LEN: usize = 7;
+const TAG_LEN: usize = 16;
+
+/// Size, in bytes, of the plaintext in every segment but the last one.
+pub const STREAM_SEGMENT_SIZE: usize = 64 * 1024;
+
+/// Per-stream state of the STREAM construction.
+struct Segments {
+    cipher: SymCipher,
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    counter: u32,
+    aad: Vec<u8>,
+    done: bool,
+}
+
+impl Segments {
+    fn new(
+        suite: CipherSuite,
+        gt: &Gt,
+        nonce_prefix: [u8; NONCE_PREFIX_LEN],
+        aad: &[u8],
+    ) -> Result<Self> {
+        Ok(Self {
+            cipher: SymCipher::from_gt(suite, gt, ABE4_STREAM_KDF_LABEL)?,
+            nonce_prefix,
+            counter: 0,
+            aad: aad.to_vec(),
+            done: false,
+        })
+    }
+
+    fn nonce(&self, last: bool) -> [u8; NONCE_LEN] {
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[..NONCE_PREFIX_LEN].copy_from_slice(&self.nonce_prefix);
+        nonce[NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&self.counter.to_be_bytes());
+        nonce[NONCE_LEN - 1] = last as u8;
+        nonce
+    }
+
+    /// Moves on to the next segment, making sure that we never re-use a nonce.
+    fn advance(&mut self, last: bool) -> io::Result<()> {
+        if last {
+            self.done = true;
+            return Ok(());
+        }
+        self.counter = self.counter.checked_add(1).ok_or_else(|| {
+            let reason = "stream exceeds the maximum number of segments";
+            error!("advance(): {reason}");
+            io::Error::other(reason)
+        })?;
+        Ok(())
+    }
+
+    fn seal(&mut self, plaintext: &[u8], last: bool) -> io::Result<Vec<u8>> {
+        let payload = Payload {
+            msg: plaintext,
+            aad: &self.aad,
+        };
+        let ct = self
+            .cipher
+            .encrypt(&self.nonce(last), payload)
+            .map_err(|e| {
+                let reason = format!("error encrypting stream segment (error={e})");
+                error!("seal(): {reason}");
+                io::Error::other(Abe4Error::Aead(reason))
+            })?;
+        self.advance(last)?;
+        Ok(ct)
+    }
+
+    fn open(&mut self, ciphertext: &[u8], last: bool) -> io::Result<Vec<u8>> {
+        let payload = Payload {
+            msg: ciphertext,
+            aad: &self.aad,
+        };
+        let pt = self
+            .cipher
+            .decrypt(&self.nonce(last), payload)
+            .map_err(|e| {
+                let reason = format!(
+                    "error decrypting stream segment (segment={}, error={e})",
+                    self.counter
+                );
+                error!("open(): {reason}");
+                io::Error::new(io::ErrorKind::InvalidData, Abe4Error::Aead(reason))
+            })?;
+        self.advance(last)?;
+        Ok(pt)
+    }
+}
+
+/// Encrypts a stream of data under a policy.
+///
+/// All the data written to this writer is encrypted and forwarded to the
+/// inner writer, one segment at a time. Callers must call `finish` once they
+/// are done writing, otherwise the stream is truncated and will fail to
+/// decrypt.
+pub struct HybridEncryptWriter<W: Write> {
+    inner: W,
+    segments: Segments,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> HybridEncryptWriter<W> {
+    /// Runs the KEM encapsulation and writes the stream header.
+    ///
+    /// # Arguments
+    ///
+    /// * `rng`: A random number generator.
+    /// * `mpk`: The master public key.
+    /// * `policy`: The policy to encrypt under.
+    /// * `suite`: The symmetric cipher suite.
+    /// * `aad`: Associated data bound to every segment. It is not stored in the
+    ///   stream, so the reader must provide it again.
+    /// * `inner`: The writer to forward the encrypted stream to.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        mpk: &MPK,
+        policy: &Policy,
+        suite: CipherSuite,
+        aad: &[u8],
+        mut inner: W,
+    ) -> Result<Self> {
+        let tau = Tau::new(policy);
+        let (gt, abe_ct) = encrypt(&mut *rng, mpk, policy, &tau)?;
+
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        rng.fill_bytes(&mut nonce_prefix);
+        let bound_aad = bind_aad(policy, &abe_ct, aad)?;
+        let segments = Segments::new(suite, &gt, nonce_prefix, &bound_aad)?;
+
+        let mut abe_ct_bytes = Vec::new();
+        abe_ct
+            .serialize_compressed(&mut abe_ct_bytes)
+            .map_err(|e| {
+                let reason = format!("error serializing abe ciphertext (error={e:?})");
+                error!("new(): {reason}");
+                Abe4Error::Serialization(reason)
+            })?;
+        let policy_bytes = policy.to_bytes();
+
+        let mut header = Vec::new();
+        header.extend_from_slice(STREAM_MAGIC);
+        header.push(STREAM_VERSION);
+        header.push(suite.id());
+        header.extend_from_slice(&u32_len(policy_bytes.len())?.to_be_bytes());
+        header.extend_from_slice(&policy_bytes);
+        header.extend_from_slice(&u32_len(abe_ct_bytes.len())?.to_be_bytes());
+        header.extend_from_slice(&abe_ct_bytes);
+        header.extend_from_slice(&nonce_prefix);
+        inner.write_all(&header)?;
+
+        Ok(Self {
+            inner,
+            segments,
+            buffer: Vec::with_capacity(STREAM_SEGMENT_SIZE),
+        })
+    }
+
+    /// Encrypts the last segment, flushes the inner writer, and returns it.
+    pub fn finish(mut self) -> io::Result<W> {
+        let ct = self.segments.seal(&self.buffer, true)?;
+        self.inner.write_all(&ct)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for HybridEncryptWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // We only seal a full segment once we know that more data follows, as
+        // the last segment must be flagged as such.
+        if self.buffer.len() == STREAM_SEGMENT_SIZE && !buf.is_empty() {
+            let ct = self.segments.seal(&self.buffer, false)?;
+            self.buffer.clear();
+            self.inner.write_all(&ct)?;
+        }
+
+        let len = buf.len().min(STREAM_SEGMENT_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts a stream produced by `HybridEncryptWriter`.
+///
+/// Every segment is authenticated before any of its plaintext is returned.
+/// Reads fail with `io::ErrorKind::InvalidData` if the stream has been
+/// tampered with, re-ordered, or truncated.
+pub struct HybridDecryptReader<R: Read> {
+    inner: R,
+    policy: Policy,
+    segments: Segments,
+    plaintext: Vec<u8>,
+    pos: usize,
+    // Byte we had to read ahead of the current segment to check for EOF.
+    lookahead: Option<u8>,
+}
+
+impl<R: Read> HybridDecryptReader<R> {
+    /// Reads the stream header and runs the KEM decapsulation.
+    ///
+    /// # Arguments
+    ///
+    /// * `usk`: The user secret key.
+    /// * `gid`: The global identifier of the user.
+    /// * `aad`: The associated data the stream was encrypted with.
+    /// * `inner`: The reader to read the encrypted stream from.
+    ///
+    /// # Returns
+    ///
+    /// A reader over the plaintext, or an error if the header is malformed or
+    /// the user attributes do not satisfy the policy.
+    pub fn new(usk: &USK, gid: &str, aad: &[u8], mut inner: R) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        inner.read_exact(&mut magic)?;
+        if &magic != STREAM_MAGIC {
+            let reason = "bad magic number";
+            error!("new(): {reason}");
+            return Err(Abe4Error::Serialization(String::from(reason)));
+        }
+        let mut version_suite = [0u8; 2];
+        inner.read_exact(&mut version_suite)?;
+        if version_suite[0] != STREAM_VERSION {
+            let reason = format!("unsupported stream version (version={})", version_suite[0]);
+            error!("new(): {reason}");
+            return Err(Abe4Error::Serialization(reason));
+        }
+        let suite = CipherSuite::from_id(version_suite[1])?;
+
+        let policy = Policy::from_bytes(&read_u32_prefixed(&mut inner)?)?;
+        let abe_ct_bytes = read_u32_prefixed(&mut inner)?;
+        let abe_ct = Ciphertext::deserialize_compressed(&abe_ct_bytes[..]).map_err(|e| {
+            let reason = format!("error deserializing abe ciphertext (error={e:?})");
+            error!("new(): {reason}");
+            Abe4Error::Serialization(reason)
+        })?;
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        inner.read_exact(&mut nonce_prefix)?;
+
+        let tau = Tau::new(&policy);
+        let user_attrs = usk.get_user_attributes();
+        let iota = Iota::new(&user_attrs);
+        let gt = decrypt(usk, gid, &iota, &tau, &policy, &abe_ct).inspect_err(|e| {
+            error!("new(): CP-ABE decryption failed (error={e})");
+        })?;
+
+        let bound_aad = bind_aad(&policy, &abe_ct, aad)?;
+        Ok(Self {
+            inner,
+            policy,
+            segments: Segments::new(suite, &gt, nonce_prefix, &bound_aad)?,
+            plaintext: Vec::new(),
+            pos: 0,
+            lookahead: None,
+        })
+    }
+
+    /// Returns the policy the stream was encrypted under.
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Reads and decrypts the next segment into `self.plaintext`.
+    fn next_segment(&mut self) -> io::Result<()> {
+        let mut ct = Vec::with_capacity(STREAM_SEGMENT_SIZE + TAG_LEN);
+        if let Some(byte) = self.lookahead.take() {
+            ct.push(byte);
+        }
+        (&mut self.inner)
+            .take((STREAM_SEGMENT_SIZE + TAG_LEN - ct.len()) as u64)
+            .read_to_end(&mut ct)?;
+
+        // A full segment is the last one iff it is followed by EOF.
+        let mut last = true;
+        if ct.len() == STREAM_SEGMENT_SIZE + TAG_LEN {
+            let mut byte = [0u8; 1];
+            if read_byte(&mut self.inner, &mut byte)? {
+                self.lookahead = Some(byte[0]);
+                last = false;
+            }
+        }
+
+        self.plaintext = self.segments.open(&ct, last)?;
+        self.pos = 0;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for HybridDecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plaintext.len() {
+            if self.segments.done || buf.is_empty() {
+                return Ok(0);
+            }
+            self.next_segment()?;
+        }
+
+        let len = buf.len().min(self.plaintext.len() - self.pos);
+        buf[..len].copy_from_slice(&self.plaintext[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+fn read_u32_prefixed<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    let mut bytes = Vec::new();
+    reader
+        .take(u32::from_be_bytes(len) as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() != u32::from_be_bytes(len) as usize {
+        let reason = "stream header is truncated";
+        error!("read_u32_prefixed(): {reason}");
+        return Err(Abe4Error::Serialization(String::from(reason)));
+    }
+    Ok(bytes)
+}
+
+fn u32_len(len: usize) -> Result<u32> {
+    u32::try_from(len).map_err(|e| {
+        let reason = format!("stream header field is too long (error={e:?})");
+        error!("u32_len(): {reason}");
+        Abe4Error::Serialization(reason)
+    })
+}
+
+/// Reads a single byte, returning `false` on EOF.
+fn read_byte<R: Read>(reader: &mut R, byte: &mut [u8; 1]) -> io::Result<bool> {
+    loop {
+        match reader.read(byte) {
+            Ok(n) => return Ok(n == 1),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
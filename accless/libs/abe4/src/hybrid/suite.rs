@@ -0,0 +1,185 @@
+//! Symmetric cipher suites for the hybrid scheme.
+//!
+//! All suites are AEADs with a 96-bit nonce, keyed with HKDF-SHA256 from the
+//! `Gt` element that the CP-ABE KEM encapsulates.
+
+use crate::{
+    Gt,
+    error::{Abe4Error, Result},
+};
+use aes_gcm::{
+    Aes128Gcm, Aes256Gcm,
+    aead::{Aead, KeyInit, Payload},
+};
+use aes_gcm_siv::Aes256GcmSiv;
+use ark_serialize::CanonicalSerialize;
+use chacha20poly1305::ChaCha20Poly1305;
+use hkdf::Hkdf;
+use log::error;
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+const ABE4_KDF_SALT: &[u8] = b"accless-abe4-kem-salt";
+
+/// Length, in bytes, of the nonce of every supported suite.
+pub(crate) const NONCE_LEN: usize = 12;
+
+/// Symmetric cipher suite used to encrypt the payload of a hybrid ciphertext.
+///
+/// The discriminant is the identifier we use in serialized ciphertexts, so it
+/// must never change for an existing suite.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum CipherSuite {
+    /// AES-128-GCM.
+    #[default]
+    Aes128Gcm = 1,
+    /// AES-256-GCM.
+    Aes256Gcm = 2,
+    /// AES-256-GCM-SIV, which is nonce misuse-resistant.
+    Aes256GcmSiv = 3,
+    /// ChaCha20-Poly1305, for targets without hardware support for AES.
+    ChaCha20Poly1305 = 4,
+}
+
+impl CipherSuite {
+    /// All the supported suites.
+    pub const ALL: [CipherSuite; 4] = [
+        CipherSuite::Aes128Gcm,
+        CipherSuite::Aes256Gcm,
+        CipherSuite::Aes256GcmSiv,
+        CipherSuite::ChaCha20Poly1305,
+    ];
+
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u8) -> Result<Self> {
+        match CipherSuite::ALL.into_iter().find(|suite| suite.id() == id) {
+            Some(suite) => Ok(suite),
+            None => {
+                let reason = format!("unsupported cipher suite (id={id})");
+                error!("from_id(): {reason}");
+                Err(Abe4Error::Serialization(reason))
+            }
+        }
+    }
+
+    /// Name of the suite, also used to domain-separate the derived keys.
+    pub fn name(self) -> &'static str {
+        match self {
+            CipherSuite::Aes128Gcm => "aes-gcm-128",
+            CipherSuite::Aes256Gcm => "aes-gcm-256",
+            CipherSuite::Aes256GcmSiv => "aes-gcm-siv-256",
+            CipherSuite::ChaCha20Poly1305 => "chacha20-poly1305",
+        }
+    }
+
+    pub(crate) fn key_len(self) -> usize {
+        match self {
+            CipherSuite::Aes128Gcm => 16,
+            CipherSuite::Aes256Gcm | CipherSuite::Aes256GcmSiv | CipherSuite::ChaCha20Poly1305 => {
+                32
+            }
+        }
+    }
+}
+
+/// AEAD instance for one of the supported suites.
+pub(crate) enum SymCipher {
+    Aes128Gcm(Aes128Gcm),
+    Aes256Gcm(Aes256Gcm),
+    Aes256GcmSiv(Aes256GcmSiv),
+    ChaCha20Poly1305(ChaCha20Poly1305),
+}
+
+impl SymCipher {
+    /// Derive a symmetric key from an element in Gt.
+    ///
+    /// gt is, precisely, what we get after a successful call to encrypt of a
+    /// CP-ABEKEM scheme (i.e. the scheme we implement in the `scheme` module).
+    ///
+    /// # Arguments
+    ///
+    /// * `suite`: The cipher suite to instantiate.
+    /// * `gt`: The encapsulated key.
+    /// * `label`: A label to separate keys used for different purposes. The
+    ///   HKDF info string is `<label>-<suite name>`.
+    pub(crate) fn from_gt(suite: CipherSuite, gt: &Gt, label: &str) -> Result<Self> {
+        let mut gt_bytes = Vec::new();
+        // This should never fail for a valid group element
+        gt.serialize_compressed(&mut gt_bytes)
+            .map_err(|e| Abe4Error::Serialization(format!("Gt serialization failed: {e}")))?;
+
+        let hk = Hkdf::<Sha256>::new(Some(ABE4_KDF_SALT), &gt_bytes);
+
+        let info = format!("{label}-{}", suite.name());
+        let mut key = [0u8; 32];
+        let key_len = suite.key_len();
+        hk.expand(info.as_bytes(), &mut key[..key_len])
+            .map_err(|e| Abe4Error::Aead(format!("HKDF expand failed: {e}")))?;
+
+        // gt_bytes only holds public data, no need to zeroize, but we could:
+        gt_bytes.zeroize();
+
+        let cipher = Self::from_key(suite, &key[..key_len]);
+
+        // Zeroize key material.
+        key.zeroize();
+
+        cipher
+    }
+
+    /// Instantiate `suite` with a raw key of `suite.key_len()` bytes.
+    pub(crate) fn from_key(suite: CipherSuite, key_bytes: &[u8]) -> Result<Self> {
+        let cipher = match suite {
+            CipherSuite::Aes128Gcm => Aes128Gcm::new_from_slice(key_bytes).map(Self::Aes128Gcm),
+            CipherSuite::Aes256Gcm => Aes256Gcm::new_from_slice(key_bytes).map(Self::Aes256Gcm),
+            CipherSuite::Aes256GcmSiv => {
+                Aes256GcmSiv::new_from_slice(key_bytes).map(Self::Aes256GcmSiv)
+            }
+            CipherSuite::ChaCha20Poly1305 => {
+                ChaCha20Poly1305::new_from_slice(key_bytes).map(Self::ChaCha20Poly1305)
+            }
+        };
+        cipher.map_err(|e| Abe4Error::Aead(format!("invalid key length: {e}")))
+    }
+
+    pub(crate) fn encrypt(&self, nonce: &[u8; NONCE_LEN], payload: Payload) -> Result<Vec<u8>> {
+        let ct = match self {
+            SymCipher::Aes128Gcm(c) => c.encrypt(nonce.into(), payload),
+            SymCipher::Aes256Gcm(c) => c.encrypt(nonce.into(), payload),
+            SymCipher::Aes256GcmSiv(c) => c.encrypt(nonce.into(), payload),
+            SymCipher::ChaCha20Poly1305(c) => c.encrypt(nonce.into(), payload),
+        };
+        ct.map_err(|e| {
+            let reason = format!("error running AEAD encryption (error={e:?})");
+            error!("encrypt(): {reason}");
+            Abe4Error::Aead(reason)
+        })
+    }
+
+    pub(crate) fn decrypt(&self, nonce: &[u8; NONCE_LEN], payload: Payload) -> Result<Vec<u8>> {
+        let pt = match self {
+            SymCipher::Aes128Gcm(c) => c.decrypt(nonce.into(), payload),
+            SymCipher::Aes256Gcm(c) => c.decrypt(nonce.into(), payload),
+            SymCipher::Aes256GcmSiv(c) => c.decrypt(nonce.into(), payload),
+            SymCipher::ChaCha20Poly1305(c) => c.decrypt(nonce.into(), payload),
+        };
+        pt.map_err(|e| {
+            let reason = format!("error running AEAD decryption (error={e:?})");
+            error!("decrypt(): {reason}");
+            Abe4Error::Aead(reason)
+        })
+    }
+}
+
+#[test]
+fn test_cipher_suite_ids() {
+    for suite in CipherSuite::ALL {
+        assert_eq!(CipherSuite::from_id(suite.id()).unwrap(), suite);
+    }
+    assert!(CipherSuite::from_id(0).is_err());
+    assert_eq!(CipherSuite::default(), CipherSuite::Aes128Gcm);
+}
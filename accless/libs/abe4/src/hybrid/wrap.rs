@@ -0,0 +1,340 @@
+//! Key-wrapped layout for the symmetric ciphertext of a hybrid ciphertext.
+//!
+//! In the default layout, the payload is encrypted directly under the key
+//! derived from the `Gt` element that the CP-ABE KEM encapsulates, so moving
+//! a ciphertext to another policy means re-encrypting the payload. In the
+//! key-wrapped layout, the payload is encrypted under a random data key, and
+//! only the data key is encrypted under the key derived from `Gt`:
+//!
+//! ```text
+//! suite        u8       suite id, with the `WRAPPED_FLAG` bit set
+//! version      u8       version of the binding, see `bind_aad`
+//! wrap nonce   12 bytes
+//! wrapped key  AEAD of the data key, bound to the policy and `abe_ct`
+//! nonce        12 bytes
+//! payload      AEAD of the plaintext under the data key, with the caller's AAD
+//! ```
+//!
+//! Only the wrapped key is bound to the policy and `abe_ct` (see `bind_aad`).
+//! The payload is authenticated with the caller's AAD alone, so that it can
+//! be copied from one encapsulation to the next. It is still tied to the
+//! header through the data key, which is random for each ciphertext and is
+//! only shared by its re-encapsulations.
+//!
+//! `reencapsulate_hybrid` moves a ciphertext to a new policy (e.g. one that
+//! requires a newer epoch, see `Policy::require_epoch`) by re-wrapping the data
+//! key, and copies the payload as is. Ciphertexts in the other layouts are
+//! migrated to this one the first time they are re-encapsulated.
+//!
+//! The data key does not change. Any node that decrypted the ciphertext
+//! before, including one whose key has since been revoked, may have kept the
+//! data key, and can still decrypt the payload of every re-encapsulation.
+//! Cutting such a node off takes a fresh encryption of the plaintext, e.g.
+//! with `encrypt_hybrid_wrapped`.
+
+use crate::{
+    Ciphertext, Gt, MPK, Policy, PreparedUsk, Tau, decrypt_prepared, encrypt,
+    error::{Abe4Error, Result},
+    hybrid::{
+        BINDING_VERSION, CipherSuite, HybridCiphertext, bind_aad, check_binding_version,
+        suite::{NONCE_LEN, SymCipher},
+        sym_decrypt_with_suite,
+    },
+};
+use aes_gcm::aead::Payload;
+use ark_std::rand::{CryptoRng, RngCore};
+use log::error;
+use zeroize::Zeroizing;
+
+/// Bit set in the first byte of a key-wrapped symmetric ciphertext, on top of
+/// the suite id.
+pub(crate) const WRAPPED_FLAG: u8 = 0x80;
+const ABE4_WRAP_KDF_LABEL: &str = "accless-abe4-wrap";
+const TAG_LEN: usize = 16;
+
+/// Whether `sym_ct` uses the key-wrapped layout.
+pub(crate) fn is_wrapped(sym_ct: &[u8]) -> bool {
+    sym_ct.first().is_some_and(|id| id & WRAPPED_FLAG != 0)
+}
+
+/// Length of the header of a key-wrapped symmetric ciphertext, i.e. everything
+/// up to the payload nonce.
+fn header_len(suite: CipherSuite) -> usize {
+    2 + NONCE_LEN + suite.key_len() + TAG_LEN
+}
+
+/// Splits a key-wrapped symmetric ciphertext into its suite, its header, and
+/// its payload (nonce || ciphertext + tag).
+fn split_wrapped(sym_ct: &[u8]) -> Result<(CipherSuite, &[u8], &[u8])> {
+    let id = match sym_ct.first() {
+        Some(id) if id & WRAPPED_FLAG != 0 => id & !WRAPPED_FLAG,
+        _ => {
+            let reason = "symmetric ciphertext is not key-wrapped";
+            error!("split_wrapped(): {reason}");
+            return Err(Abe4Error::InvalidInput(String::from(reason)));
+        }
+    };
+    let suite = CipherSuite::from_id(id)?;
+    if sym_ct.len() < header_len(suite) + NONCE_LEN {
+        let reason = "ciphertext too short";
+        error!("split_wrapped(): {reason}");
+        return Err(Abe4Error::Serialization(String::from(reason)));
+    }
+    check_binding_version(sym_ct[1])?;
+    let (header, payload) = sym_ct.split_at(header_len(suite));
+    Ok((suite, header, payload))
+}
+
+/// Encrypts `data_key` under a key derived from `gt`.
+///
+/// # Returns
+///
+/// The header of a key-wrapped symmetric ciphertext: suite id | flag (1 byte)
+/// || binding version (1 byte) || nonce (12 bytes) || wrapped key + tag.
+fn wrap_key<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    gt: &Gt,
+    suite: CipherSuite,
+    data_key: &[u8],
+    bound_aad: &[u8],
+) -> Result<Vec<u8>> {
+    let cipher = SymCipher::from_gt(suite, gt, ABE4_WRAP_KDF_LABEL)?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let payload = Payload {
+        msg: data_key,
+        aad: bound_aad,
+    };
+    let mut wrapped = cipher.encrypt(&nonce, payload)?;
+
+    let mut out = Vec::with_capacity(header_len(suite));
+    out.push(suite.id() | WRAPPED_FLAG);
+    out.push(BINDING_VERSION);
+    out.extend_from_slice(&nonce);
+    out.append(&mut wrapped);
+    Ok(out)
+}
+
+/// Decrypts the data key in the `header` of a key-wrapped symmetric
+/// ciphertext, with a key derived from `gt`.
+fn unwrap_key(
+    gt: &Gt,
+    suite: CipherSuite,
+    header: &[u8],
+    bound_aad: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(&header[2..2 + NONCE_LEN]);
+    let cipher = SymCipher::from_gt(suite, gt, ABE4_WRAP_KDF_LABEL)?;
+    let payload = Payload {
+        msg: &header[2 + NONCE_LEN..],
+        aad: bound_aad,
+    };
+    cipher.decrypt(&nonce, payload).map(Zeroizing::new)
+}
+
+/// Encrypts `plaintext` under a fresh data key, wrapped under a key derived
+/// from `gt`.
+///
+/// # Arguments
+///
+/// * `bound_aad`: The associated data of the wrapped key, see `bind_aad`.
+/// * `aad`: The associated data of the payload.
+fn sym_encrypt_wrapped<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    gt: &Gt,
+    suite: CipherSuite,
+    plaintext: &[u8],
+    bound_aad: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let mut data_key = Zeroizing::new(vec![0u8; suite.key_len()]);
+    rng.fill_bytes(&mut data_key);
+    let mut out = wrap_key(rng, gt, suite, &data_key, bound_aad)?;
+
+    let cipher = SymCipher::from_key(suite, &data_key)?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill_bytes(&mut nonce);
+    let payload = Payload {
+        msg: plaintext,
+        aad,
+    };
+    let mut ct = cipher.encrypt(&nonce, payload)?;
+    out.extend_from_slice(&nonce);
+    out.append(&mut ct);
+    Ok(out)
+}
+
+/// Decrypts a key-wrapped `sym_ct`, whose data key is wrapped under a key
+/// derived from `gt`.
+pub(crate) fn sym_decrypt_wrapped(
+    gt: &Gt,
+    sym_ct: &[u8],
+    bound_aad: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    let (suite, header, payload) = split_wrapped(sym_ct)?;
+    let data_key = unwrap_key(gt, suite, header, bound_aad)?;
+    let cipher = SymCipher::from_key(suite, &data_key)?;
+    let (nonce_bytes, ct_bytes) = payload.split_at(NONCE_LEN);
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(nonce_bytes);
+    let payload = Payload { msg: ct_bytes, aad };
+    cipher.decrypt(&nonce, payload)
+}
+
+/// # Description
+///
+/// Hybrid CP-ABE encryption with the key-wrapped layout.
+///
+/// The result is decrypted with `decrypt_hybrid`, like any other hybrid
+/// ciphertext, but its payload is encrypted under a random data key that is
+/// wrapped under the encapsulated key. This makes the ciphertext eligible for
+/// `reencapsulate_hybrid`.
+///
+/// # Arguments
+///
+/// * `rng`: A random number generator.
+/// * `mpk`: The master public key.
+/// * `policy`: The policy to encrypt under.
+/// * `suite`: The symmetric cipher suite.
+/// * `plaintext`: The data to encrypt.
+/// * `aad`: Associated data bound to the symmetric ciphertext.
+pub fn encrypt_hybrid_wrapped<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    mpk: &MPK,
+    policy: &Policy,
+    suite: CipherSuite,
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<HybridCiphertext> {
+    let tau = Tau::new(policy);
+    let (gt, abe_ct) = encrypt(&mut *rng, mpk, policy, &tau)?;
+    let bound_aad = bind_aad(policy, &abe_ct, aad)?;
+    let sym_ct = sym_encrypt_wrapped(rng, &gt, suite, plaintext, &bound_aad, aad)?;
+    Ok(HybridCiphertext::new(abe_ct, sym_ct))
+}
+
+/// # Description
+///
+/// Re-encapsulates a key-wrapped hybrid ciphertext to a new policy.
+///
+/// The data key is unwrapped with `usk`, which must satisfy the current
+/// policy, and wrapped under a fresh encapsulation for `new_policy`. The
+/// payload is copied as is, so the cost does not depend on its size.
+/// Typically, `new_policy` is the current policy with a newer minimum epoch
+/// (see `Policy::require_epoch`), so that keys issued before that epoch can no
+/// longer decrypt the new ciphertext.
+///
+/// A ciphertext in another layout (e.g. from `encrypt_hybrid`) is decrypted
+/// with `usk` instead, and its payload re-encrypted in the key-wrapped layout,
+/// with the cipher suite it was encrypted with, so that later
+/// re-encapsulations are cheap.
+///
+/// This is not a policy update by the data owner: there is no owner
+/// capability, and the caller must be able to decrypt `ct`. The header of
+/// `sym_ct` is rewritten, so only the payload, and not all of `sym_ct`, stays
+/// unchanged. The data key is kept, so any node that decrypted `ct` before
+/// can still decrypt the new ciphertext, and copies of `ct` stay readable
+/// with the keys that could read it (see the module documentation).
+///
+/// # Arguments
+///
+/// * `rng`: A random number generator.
+/// * `usk`: A user secret key that satisfies `policy`, prepared with the GID it
+///   was issued for.
+/// * `mpk`: The master public key.
+/// * `policy`: The policy `ct` is encrypted under.
+/// * `ct`: The ciphertext, preferably encrypted with `encrypt_hybrid_wrapped`.
+/// * `new_policy`: The policy to re-encapsulate under.
+/// * `aad`: The associated data `ct` was encrypted with. It stays bound to the
+///   new ciphertext.
+///
+/// # Returns
+///
+/// The key-wrapped ciphertext under `new_policy`, or an error if `usk` cannot
+/// decrypt `ct`.
+pub fn reencapsulate_hybrid<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    usk: &PreparedUsk,
+    mpk: &MPK,
+    policy: &Policy,
+    ct: &HybridCiphertext,
+    new_policy: &Policy,
+    aad: &[u8],
+) -> Result<HybridCiphertext> {
+    let tau = Tau::new(policy);
+    let gt = decrypt_prepared(usk, usk.iota(), &tau, policy, &ct.abe_ct).inspect_err(|e| {
+        error!("reencapsulate_hybrid(): CP-ABE decryption failed (error={e})");
+    })?;
+
+    // Legacy ciphertexts start with a random nonce, whose first byte may carry
+    // `WRAPPED_FLAG`, so only trust the flag once the data key unwraps.
+    let bound_aad = bind_aad(policy, &ct.abe_ct, aad)?;
+    let unwrapped = split_wrapped(&ct.sym_ct).and_then(|(suite, header, payload)| {
+        let data_key = unwrap_key(&gt, suite, header, &bound_aad)?;
+        Ok((suite, data_key, payload))
+    });
+    let Ok((suite, data_key, payload)) = unwrapped else {
+        // Migrate other layouts by re-encrypting the payload.
+        let (suite, plaintext) = sym_decrypt_with_suite(&gt, &ct.sym_ct, policy, &ct.abe_ct, aad)?;
+        let plaintext = Zeroizing::new(plaintext);
+        return encrypt_hybrid_wrapped(rng, mpk, new_policy, suite, &plaintext, aad);
+    };
+
+    // Wrap the data key under a fresh encapsulation for the new policy.
+    let new_tau = Tau::new(new_policy);
+    let (new_gt, abe_ct): (Gt, Ciphertext) = encrypt(&mut *rng, mpk, new_policy, &new_tau)?;
+    let bound_aad = bind_aad(new_policy, &abe_ct, aad)?;
+    let mut sym_ct = wrap_key(rng, &new_gt, suite, &data_key, &bound_aad)?;
+    sym_ct.extend_from_slice(payload);
+    Ok(HybridCiphertext::new(abe_ct, sym_ct))
+}
+
+#[test]
+fn test_reencapsulate_legacy_layout() {
+    use crate::{
+        Iota, UserAttribute,
+        hybrid::{ABE4_KDF_LABEL, decrypt_hybrid},
+        keygen, setup,
+    };
+    use ark_std::rand::{SeedableRng, rngs::StdRng};
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let user_attrs = vec![UserAttribute::new("A", "a", "0")];
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &vec!["A"]);
+    let usk = keygen(&mut rng, "gid", &msk, &user_attrs, &iota).unwrap();
+    let usk = PreparedUsk::new(usk, "gid").unwrap();
+    let policy = Policy::parse("A.a:0").unwrap();
+    let new_policy = Policy::parse("A.a:0 | A.b:0").unwrap();
+
+    // Legacy nonces are random, so their first byte may carry `WRAPPED_FLAG`.
+    for first in [0x02, 0x81, 0x84, 0xff] {
+        let (gt, abe_ct) = encrypt(&mut rng, &mpk, &policy, &Tau::new(&policy)).unwrap();
+        let mut nonce = [7u8; NONCE_LEN];
+        nonce[0] = first;
+        let cipher = SymCipher::from_gt(CipherSuite::Aes128Gcm, &gt, ABE4_KDF_LABEL).unwrap();
+        let payload = Payload {
+            msg: b"legacy payload",
+            aad: b"aad",
+        };
+        let mut sym_ct = nonce.to_vec();
+        sym_ct.extend(cipher.encrypt(&nonce, payload).unwrap());
+        let ct = HybridCiphertext::new(abe_ct, sym_ct);
+
+        let new_ct =
+            reencapsulate_hybrid(&mut rng, &usk, &mpk, &policy, &ct, &new_policy, b"aad").unwrap();
+        assert_eq!(new_ct.sym_ct[0], CipherSuite::Aes128Gcm.id() | WRAPPED_FLAG);
+        let pt = decrypt_hybrid(
+            usk.usk(),
+            "gid",
+            &new_policy,
+            &new_ct.abe_ct,
+            &new_ct.sym_ct,
+            b"aad",
+        )
+        .unwrap();
+        assert_eq!(pt, b"legacy payload");
+    }
+}
@@ -1,4 +1,6 @@
-mod curve;
+pub mod capi;
+pub mod curve;
+pub mod error;
 mod hashing;
 pub mod hybrid;
 pub mod policy;
@@ -6,18 +8,29 @@ pub mod scheme;
 
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use base64::engine::{Engine as _, general_purpose};
-pub use curve::Gt;
-pub use hybrid::{decrypt_hybrid, encrypt_hybrid};
-pub use policy::{Policy, UserAttribute};
-pub use scheme::{decrypt, encrypt, iota, keygen, setup, tau};
+pub use curve::{Curve, DefaultCurve, Gt};
+pub use error::Abe4Error;
+use hybrid::HybridCiphertext;
+pub use hybrid::{
+    CipherSuite, HybridContainer, HybridDecryptReader, HybridEncryptWriter, STREAM_SEGMENT_SIZE,
+    decrypt_hybrid, decrypt_hybrid_container, decrypt_hybrid_many, decrypt_hybrid_transformed,
+    encrypt_hybrid, encrypt_hybrid_container, encrypt_hybrid_many, encrypt_hybrid_with_suite,
+    encrypt_hybrid_wrapped, reencapsulate_hybrid, transform_hybrid,
+};
+pub use policy::{EPOCH_LABEL, Evaluation, Policy, PolicyDiagnostic, UserAttribute};
+pub use scheme::{
+    decrypt, decrypt_many, decrypt_prepared, decrypt_transformed, delegate, encrypt, encrypt_many,
+    encrypt_prepared, epoch_gid, iota, keygen, keygen_with_epoch,
+    prepared::{PreparedMpk, PreparedPolicy, PreparedUsk},
+    setup, setup_with_curve, tau, threshold, transform, transform_keygen,
+};
 use scheme::{
-    iota::Iota,
+    iota::{Iota, sort_user_attrs},
     tau::Tau,
-    types::{Ciphertext, MPK, MSK, USK},
+    types::{Ciphertext, MPK, MSK, PartialMPK, PartialUSK, USK},
 };
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeSet,
     ffi::{CStr, CString},
     os::raw::c_char,
 };
@@ -58,12 +71,7 @@ pub unsafe extern "C" fn policy_authorities_abe4(policy_str: *const c_char) -> *
         }
     };
 
-    let mut authorities = BTreeSet::new();
-    for idx in 0..policy.len() {
-        authorities.insert(policy.get(idx).0.authority().to_string());
-    }
-
-    let authorities: Vec<String> = authorities.into_iter().collect();
+    let authorities = policy.authorities();
     let json = match serde_json::to_string(&authorities) {
         Ok(s) => s,
         Err(e) => {
@@ -87,6 +95,121 @@ pub unsafe extern "C" fn policy_authorities_abe4(policy_str: *const c_char) -> *
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct EvaluatePolicyOutput {
+    satisfied: bool,
+    literals: Vec<String>,
+    missing: Vec<String>,
+}
+
+/// # Description
+///
+/// FFI wrapper to check whether a set of user attributes satisfies a policy.
+///
+/// This function does not compute any pairings. It is meant to be used before
+/// attempting a decryption, and to explain why a decryption would fail.
+///
+/// # Arguments
+///
+/// * `policy_str`: A C-style string containing the policy.
+/// * `user_attrs_json`: A C-style string containing a JSON array of user
+///   attributes.
+///
+/// # Returns
+///
+/// A C-style string containing a JSON object with the following fields:
+/// - `satisfied`: whether the user attributes satisfy the policy.
+/// - `literals`: if satisfied, the literals used to satisfy the policy.
+/// - `missing`: if not satisfied, the sub-expressions of the policy that are
+///   not satisfied.
+///
+/// Returns a null pointer on error.
+#[allow(clippy::missing_safety_doc)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn evaluate_policy_abe4(
+    policy_str: *const c_char,
+    user_attrs_json: *const c_char,
+) -> *mut c_char {
+    let policy_str = match unsafe { CStr::from_ptr(policy_str).to_str() } {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert policy C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let policy = match Policy::parse(policy_str) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to parse policy: {:?}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let user_attrs_str = match unsafe { CStr::from_ptr(user_attrs_json).to_str() } {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert user attributes C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let user_attrs: Vec<UserAttribute> = match serde_json::from_str(user_attrs_str) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to deserialize user attributes from JSON: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let output = match policy.evaluate(&user_attrs) {
+        Evaluation::Satisfied(literals) => EvaluatePolicyOutput {
+            satisfied: true,
+            literals: literals
+                .into_iter()
+                .map(|(ua, is_neg)| format!("{}{:?}", if is_neg { "!" } else { "" }, ua))
+                .collect(),
+            missing: Vec::new(),
+        },
+        Evaluation::Unsatisfied(missing) => EvaluatePolicyOutput {
+            satisfied: false,
+            literals: Vec::new(),
+            missing: missing.iter().map(|p| format!("{:?}", p)).collect(),
+        },
+    };
+
+    let json = match serde_json::to_string(&output) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to serialize policy evaluation to JSON: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    match CString::new(json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to create CString for policy evaluation: {}",
+                e
+            );
+            std::ptr::null_mut()
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct SetupOutput {
     msk: String,
@@ -315,7 +438,7 @@ pub unsafe extern "C" fn keygen_partial_abe4(
         }
     };
 
-    let user_attrs: Vec<UserAttribute> = match serde_json::from_str(user_attrs_str) {
+    let mut user_attrs: Vec<UserAttribute> = match serde_json::from_str(user_attrs_str) {
         Ok(v) => v,
         Err(e) => {
             eprintln!(
@@ -326,12 +449,21 @@ pub unsafe extern "C" fn keygen_partial_abe4(
         }
     };
 
+    // Number the values of each label in the order `USK::get_user_attributes`
+    // returns them in, which is what decryption over FFI rebuilds `Iota` from.
+    sort_user_attrs(&mut user_attrs);
     let iota = Iota::new(&user_attrs);
     let mut rng = ark_std::rand::thread_rng();
 
     let user_attrs_refs: Vec<&UserAttribute> = user_attrs.iter().collect();
     let partial_usk =
-        scheme::keygen_partial(&mut rng, gid_str, &partial_msk, &user_attrs_refs, &iota);
+        match scheme::keygen_partial(&mut rng, gid_str, &partial_msk, &user_attrs_refs, &iota) {
+            Ok(k) => k,
+            Err(e) => {
+                eprintln!("[accless-abe4-rs] Failed to generate PartialUSK: {}", e);
+                return std::ptr::null_mut();
+            }
+        };
 
     let mut partial_usk_bytes = Vec::new();
     if partial_usk
@@ -404,7 +536,7 @@ pub unsafe extern "C" fn keygen_abe4(
         }
     };
 
-    let user_attrs: Vec<UserAttribute> = match serde_json::from_str(user_attrs_str) {
+    let mut user_attrs: Vec<UserAttribute> = match serde_json::from_str(user_attrs_str) {
         Ok(v) => v,
         Err(e) => {
             eprintln!(
@@ -415,6 +547,9 @@ pub unsafe extern "C" fn keygen_abe4(
         }
     };
 
+    // Number the values of each label in the order `USK::get_user_attributes`
+    // returns them in, which is what decryption over FFI rebuilds `Iota` from.
+    sort_user_attrs(&mut user_attrs);
     let iota = Iota::new(&user_attrs);
     let mut rng = ark_std::rand::thread_rng();
 
@@ -429,7 +564,13 @@ pub unsafe extern "C" fn keygen_abe4(
         }
     };
 
-    let usk = keygen(&mut rng, gid_str, &msk, &user_attrs, &iota);
+    let usk = match keygen(&mut rng, gid_str, &msk, &user_attrs, &iota) {
+        Ok(k) => k,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to generate USK: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
 
     let mut usk_bytes = Vec::new();
     if usk.serialize_compressed(&mut usk_bytes).is_err() {
@@ -447,6 +588,239 @@ pub unsafe extern "C" fn keygen_abe4(
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct VerifyKeyOutput {
+    valid: bool,
+    error: String,
+}
+
+impl VerifyKeyOutput {
+    fn into_raw(result: Result<(), Abe4Error>) -> *mut c_char {
+        let output = VerifyKeyOutput {
+            valid: result.is_ok(),
+            error: result.err().map(|e| e.to_string()).unwrap_or_default(),
+        };
+        let json = match serde_json::to_string(&output) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!(
+                    "[accless-abe4-rs] Failed to serialize key verification to JSON: {}",
+                    e
+                );
+                return std::ptr::null_mut();
+            }
+        };
+
+        match CString::new(json) {
+            Ok(s) => s.into_raw(),
+            Err(e) => {
+                eprintln!(
+                    "[accless-abe4-rs] Failed to create CString for key verification: {}",
+                    e
+                );
+                std::ptr::null_mut()
+            }
+        }
+    }
+}
+
+/// # Description
+///
+/// FFI wrapper to verify a partial User Secret Key (USK) against the partial
+/// Master Public Key (MPK) of the authority that issued it.
+///
+/// This lets the recipient of a partial USK check that it is well-formed for
+/// its GID and attributes when it receives it, instead of finding out when a
+/// decryption fails.
+///
+/// # Arguments
+///
+/// * `gid_cstr`: A C-style string containing the global identifier of the user.
+/// * `partial_mpk_b64_cstr`: A C-style string containing the base64-encoded
+///   partial Master Public Key.
+/// * `partial_usk_b64_cstr`: A C-style string containing the base64-encoded
+///   partial User Secret Key.
+/// * `user_attrs_json`: A C-style string containing a JSON array of user
+///   attributes, in the order they were given to `keygen_partial_abe4`.
+///
+/// # Returns
+///
+/// A C-style string containing a JSON object with two fields:
+/// - `valid`: whether the key verifies.
+/// - `error`: if not valid, the reason why.
+///
+/// Returns a null pointer if an argument is malformed.
+#[allow(clippy::missing_safety_doc)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn verify_partial_usk_abe4(
+    gid_cstr: *const c_char,
+    partial_mpk_b64_cstr: *const c_char,
+    partial_usk_b64_cstr: *const c_char,
+    user_attrs_json: *const c_char,
+) -> *mut c_char {
+    let gid_str = match unsafe { CStr::from_ptr(gid_cstr).to_str() } {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert GID C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+    let Some(partial_mpk) = key_from_b64::<PartialMPK>(
+        unsafe { CStr::from_ptr(partial_mpk_b64_cstr) },
+        "PartialMPK",
+    ) else {
+        return std::ptr::null_mut();
+    };
+    let Some(partial_usk) = key_from_b64::<PartialUSK>(
+        unsafe { CStr::from_ptr(partial_usk_b64_cstr) },
+        "PartialUSK",
+    ) else {
+        return std::ptr::null_mut();
+    };
+    let Some(user_attrs) = user_attrs_from_json(unsafe { CStr::from_ptr(user_attrs_json) }) else {
+        return std::ptr::null_mut();
+    };
+
+    VerifyKeyOutput::into_raw(partial_usk.verify(&partial_mpk, gid_str, &user_attrs))
+}
+
+/// # Description
+///
+/// FFI wrapper to verify a User Secret Key (USK) against the Master Public
+/// Key (MPK) of the authorities that issued it.
+///
+/// On top of verifying each partial key, like `verify_partial_usk_abe4`, this
+/// checks that all the partial keys were issued for the same GID.
+///
+/// # Arguments
+///
+/// * `gid_cstr`: A C-style string containing the global identifier of the user.
+/// * `mpk_b64_cstr`: A C-style string containing the base64-encoded Master
+///   Public Key.
+/// * `usk_b64_cstr`: A C-style string containing the base64-encoded User Secret
+///   Key.
+/// * `user_attrs_json`: A C-style string containing a JSON array of user
+///   attributes, in the order they were given to `keygen_abe4`.
+///
+/// # Returns
+///
+/// A C-style string containing a JSON object with two fields:
+/// - `valid`: whether the key verifies.
+/// - `error`: if not valid, the reason why.
+///
+/// Returns a null pointer if an argument is malformed.
+#[allow(clippy::missing_safety_doc)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn verify_usk_abe4(
+    gid_cstr: *const c_char,
+    mpk_b64_cstr: *const c_char,
+    usk_b64_cstr: *const c_char,
+    user_attrs_json: *const c_char,
+) -> *mut c_char {
+    let gid_str = match unsafe { CStr::from_ptr(gid_cstr).to_str() } {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert GID C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+    let Some(mpk) = key_from_b64::<MPK>(unsafe { CStr::from_ptr(mpk_b64_cstr) }, "MPK") else {
+        return std::ptr::null_mut();
+    };
+    let Some(usk) = key_from_b64::<USK>(unsafe { CStr::from_ptr(usk_b64_cstr) }, "USK") else {
+        return std::ptr::null_mut();
+    };
+    let Some(user_attrs) = user_attrs_from_json(unsafe { CStr::from_ptr(user_attrs_json) }) else {
+        return std::ptr::null_mut();
+    };
+
+    VerifyKeyOutput::into_raw(usk.verify(&mpk, gid_str, &user_attrs))
+}
+
+/// # Description
+///
+/// FFI wrapper for user secret key delegation.
+///
+/// This function derives, from a base64-encoded user secret key, a fresh key
+/// that only holds the given subset of its attributes, so that a function can
+/// hand an attenuated key to the functions it chains to.
+///
+/// # Arguments
+///
+/// * `gid_cstr`: A C-style string containing the global identifier of the user.
+/// * `mpk_b64_cstr`: A C-style string containing the base64-encoded Master
+///   Public Key.
+/// * `usk_b64_cstr`: A C-style string containing the base64-encoded User Secret
+///   Key to delegate.
+/// * `user_attrs_json`: A C-style string containing a JSON array of the user
+///   attributes to keep. For each of their labels, it must hold every value of
+///   the label that the key holds.
+///
+/// # Returns
+///
+/// A C-style string containing the base64-encoded delegated User Secret Key,
+/// or a null pointer on error.
+#[allow(clippy::missing_safety_doc)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn delegate_usk_abe4(
+    gid_cstr: *const c_char,
+    mpk_b64_cstr: *const c_char,
+    usk_b64_cstr: *const c_char,
+    user_attrs_json: *const c_char,
+) -> *mut c_char {
+    let gid_str = match unsafe { CStr::from_ptr(gid_cstr).to_str() } {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert GID C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+    let Some(mpk) = key_from_b64::<MPK>(unsafe { CStr::from_ptr(mpk_b64_cstr) }, "MPK") else {
+        return std::ptr::null_mut();
+    };
+    let Some(usk) = key_from_b64::<USK>(unsafe { CStr::from_ptr(usk_b64_cstr) }, "USK") else {
+        return std::ptr::null_mut();
+    };
+    let Some(user_attrs) = user_attrs_from_json(unsafe { CStr::from_ptr(user_attrs_json) }) else {
+        return std::ptr::null_mut();
+    };
+
+    // Keys cross the FFI without their `Iota`, so rebuild it from the key's
+    // attributes, as `decrypt_abe4` does.
+    let iota = Iota::new(&usk.get_user_attributes());
+    let mut rng = ark_std::rand::thread_rng();
+    let delegated = match delegate(&mut rng, &mpk, &usk, gid_str, &iota, &user_attrs) {
+        Ok(usk) => usk,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] delegate failed: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let mut usk_bytes = Vec::new();
+    if delegated.serialize_compressed(&mut usk_bytes).is_err() {
+        eprintln!("[accless-abe4-rs] Failed to serialize USK");
+        return std::ptr::null_mut();
+    }
+
+    match CString::new(general_purpose::STANDARD.encode(&usk_bytes)) {
+        Ok(s) => s.into_raw(),
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to create CString for USK: {}", e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct EncryptOutput {
     gt: String,
@@ -532,7 +906,13 @@ pub unsafe extern "C" fn encrypt_abe4(
     let tau = Tau::new(&policy);
     let mut rng = ark_std::rand::thread_rng();
 
-    let (gt, ct) = encrypt(&mut rng, &mpk, &policy, &tau);
+    let (gt, ct) = match encrypt(&mut rng, &mpk, &policy, &tau) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Encryption failed: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
 
     let mut gt_bytes = Vec::new();
     if gt.serialize_compressed(&mut gt_bytes).is_err() {
@@ -701,7 +1081,7 @@ pub unsafe extern "C" fn decrypt_abe4(
     let result = decrypt(&usk, gid_str, &iota, &tau, &policy, &ct);
 
     match result {
-        Some(gt) => {
+        Ok(gt) => {
             let mut gt_bytes = Vec::new();
             if gt.serialize_compressed(&mut gt_bytes).is_err() {
                 eprintln!("[accless-abe4-rs] Failed to serialize Gt");
@@ -716,8 +1096,8 @@ pub unsafe extern "C" fn decrypt_abe4(
                 }
             }
         }
-        None => {
-            eprintln!("[accless-abe4-rs] Decryption returned None");
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Decryption failed: {}", e);
             std::ptr::null_mut()
         }
     }
@@ -1098,3 +1478,696 @@ pub unsafe extern "C" fn decrypt_hybrid_abe4(
         }
     }
 }
+
+/// # Description
+///
+/// FFI wrapper for the hybrid CP-ABE encryption function with the key-wrapped
+/// layout (see `encrypt_hybrid_wrapped`), which can later be re-encapsulated
+/// to another policy with `reencapsulate_hybrid_abe4`.
+///
+/// # Arguments
+///
+/// * `mpk_b64`: A C-style string containing the base64-encoded master public
+///   key.
+/// * `policy_str`: A C-style string containing the policy string.
+/// * `plaintext_b64`: A C-style string containing the base64-encoded plaintext
+///   to encrypt.
+/// * `aad_b64`: A C-style string containing the base64-encoded AAD to bind to
+///   the symmetric encryption.
+///
+/// # Returns
+///
+/// A C-style string containing a JSON object with two fields:
+/// - `abe_ct`: The base64-encoded ABE ciphertext.
+/// - `sym_ct`: The base64-encoded symmetric ciphertext.
+///
+/// Returns a null pointer on error.
+#[allow(clippy::missing_safety_doc)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn encrypt_hybrid_wrapped_abe4(
+    mpk_b64: *const c_char,
+    policy_str: *const c_char,
+    plaintext_b64: *const c_char,
+    aad_b64: *const c_char,
+) -> *mut c_char {
+    let Some(mpk) = key_from_b64::<MPK>(unsafe { CStr::from_ptr(mpk_b64) }, "MPK") else {
+        return std::ptr::null_mut();
+    };
+    let Some(policy) = policy_from_cstr(unsafe { CStr::from_ptr(policy_str) }) else {
+        return std::ptr::null_mut();
+    };
+    let Some(plaintext) = bytes_from_b64(unsafe { CStr::from_ptr(plaintext_b64) }, "plaintext")
+    else {
+        return std::ptr::null_mut();
+    };
+    let Some(aad) = bytes_from_b64(unsafe { CStr::from_ptr(aad_b64) }, "AAD") else {
+        return std::ptr::null_mut();
+    };
+
+    let mut rng = ark_std::rand::thread_rng();
+    match encrypt_hybrid_wrapped(
+        &mut rng,
+        &mpk,
+        &policy,
+        CipherSuite::default(),
+        &plaintext,
+        &aad,
+    ) {
+        Ok(hybrid_ct) => hybrid_output(&hybrid_ct),
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] encrypt_hybrid_wrapped failed: {}", e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// # Description
+///
+/// FFI wrapper to re-encapsulate a hybrid ciphertext to a new policy (see
+/// `reencapsulate_hybrid`), e.g. when the template graph of a workflow
+/// changes.
+///
+/// This is not an owner-side policy update: the caller must hold a key that
+/// satisfies the current policy. The data key is kept, and the payload is
+/// only authenticated with the AAD, not with the new policy.
+///
+/// # Arguments
+///
+/// * `mpk_b64`: A C-style string containing the base64-encoded master public
+///   key.
+/// * `usk_b64`: A C-style string containing the base64-encoded user secret key
+///   that satisfies the current policy.
+/// * `gid`: A C-style string containing the global identifier of `usk`.
+/// * `policy_str`: A C-style string containing the current policy string.
+/// * `abe_ct_b64`: A C-style string containing the base64-encoded ABE
+///   ciphertext.
+/// * `sym_ct_b64`: A C-style string containing the base64-encoded symmetric
+///   ciphertext.
+/// * `new_policy_str`: A C-style string containing the new policy string.
+/// * `aad_b64`: A C-style string containing the base64-encoded AAD the
+///   ciphertext was encrypted with.
+///
+/// # Returns
+///
+/// A C-style string containing a JSON object with the `abe_ct` and `sym_ct` of
+/// the ciphertext under the new policy, as returned by
+/// `encrypt_hybrid_wrapped_abe4`, or a null pointer on error. For a key-wrapped
+/// ciphertext, only the header of `sym_ct` changes, and its payload is copied
+/// as is.
+#[allow(clippy::missing_safety_doc)]
+#[allow(clippy::too_many_arguments)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn reencapsulate_hybrid_abe4(
+    mpk_b64: *const c_char,
+    usk_b64: *const c_char,
+    gid: *const c_char,
+    policy_str: *const c_char,
+    abe_ct_b64: *const c_char,
+    sym_ct_b64: *const c_char,
+    new_policy_str: *const c_char,
+    aad_b64: *const c_char,
+) -> *mut c_char {
+    let Some(mpk) = key_from_b64::<MPK>(unsafe { CStr::from_ptr(mpk_b64) }, "MPK") else {
+        return std::ptr::null_mut();
+    };
+    let Some(usk) = key_from_b64::<USK>(unsafe { CStr::from_ptr(usk_b64) }, "USK") else {
+        return std::ptr::null_mut();
+    };
+    let gid_str = match unsafe { CStr::from_ptr(gid) }.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert GID C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+    let Some(policy) = policy_from_cstr(unsafe { CStr::from_ptr(policy_str) }) else {
+        return std::ptr::null_mut();
+    };
+    let Some(abe_ct) =
+        key_from_b64::<Ciphertext>(unsafe { CStr::from_ptr(abe_ct_b64) }, "ABE ciphertext")
+    else {
+        return std::ptr::null_mut();
+    };
+    let Some(sym_ct) = bytes_from_b64(unsafe { CStr::from_ptr(sym_ct_b64) }, "sym_ct") else {
+        return std::ptr::null_mut();
+    };
+    let Some(new_policy) = policy_from_cstr(unsafe { CStr::from_ptr(new_policy_str) }) else {
+        return std::ptr::null_mut();
+    };
+    let Some(aad) = bytes_from_b64(unsafe { CStr::from_ptr(aad_b64) }, "AAD") else {
+        return std::ptr::null_mut();
+    };
+
+    let usk = match PreparedUsk::new(usk, gid_str) {
+        Ok(usk) => usk,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to prepare USK: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+    let mut rng = ark_std::rand::thread_rng();
+    let hybrid_ct = HybridCiphertext::new(abe_ct, sym_ct);
+    match reencapsulate_hybrid(&mut rng, &usk, &mpk, &policy, &hybrid_ct, &new_policy, &aad) {
+        Ok(new_ct) => hybrid_output(&new_ct),
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] reencapsulate_hybrid failed: {}", e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// # Description
+///
+/// FFI wrapper for the hybrid CP-ABE encryption function that returns a
+/// self-describing container (see `HybridContainer`), using the default
+/// cipher suite.
+///
+/// # Arguments
+///
+/// * `mpk_b64`: A C-style string containing the base64-encoded master public
+///   key.
+/// * `policy_str`: A C-style string containing the policy string.
+/// * `plaintext_b64`: A C-style string containing the base64-encoded plaintext
+///   to encrypt.
+/// * `aad_b64`: A C-style string containing the base64-encoded AAD to bind to
+///   the symmetric encryption. The AAD is stored in the container.
+///
+/// # Returns
+///
+/// A C-style string containing the base64-encoded container, or a null pointer
+/// on error.
+#[allow(clippy::missing_safety_doc)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn encrypt_hybrid_container_abe4(
+    mpk_b64: *const c_char,
+    policy_str: *const c_char,
+    plaintext_b64: *const c_char,
+    aad_b64: *const c_char,
+) -> *mut c_char {
+    unsafe {
+        encrypt_hybrid_container_with_suite_abe4(
+            mpk_b64,
+            policy_str,
+            CipherSuite::default().id(),
+            plaintext_b64,
+            aad_b64,
+        )
+    }
+}
+
+/// # Description
+///
+/// Same as `encrypt_hybrid_container_abe4`, but encrypts the plaintext with
+/// the given symmetric cipher suite.
+///
+/// # Arguments
+///
+/// * `mpk_b64`: A C-style string containing the base64-encoded master public
+///   key.
+/// * `policy_str`: A C-style string containing the policy string.
+/// * `suite_id`: The identifier of the symmetric cipher suite (see
+///   `CipherSuite`).
+/// * `plaintext_b64`: A C-style string containing the base64-encoded plaintext
+///   to encrypt.
+/// * `aad_b64`: A C-style string containing the base64-encoded AAD to bind to
+///   the symmetric encryption. The AAD is stored in the container.
+///
+/// # Returns
+///
+/// A C-style string containing the base64-encoded container, or a null pointer
+/// on error.
+#[allow(clippy::missing_safety_doc)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn encrypt_hybrid_container_with_suite_abe4(
+    mpk_b64: *const c_char,
+    policy_str: *const c_char,
+    suite_id: u8,
+    plaintext_b64: *const c_char,
+    aad_b64: *const c_char,
+) -> *mut c_char {
+    let mpk_b64_cstr = unsafe { CStr::from_ptr(mpk_b64) };
+    let policy_cstr = unsafe { CStr::from_ptr(policy_str) };
+    let plaintext_cstr = unsafe { CStr::from_ptr(plaintext_b64) };
+    let aad_cstr = unsafe { CStr::from_ptr(aad_b64) };
+
+    let mpk_b64_str = match mpk_b64_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert MPK C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let mpk_bytes = match general_purpose::STANDARD.decode(mpk_b64_str) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to decode MPK from base64: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let mpk: MPK = match MPK::deserialize_compressed(&mpk_bytes[..]) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to deserialize MPK: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let policy_str = match policy_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert policy C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let policy = match Policy::parse(policy_str) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to parse policy: {:?}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let plaintext_b64_str = match plaintext_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert plaintext C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let plaintext = match general_purpose::STANDARD.decode(plaintext_b64_str) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to decode plaintext from base64: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let aad_b64_str = match aad_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert AAD C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let aad = match general_purpose::STANDARD.decode(aad_b64_str) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to decode AAD from base64: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let suite = match CipherSuite::from_id(suite_id) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to parse cipher suite: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let mut rng = ark_std::rand::thread_rng();
+    let container = match encrypt_hybrid_container(&mut rng, &mpk, &policy, suite, &plaintext, &aad)
+    {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] encrypt_hybrid_container failed: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let container_bytes = match container.to_bytes() {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to serialize container: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    match CString::new(general_purpose::STANDARD.encode(&container_bytes)) {
+        Ok(s) => s.into_raw(),
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to create CString for container: {}",
+                e
+            );
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// # Description
+///
+/// FFI wrapper for the hybrid CP-ABE decryption function that takes a
+/// self-describing container (see `HybridContainer`). The policy and the AAD
+/// are read from the container.
+///
+/// # Arguments
+///
+/// * `usk_b64`: A C-style string containing the base64-encoded user secret key.
+/// * `gid`: A C-style string containing the global identifier of the user.
+/// * `container_b64`: A C-style string containing the base64-encoded container.
+///
+/// # Returns
+///
+/// A C-style string containing the base64-encoded plaintext if decryption is
+/// successful, or a null pointer otherwise.
+#[allow(clippy::missing_safety_doc)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn decrypt_hybrid_container_abe4(
+    usk_b64: *const c_char,
+    gid: *const c_char,
+    container_b64: *const c_char,
+) -> *mut c_char {
+    let usk_b64_cstr = unsafe { CStr::from_ptr(usk_b64) };
+    let gid_cstr = unsafe { CStr::from_ptr(gid) };
+    let container_cstr = unsafe { CStr::from_ptr(container_b64) };
+
+    let usk_b64_str = match usk_b64_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert USK C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let usk_bytes = match general_purpose::STANDARD.decode(usk_b64_str) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to decode USK from base64: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let usk: USK = match USK::deserialize_compressed(&usk_bytes[..]) {
+        Ok(u) => u,
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to deserialize USK: {}", e);
+            return std::ptr::null_mut();
+        }
+    };
+
+    let gid_str = match gid_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert GID C string to Rust string: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    let container = match container_from_b64(container_cstr) {
+        Some(c) => c,
+        None => return std::ptr::null_mut(),
+    };
+
+    match decrypt_hybrid_container(&usk, gid_str, &container) {
+        Ok(pt) => {
+            let pt_b64 = general_purpose::STANDARD.encode(&pt);
+            match CString::new(pt_b64) {
+                Ok(s) => s.into_raw(),
+                Err(e) => {
+                    eprintln!(
+                        "[accless-abe4-rs] Failed to create CString for plaintext: {}",
+                        e
+                    );
+                    std::ptr::null_mut()
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] decrypt_hybrid_container failed: {}", e);
+            std::ptr::null_mut()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct HybridContainerInfo {
+    version: u8,
+    suite: u8,
+    policy: String,
+    authorities: Vec<String>,
+}
+
+/// # Description
+///
+/// Reads the metadata of a hybrid container without decrypting it.
+///
+/// # Arguments
+///
+/// * `container_b64`: A C-style string containing the base64-encoded container.
+///
+/// # Returns
+///
+/// A C-style string containing a JSON object with the following fields:
+/// - `version`: The container format version.
+/// - `suite`: The identifier of the symmetric cipher suite.
+/// - `policy`: The policy the container was encrypted under.
+/// - `authorities`: The authorities referenced in the policy.
+///
+/// Returns a null pointer if the container is malformed.
+#[allow(clippy::missing_safety_doc)]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hybrid_container_info_abe4(container_b64: *const c_char) -> *mut c_char {
+    let container_cstr = unsafe { CStr::from_ptr(container_b64) };
+    let container = match container_from_b64(container_cstr) {
+        Some(c) => c,
+        None => return std::ptr::null_mut(),
+    };
+
+    let info = HybridContainerInfo {
+        version: container.version,
+        suite: container.suite.id(),
+        policy: container.policy,
+        authorities: container.authorities,
+    };
+
+    let json = match serde_json::to_string(&info) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to serialize container info to JSON: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    match CString::new(json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to create CString for container info: {}",
+                e
+            );
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Decodes a base64-encoded container passed over FFI.
+fn container_from_b64(container_cstr: &CStr) -> Option<HybridContainer> {
+    let container_b64_str = match container_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert container C string to Rust string: {}",
+                e
+            );
+            return None;
+        }
+    };
+
+    let container_bytes = match general_purpose::STANDARD.decode(container_b64_str) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to decode container from base64: {}",
+                e
+            );
+            return None;
+        }
+    };
+
+    match HybridContainer::from_bytes(&container_bytes) {
+        Ok(c) => Some(c),
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to deserialize container: {}", e);
+            None
+        }
+    }
+}
+
+/// Decodes a base64-encoded key passed over FFI.
+fn key_from_b64<T: CanonicalDeserialize>(key_cstr: &CStr, name: &str) -> Option<T> {
+    let key_b64_str = match key_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert {} C string to Rust string: {}",
+                name, e
+            );
+            return None;
+        }
+    };
+
+    let key_bytes = match general_purpose::STANDARD.decode(key_b64_str) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to decode {} from base64: {}",
+                name, e
+            );
+            return None;
+        }
+    };
+
+    match T::deserialize_compressed(&key_bytes[..]) {
+        Ok(k) => Some(k),
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to deserialize {}: {}", name, e);
+            None
+        }
+    }
+}
+
+/// Parses a JSON array of user attributes passed over FFI.
+fn user_attrs_from_json(user_attrs_cstr: &CStr) -> Option<Vec<UserAttribute>> {
+    let user_attrs_str = match user_attrs_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert user attributes C string to Rust string: {}",
+                e
+            );
+            return None;
+        }
+    };
+
+    match serde_json::from_str(user_attrs_str) {
+        Ok(v) => Some(v),
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to parse user attributes JSON: {}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Decodes base64-encoded bytes passed over FFI.
+fn bytes_from_b64(bytes_cstr: &CStr, name: &str) -> Option<Vec<u8>> {
+    let bytes_b64_str = match bytes_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert {} C string to Rust string: {}",
+                name, e
+            );
+            return None;
+        }
+    };
+
+    match general_purpose::STANDARD.decode(bytes_b64_str) {
+        Ok(b) => Some(b),
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to decode {} from base64: {}",
+                name, e
+            );
+            None
+        }
+    }
+}
+
+/// Parses a policy passed over FFI.
+fn policy_from_cstr(policy_cstr: &CStr) -> Option<Policy> {
+    let policy_str = match policy_cstr.to_str() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to convert policy C string to Rust string: {}",
+                e
+            );
+            return None;
+        }
+    };
+
+    match Policy::parse(policy_str) {
+        Ok(p) => Some(p),
+        Err(e) => {
+            eprintln!("[accless-abe4-rs] Failed to parse policy: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Encodes a hybrid ciphertext as the JSON object returned over FFI, see
+/// `encrypt_hybrid_wrapped_abe4`.
+fn hybrid_output(hybrid_ct: &HybridCiphertext) -> *mut c_char {
+    let mut abe_ct_bytes = Vec::new();
+    if hybrid_ct
+        .abe_ct
+        .serialize_compressed(&mut abe_ct_bytes)
+        .is_err()
+    {
+        eprintln!("[accless-abe4-rs] Failed to serialize HybridCiphertext ABE part");
+        return std::ptr::null_mut();
+    }
+
+    let output = HybridEncryptOutput {
+        abe_ct: general_purpose::STANDARD.encode(&abe_ct_bytes),
+        sym_ct: general_purpose::STANDARD.encode(&hybrid_ct.sym_ct),
+    };
+
+    let output_json = match serde_json::to_string(&output) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to serialize hybrid output to JSON: {}",
+                e
+            );
+            return std::ptr::null_mut();
+        }
+    };
+
+    match CString::new(output_json) {
+        Ok(s) => s.into_raw(),
+        Err(e) => {
+            eprintln!(
+                "[accless-abe4-rs] Failed to create CString for hybrid output: {}",
+                e
+            );
+            std::ptr::null_mut()
+        }
+    }
+}
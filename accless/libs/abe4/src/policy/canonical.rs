@@ -0,0 +1,243 @@
+//! Canonical binary encoding of policies.
+//!
+//! The string representation of a policy is meant for humans, and may change
+//! between versions of the parser and of the `Debug` implementation. Anything
+//! that authenticates or stores a policy uses this encoding instead, which
+//! only depends on the expression tree of the policy in negation normal form.
+//! All integers are big-endian:
+//!
+//! ```text
+//! version      u8        `POLICY_ENCODING_VERSION`
+//! expr         node
+//!
+//! node         u8 tag, followed by:
+//!   0 (lit)       u8 negated (0 or 1), then authority, label and attribute,
+//!                 each as u32 len + UTF-8 bytes
+//!   1 (and)       lhs node, rhs node
+//!   2 (or)        lhs node, rhs node
+//!   3 (threshold) u32 k, u32 n, then n child nodes
+//! ```
+
+use crate::{
+    error::{Abe4Error, Result},
+    policy::{
+        Expr, Policy, UserAttribute, check_idents,
+        normalize::{RawExpr, normalize},
+    },
+};
+use log::error;
+
+/// Version of the canonical policy encoding.
+pub const POLICY_ENCODING_VERSION: u8 = 1;
+
+const TAG_LIT: u8 = 0;
+const TAG_AND: u8 = 1;
+const TAG_OR: u8 = 2;
+const TAG_THRESHOLD: u8 = 3;
+
+impl Policy {
+    /// # Description
+    ///
+    /// Encodes the policy in its canonical binary form (see the module
+    /// documentation). Two policies have the same encoding if and only if
+    /// they are equal.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![POLICY_ENCODING_VERSION];
+        encode(&self.expr, &mut out);
+        out
+    }
+
+    /// # Description
+    ///
+    /// Decodes a policy from its canonical binary form.
+    ///
+    /// # Returns
+    ///
+    /// The policy, or `Abe4Error::Serialization` if `bytes` is not a valid
+    /// encoding, or the errors of `Policy::parse` if the encoded policy is one
+    /// the parser would reject.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        let version = reader.u8()?;
+        if version != POLICY_ENCODING_VERSION {
+            let reason = format!("unsupported policy encoding version (version={version})");
+            error!("from_bytes(): {reason}");
+            return Err(Abe4Error::Serialization(reason));
+        }
+        let raw = decode(&mut reader)?;
+        if reader.pos != bytes.len() {
+            let reason = "trailing bytes after policy";
+            error!("from_bytes(): {reason}");
+            return Err(Abe4Error::Serialization(reason.into()));
+        }
+
+        let (expr, attrs, negs) = normalize(&raw)?;
+        Ok(Policy { expr, attrs, negs })
+    }
+}
+
+fn encode(expr: &Expr<(bool, UserAttribute)>, out: &mut Vec<u8>) {
+    match expr {
+        Expr::Lit((is_neg, user_attr)) => {
+            out.push(TAG_LIT);
+            out.push(*is_neg as u8);
+            for part in [
+                user_attr.authority(),
+                user_attr.label(),
+                user_attr.attribute(),
+            ] {
+                encode_u32(part.len(), out);
+                out.extend_from_slice(part.as_bytes());
+            }
+        }
+        Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+            out.push(if matches!(expr, Expr::And(..)) {
+                TAG_AND
+            } else {
+                TAG_OR
+            });
+            encode(lhs, out);
+            encode(rhs, out);
+        }
+        Expr::Threshold(k, children) => {
+            out.push(TAG_THRESHOLD);
+            encode_u32(*k, out);
+            encode_u32(children.len(), out);
+            for child in children {
+                encode(child, out);
+            }
+        }
+    }
+}
+
+fn encode_u32(n: usize, out: &mut Vec<u8>) {
+    // Policies are parsed from strings, or built, in memory, so no length
+    // comes anywhere near `u32::MAX`.
+    out.extend_from_slice(&(n as u32).to_be_bytes());
+}
+
+fn decode(reader: &mut Reader) -> Result<RawExpr> {
+    match reader.u8()? {
+        TAG_LIT => {
+            let is_neg = match reader.u8()? {
+                0 => false,
+                1 => true,
+                b => {
+                    let reason = format!("invalid negation flag (flag={b})");
+                    error!("decode(): {reason}");
+                    return Err(Abe4Error::Serialization(reason));
+                }
+            };
+            let auth = reader.string()?;
+            let lbl = reader.string()?;
+            let attr = reader.string()?;
+            let user_attr = UserAttribute::new(&auth, &lbl, &attr);
+            check_idents(&user_attr)?;
+            let lit = RawExpr::Lit(user_attr);
+            Ok(if is_neg {
+                RawExpr::Not(Box::new(lit))
+            } else {
+                lit
+            })
+        }
+        tag @ (TAG_AND | TAG_OR) => {
+            let lhs = Box::new(decode(reader)?);
+            let rhs = Box::new(decode(reader)?);
+            Ok(if tag == TAG_AND {
+                RawExpr::And(lhs, rhs)
+            } else {
+                RawExpr::Or(lhs, rhs)
+            })
+        }
+        TAG_THRESHOLD => {
+            let k = reader.u32()? as usize;
+            let n = reader.u32()?;
+            let children = (0..n)
+                .map(|_| decode(reader))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(RawExpr::Threshold(k, children))
+        }
+        tag => {
+            let reason = format!("invalid policy node (tag={tag})");
+            error!("decode(): {reason}");
+            Err(Abe4Error::Serialization(reason))
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len());
+        let Some(end) = end else {
+            let reason = "policy encoding is truncated";
+            error!("take(): {reason}");
+            return Err(Abe4Error::Serialization(reason.into()));
+        };
+        let out = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|e| {
+            let reason = format!("invalid UTF-8 string (error={e})");
+            error!("string(): {reason}");
+            Abe4Error::Serialization(reason)
+        })
+    }
+}
+
+#[test]
+fn test_canonical_round_trip() {
+    for s in [
+        "A.a:0",
+        "!A.a:0",
+        "A.a:0 & !(B.b:1 | C.c:\"x y\")",
+        "2of(A.a:0, B.b:1 & C.c:2, !D.d:3)",
+        "!2of(A.a:0, B.b:1, C.c:2)",
+        "as.epoch >= 3",
+    ] {
+        let policy = Policy::parse(s).unwrap();
+        let bytes = policy.to_bytes();
+        assert_eq!(bytes[0], POLICY_ENCODING_VERSION);
+        assert_eq!(Policy::from_bytes(&bytes).unwrap(), policy, "{s}");
+    }
+
+    let a = Policy::parse("A.a:0 & B.b:1").unwrap().to_bytes();
+    let b = Policy::parse("B.b:1 & A.a:0").unwrap().to_bytes();
+    assert_ne!(a, b);
+
+    let mut bad_version = a.clone();
+    bad_version[0] = 0;
+    assert!(Policy::from_bytes(&bad_version).is_err());
+    assert!(Policy::from_bytes(&a[..a.len() - 1]).is_err());
+    let mut trailing = a.clone();
+    trailing.push(0);
+    assert!(Policy::from_bytes(&trailing).is_err());
+
+    // A literal the parser would reject.
+    let mut bad_ident = vec![POLICY_ENCODING_VERSION, TAG_LIT, 0];
+    for part in ["A.x", "a", "0"] {
+        encode_u32(part.len(), &mut bad_ident);
+        bad_ident.extend_from_slice(part.as_bytes());
+    }
+    assert!(Policy::from_bytes(&bad_ident).is_err());
+}
@@ -0,0 +1,167 @@
+use std::{
+    fmt::{self, Write},
+    ops::Range,
+};
+
+/// Byte range of a token in a policy string.
+pub type Span = Range<usize>;
+
+/// # Description
+///
+/// A structured description of a syntax error in a policy or user attribute.
+///
+/// Errors found while parsing a string point at the offending token with a
+/// byte `span` into `source`, and list the tokens that would have been valid
+/// there. Errors found in policies built programmatically, or deserialized
+/// from JSON, have no source and only carry a message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolicyDiagnostic {
+    /// What went wrong, e.g. "unexpected '&'".
+    pub message: String,
+    /// The policy string the error was found in, if any.
+    pub source: Option<String>,
+    /// Byte range of the offending token in `source`.
+    pub span: Option<Span>,
+    /// Tokens that would have been valid at `span`.
+    pub expected: Vec<String>,
+}
+
+impl PolicyDiagnostic {
+    /// Creates a diagnostic without a source location.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            source: None,
+            span: None,
+            expected: Vec::new(),
+        }
+    }
+
+    /// Points the diagnostic at `span`.
+    pub fn at(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Sets the tokens that would have been valid at the span.
+    pub fn expecting(mut self, expected: &[&str]) -> Self {
+        self.expected = expected.iter().map(|e| e.to_string()).collect();
+        self
+    }
+
+    /// Attaches the string that the span points into.
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    /// Formats the expected tokens as "a", "a or b", or "a, b or c".
+    fn expected_list(&self) -> Option<String> {
+        let (last, rest) = self.expected.split_last()?;
+        if rest.is_empty() {
+            return Some(last.clone());
+        }
+        Some(format!("{} or {last}", rest.join(", ")))
+    }
+
+    /// # Description
+    ///
+    /// Renders the diagnostic as a multi-line, caret-style message that
+    /// shows the offending line of the policy, e.g.:
+    ///
+    /// ```text
+    /// error: unexpected '&'
+    ///  --> line 1, column 9
+    ///   |
+    /// 1 | A.a:0 & & B.b:0
+    ///   |         ^ expected '!', '(' or identifier
+    /// ```
+    ///
+    /// # Returns
+    ///
+    /// The rendered message, or the single-line `Display` representation if
+    /// the diagnostic has no source location.
+    pub fn render(&self) -> String {
+        let (Some(source), Some(span)) = (&self.source, &self.span) else {
+            return self.to_string();
+        };
+
+        let start = span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let line = source[line_start..line_end].trim_end_matches('\r');
+        let line_no = source[..line_start].matches('\n').count() + 1;
+        let column = source[line_start..start].chars().count() + 1;
+        let width = source[start..span.end.clamp(start, line_end)]
+            .chars()
+            .count()
+            .max(1);
+
+        // Keep tabs in the padding, so that the caret lines up with the line
+        // above it.
+        let padding: String = source[line_start..start]
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let gutter = " ".repeat(line_no.to_string().len());
+
+        let mut out = String::new();
+        let _ = writeln!(out, "error: {}", self.message);
+        let _ = writeln!(out, "{gutter}--> line {line_no}, column {column}");
+        let _ = writeln!(out, "{gutter} |");
+        let _ = writeln!(out, "{line_no} | {line}");
+        let _ = write!(out, "{gutter} | {padding}{}", "^".repeat(width));
+        if let Some(expected) = self.expected_list() {
+            let _ = write!(out, " expected {expected}");
+        }
+        out
+    }
+}
+
+impl fmt::Display for PolicyDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(span) = &self.span {
+            write!(f, " at bytes {}..{}", span.start, span.end)?;
+        }
+        if let Some(expected) = self.expected_list() {
+            write!(f, ", expected {expected}")?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_diagnostic_render() {
+    let diagnostic = PolicyDiagnostic::new("unexpected '&'")
+        .at(8..9)
+        .expecting(&["'!'", "'('", "identifier"])
+        .with_source("A.a:0 & & B.b:0");
+    assert_eq!(
+        diagnostic.to_string(),
+        "unexpected '&' at bytes 8..9, expected '!', '(' or identifier"
+    );
+    assert_eq!(
+        diagnostic.render(),
+        "error: unexpected '&'\n --> line 1, column 9\n  |\n1 | A.a:0 & & B.b:0\n  |         ^ \
+         expected '!', '(' or identifier"
+    );
+
+    // Multi-line policies point at the right line.
+    let diagnostic = PolicyDiagnostic::new("unexpected identifier 'B'")
+        .at(10..11)
+        .with_source("A.a:0 &\n  B B.b:0");
+    assert_eq!(
+        diagnostic.render(),
+        "error: unexpected identifier 'B'\n --> line 2, column 3\n  |\n2 |   B B.b:0\n  |   ^"
+    );
+
+    // Without a location, rendering falls back to the message.
+    let diagnostic = PolicyDiagnostic::new("'and' node must have at least one child");
+    assert_eq!(
+        diagnostic.render(),
+        "'and' node must have at least one child"
+    );
+}
@@ -0,0 +1,105 @@
+//! Epoch-scoped attributes, used to revoke user secret keys.
+//!
+//! A key issued at epoch `e` holds, for each authority that issued it, the
+//! numeric attribute `auth.epoch = e` (see `UserAttribute::numeric`). A policy
+//! that requires a minimum epoch `m` from each of its authorities is then only
+//! satisfied by keys issued at epoch `m` or later. Authorities revoke a key by
+//! moving on to a new epoch, and re-issuing keys to the users that still hold
+//! their attributes: keys issued before that age out as soon as ciphertexts
+//! are encrypted (or re-encapsulated, see `reencapsulate_hybrid`) with the
+//! new minimum epoch. Keys issued at different epochs are bound to different
+//! GIDs (see `epoch_gid`), so that the epoch of one can not be combined with
+//! the attributes of another.
+
+use crate::policy::{Comparison, NUMERIC_BITS, Policy, UserAttribute, numeric::bit_label};
+use std::collections::HashSet;
+
+/// Label of the numeric attribute that holds the epoch a key was issued at.
+pub const EPOCH_LABEL: &str = "epoch";
+
+/// Whether `user_attr` holds a bit of an epoch.
+fn is_epoch_bit(user_attr: &UserAttribute) -> bool {
+    (0..NUMERIC_BITS).any(|bit| user_attr.label() == bit_label(EPOCH_LABEL, bit))
+}
+
+impl UserAttribute {
+    /// # Description
+    ///
+    /// Stamps the user attributes with the epoch they are issued at.
+    ///
+    /// # Returns
+    ///
+    /// The given attributes, without any previous epoch, followed by the bits
+    /// of `auth.epoch = epoch` for each of their authorities, in the order the
+    /// authorities first appear. This is the list of attributes to issue a key
+    /// for, and to verify the key against.
+    pub fn with_epoch(user_attrs: &[UserAttribute], epoch: u32) -> Vec<UserAttribute> {
+        let mut stamped: Vec<UserAttribute> = user_attrs
+            .iter()
+            .filter(|ua| !is_epoch_bit(ua))
+            .cloned()
+            .collect();
+        let mut seen = HashSet::new();
+        for user_attr in user_attrs {
+            if seen.insert(user_attr.authority()) {
+                stamped.extend(UserAttribute::numeric(
+                    user_attr.authority(),
+                    EPOCH_LABEL,
+                    epoch,
+                ));
+            }
+        }
+        stamped
+    }
+}
+
+impl Policy {
+    /// # Description
+    ///
+    /// Restricts the policy to keys issued at epoch `min_epoch` or later.
+    ///
+    /// # Returns
+    ///
+    /// The conjunction of the policy with `auth.epoch >= min_epoch` for each
+    /// authority of the policy. A user must then hold a recent enough epoch
+    /// from every authority of the policy, including the ones whose
+    /// attributes they do not need to satisfy it.
+    pub fn require_epoch(self, min_epoch: u32) -> Policy {
+        self.authorities().iter().fold(self, |policy, auth| {
+            let epoch = Policy::compare(auth, EPOCH_LABEL, Comparison::Ge, min_epoch)
+                .expect("every minimum epoch is satisfiable");
+            Policy::and(policy, epoch)
+        })
+    }
+}
+
+#[test]
+fn test_epoch() {
+    let user_attrs = vec![
+        UserAttribute::new("A", "wf", "w1"),
+        UserAttribute::new("B", "node", "n1"),
+        UserAttribute::new("A", "node", "n1"),
+    ];
+    let stamped = UserAttribute::with_epoch(&user_attrs, 7);
+    assert_eq!(stamped.len(), 3 + 2 * NUMERIC_BITS as usize);
+    assert_eq!(&stamped[..3], &user_attrs[..]);
+    assert_eq!(stamped[3], UserAttribute::new("A", "epoch#0", "1"));
+    // Re-stamping replaces the previous epoch.
+    assert_eq!(
+        UserAttribute::with_epoch(&stamped, 9),
+        UserAttribute::with_epoch(&user_attrs, 9)
+    );
+
+    let policy = Policy::parse("A.wf:w1 & B.node:n1")
+        .unwrap()
+        .require_epoch(7);
+    assert_eq!(policy.authorities(), vec!["A", "B"]);
+    for (epoch, satisfied) in [(6, false), (7, true), (8, true)] {
+        let stamped = UserAttribute::with_epoch(&user_attrs, epoch);
+        assert_eq!(policy.evaluate(&stamped).is_satisfied(), satisfied);
+    }
+    // Both authorities must have issued a recent enough key.
+    let mut mixed = UserAttribute::with_epoch(&user_attrs[..1], 7);
+    mixed.extend(UserAttribute::with_epoch(&user_attrs[1..2], 6));
+    assert!(!policy.evaluate(&mixed).is_satisfied());
+}
@@ -0,0 +1,197 @@
+use crate::policy::{Expr, Policy, PolicyExpr, UserAttribute, secret_sharing};
+
+/// Outcome of evaluating a policy against a set of user attributes.
+#[derive(Debug, PartialEq)]
+pub enum Evaluation {
+    /// The user attributes satisfy the policy. Contains the literals, and
+    /// whether each of them is negated, that decryption would use to
+    /// reconstruct the secret.
+    Satisfied(Vec<(UserAttribute, bool)>),
+    /// The user attributes do not satisfy the policy. Contains the
+    /// sub-expressions that are still missing: satisfying all of them (in
+    /// addition to the current attributes) would satisfy the policy.
+    Unsatisfied(Vec<Policy>),
+}
+
+impl Evaluation {
+    pub fn is_satisfied(&self) -> bool {
+        matches!(self, Evaluation::Satisfied(_))
+    }
+}
+
+/// Evaluates a policy against a set of user attributes.
+///
+/// This function does not compute any pairings, so it is cheap enough to run
+/// before attempting a decryption.
+///
+/// # Arguments
+///
+/// * `user_attrs`: The user attributes to evaluate the policy against.
+/// * `policy`: The policy to evaluate.
+///
+/// # Returns
+///
+/// An `Evaluation` with either the literals that satisfy the policy, or the
+/// sub-expressions that are not satisfied.
+pub fn evaluate(user_attrs: &[UserAttribute], policy: &Policy) -> Evaluation {
+    if let Some(idcs) = secret_sharing::reconstruct_secret(user_attrs, policy) {
+        return Evaluation::Satisfied(idcs.into_iter().map(|idx| policy.get(idx)).collect());
+    }
+
+    let mut missing = Vec::new();
+    if let Some(residual) = residual(user_attrs, &policy.expr) {
+        split_conjuncts(residual, &mut missing);
+    }
+    Evaluation::Unsatisfied(missing.into_iter().map(to_policy).collect())
+}
+
+/// Returns the part of `expr` that is not satisfied by `user_attrs`, or `None`
+/// if `expr` is satisfied.
+fn residual(user_attrs: &[UserAttribute], expr: &PolicyExpr) -> Option<PolicyExpr> {
+    match expr {
+        Expr::Lit((is_neg, user_attr)) => {
+            match secret_sharing::satisfies(user_attrs, user_attr, *is_neg) {
+                Some(_) => None,
+                None => Some(expr.clone()),
+            }
+        }
+        Expr::And(lhs, rhs) => match (residual(user_attrs, lhs), residual(user_attrs, rhs)) {
+            (None, None) => None,
+            (Some(lhs), None) => Some(lhs),
+            (None, Some(rhs)) => Some(rhs),
+            (Some(lhs), Some(rhs)) => Some(Expr::And(Box::new(lhs), Box::new(rhs))),
+        },
+        Expr::Or(lhs, rhs) => match (residual(user_attrs, lhs), residual(user_attrs, rhs)) {
+            (Some(lhs), Some(rhs)) => Some(Expr::Or(Box::new(lhs), Box::new(rhs))),
+            (_, _) => None,
+        },
+        Expr::Threshold(k, children) => {
+            let unsatisfied: Vec<PolicyExpr> = children
+                .iter()
+                .filter_map(|child| residual(user_attrs, child))
+                .collect();
+            let num_satisfied = children.len() - unsatisfied.len();
+            if num_satisfied >= *k {
+                return None;
+            }
+
+            // We still need `k - num_satisfied` of the unsatisfied children.
+            let k = k - num_satisfied;
+            let n = unsatisfied.len();
+            let mut unsatisfied = unsatisfied.into_iter();
+            if k == 1 || k == n {
+                let mut lhs = unsatisfied.next()?;
+                for rhs in unsatisfied {
+                    lhs = if k == 1 {
+                        Expr::Or(Box::new(lhs), Box::new(rhs))
+                    } else {
+                        Expr::And(Box::new(lhs), Box::new(rhs))
+                    };
+                }
+                return Some(lhs);
+            }
+            Some(Expr::Threshold(k, unsatisfied.collect()))
+        }
+    }
+}
+
+fn split_conjuncts(expr: PolicyExpr, result: &mut Vec<PolicyExpr>) {
+    match expr {
+        Expr::And(lhs, rhs) => {
+            split_conjuncts(*lhs, result);
+            split_conjuncts(*rhs, result);
+        }
+        expr => result.push(expr),
+    }
+}
+
+fn to_policy(expr: PolicyExpr) -> Policy {
+    fn collect(expr: &PolicyExpr, attrs: &mut Vec<UserAttribute>, negs: &mut Vec<bool>) {
+        match expr {
+            Expr::Lit((is_neg, user_attr)) => {
+                attrs.push(user_attr.clone());
+                negs.push(*is_neg);
+            }
+            Expr::And(lhs, rhs) | Expr::Or(lhs, rhs) => {
+                collect(lhs, attrs, negs);
+                collect(rhs, attrs, negs);
+            }
+            Expr::Threshold(_, children) => {
+                for child in children {
+                    collect(child, attrs, negs);
+                }
+            }
+        }
+    }
+
+    let mut attrs = Vec::new();
+    let mut negs = Vec::new();
+    collect(&expr, &mut attrs, &mut negs);
+    Policy { expr, attrs, negs }
+}
+
+#[cfg(test)]
+fn user_attrs(attrs: &[&str]) -> Vec<UserAttribute> {
+    attrs
+        .iter()
+        .map(|ua| UserAttribute::parse(ua).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_evaluate_satisfied() {
+    let policy = Policy::parse("A.a:0 & (B.b:0 | !C.c:0)").unwrap();
+    let evaluation = evaluate(&user_attrs(&["A.a:0", "C.c:1"]), &policy);
+    assert_eq!(
+        evaluation,
+        Evaluation::Satisfied(vec![
+            (UserAttribute::new("A", "a", "0"), false),
+            (UserAttribute::new("C", "c", "0"), true),
+        ])
+    );
+    assert!(evaluation.is_satisfied());
+}
+
+#[test]
+fn test_evaluate_unsatisfied() {
+    let policy = Policy::parse("A.a:0 & (B.b:0 | !C.c:0) & D.d:0").unwrap();
+    let evaluation = evaluate(&user_attrs(&["D.d:0", "C.c:0"]), &policy);
+    assert_eq!(
+        evaluation,
+        Evaluation::Unsatisfied(vec![
+            Policy::parse("A.a:0").unwrap(),
+            Policy::parse("B.b:0 | !C.c:0").unwrap(),
+        ])
+    );
+
+    // A negated literal is only satisfied if the user holds another value for
+    // the same label.
+    let policy = Policy::parse("!A.a:0").unwrap();
+    let evaluation = evaluate(&[], &policy);
+    assert_eq!(
+        evaluation,
+        Evaluation::Unsatisfied(vec![Policy::parse("!A.a:0").unwrap()])
+    );
+}
+
+#[test]
+fn test_evaluate_threshold() {
+    let policy = Policy::parse("3of(A.a:0, A.b:0 & A.c:0, B.b:0, C.c:0)").unwrap();
+
+    let evaluation = evaluate(&user_attrs(&["A.a:0", "A.b:0", "B.b:0"]), &policy);
+    assert_eq!(
+        evaluation,
+        Evaluation::Unsatisfied(vec![Policy::parse("A.c:0 | C.c:0").unwrap()])
+    );
+
+    let evaluation = evaluate(&user_attrs(&["A.b:0"]), &policy);
+    assert_eq!(
+        evaluation,
+        Evaluation::Unsatisfied(vec![
+            Policy::parse("3of(A.a:0, A.c:0, B.b:0, C.c:0)").unwrap()
+        ])
+    );
+
+    let evaluation = evaluate(&user_attrs(&["A.a:0", "B.b:0", "C.c:0"]), &policy);
+    assert!(evaluation.is_satisfied());
+}
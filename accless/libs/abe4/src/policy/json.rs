@@ -0,0 +1,173 @@
+use crate::{
+    error::Result,
+    policy::{
+        Expr, Policy, PolicyDiagnostic, UserAttribute, check_idents,
+        normalize::{RawExpr, normalize},
+    },
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+/// Stable JSON representation of a policy's expression tree.
+///
+/// Each node is an object with a single key naming the node type:
+///
+/// ```json
+/// {"and": [
+///     {"lit": {"authority": "A", "label": "wf", "attribute": "0"}},
+///     {"not": {"lit": {"authority": "B", "label": "node", "attribute": "1"}}},
+///     {"threshold": {"k": 2, "of": [...]}}
+/// ]}
+/// ```
+///
+/// Conjunctions and disjunctions are n-ary, and are folded left-to-right in
+/// the same way the string grammar does, so `a & b & c` and
+/// `{"and": [a, b, c]}` produce the same policy.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum PolicyNode {
+    Lit(UserAttribute),
+    Not(Box<PolicyNode>),
+    And(Vec<PolicyNode>),
+    Or(Vec<PolicyNode>),
+    Threshold { k: usize, of: Vec<PolicyNode> },
+}
+
+impl PolicyNode {
+    fn from_expr(expr: &Expr<(bool, UserAttribute)>) -> Self {
+        match expr {
+            Expr::Lit((false, user_attr)) => PolicyNode::Lit(user_attr.clone()),
+            Expr::Lit((true, user_attr)) => {
+                PolicyNode::Not(Box::new(PolicyNode::Lit(user_attr.clone())))
+            }
+            // Flatten left-associative chains so that they round-trip exactly.
+            Expr::And(lhs, rhs) => match PolicyNode::from_expr(lhs) {
+                PolicyNode::And(mut children) => {
+                    children.push(PolicyNode::from_expr(rhs));
+                    PolicyNode::And(children)
+                }
+                lhs => PolicyNode::And(vec![lhs, PolicyNode::from_expr(rhs)]),
+            },
+            Expr::Or(lhs, rhs) => match PolicyNode::from_expr(lhs) {
+                PolicyNode::Or(mut children) => {
+                    children.push(PolicyNode::from_expr(rhs));
+                    PolicyNode::Or(children)
+                }
+                lhs => PolicyNode::Or(vec![lhs, PolicyNode::from_expr(rhs)]),
+            },
+            Expr::Threshold(k, children) => PolicyNode::Threshold {
+                k: *k,
+                of: children.iter().map(PolicyNode::from_expr).collect(),
+            },
+        }
+    }
+
+    fn into_raw(self) -> Result<RawExpr> {
+        match self {
+            PolicyNode::Lit(user_attr) => {
+                check_idents(&user_attr)?;
+                Ok(RawExpr::Lit(user_attr))
+            }
+            PolicyNode::Not(inner) => Ok(RawExpr::Not(Box::new(inner.into_raw()?))),
+            PolicyNode::And(children) => Self::fold(children, "and", RawExpr::And),
+            PolicyNode::Or(children) => Self::fold(children, "or", RawExpr::Or),
+            PolicyNode::Threshold { k, of } => {
+                let children = of
+                    .into_iter()
+                    .map(PolicyNode::into_raw)
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(RawExpr::Threshold(k, children))
+            }
+        }
+    }
+
+    fn fold(
+        children: Vec<PolicyNode>,
+        op: &str,
+        f: impl Fn(Box<RawExpr>, Box<RawExpr>) -> RawExpr,
+    ) -> Result<RawExpr> {
+        let mut children = children.into_iter();
+        let Some(first) = children.next() else {
+            return Err(
+                PolicyDiagnostic::new(format!("'{op}' node must have at least one child")).into(),
+            );
+        };
+        let mut lhs = first.into_raw()?;
+        for rhs in children {
+            lhs = f(Box::new(lhs), Box::new(rhs.into_raw()?));
+        }
+        Ok(lhs)
+    }
+}
+
+impl Serialize for Policy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        PolicyNode::from_expr(&self.expr).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Policy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let node = PolicyNode::deserialize(deserializer)?;
+        let raw = node.into_raw().map_err(D::Error::custom)?;
+        let (expr, attrs, negs) = normalize(&raw).map_err(D::Error::custom)?;
+        Ok(Policy { expr, attrs, negs })
+    }
+}
+
+#[test]
+fn test_json_round_trip() {
+    let policies = [
+        "A.a:0",
+        "!A.a:0",
+        "A.a:0 & B.b:1 & !C.c:2",
+        "A.a:0 & (B.b:1 & C.c:2)",
+        "(A.a:0 | A.a:1) & !(B.b:1 | 2of(C.c:2, C.c:3, D.d:4))",
+        "3of(A.a:0, A.a:1 & B.b:0, A.a:2, A.a:3)",
+        r#"k8s.ns:"team-a" & !corp.email:"alice@example.com""#,
+    ];
+    for s in policies {
+        let policy = Policy::parse(s).unwrap();
+        let json = serde_json::to_string(&policy).unwrap();
+        let parsed: Policy = serde_json::from_str(&json).unwrap();
+        assert_eq!(policy, parsed, "{s} did not round-trip through {json}");
+    }
+}
+
+#[test]
+fn test_json_format() {
+    let policy = Policy::parse("A.a:0 & B.b:1 & !C.c:2").unwrap();
+    let json = serde_json::to_value(&policy).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"and": [
+            {"lit": {"authority": "A", "label": "a", "attribute": "0"}},
+            {"lit": {"authority": "B", "label": "b", "attribute": "1"}},
+            {"not": {"lit": {"authority": "C", "label": "c", "attribute": "2"}}},
+        ]})
+    );
+
+    // Negations over sub-expressions are normalised on deserialization.
+    let json = r#"{"not": {"or": [
+        {"lit": {"authority": "A", "label": "a", "attribute": "0"}},
+        {"threshold": {"k": 1, "of": [
+            {"lit": {"authority": "B", "label": "b", "attribute": "1"}},
+            {"lit": {"authority": "C", "label": "c", "attribute": "2"}}
+        ]}}
+    ]}}"#;
+    let policy: Policy = serde_json::from_str(json).unwrap();
+    assert_eq!(policy, Policy::parse("!A.a:0 & (!B.b:1 & !C.c:2)").unwrap());
+}
+
+#[test]
+fn test_json_invalid() {
+    let invalid = [
+        r#"{"and": []}"#,
+        r#"{"xor": []}"#,
+        r#"{"threshold": {"k": 3, "of": [{"lit": {"authority": "A", "label": "a", "attribute": "0"}}]}}"#,
+        r#"{"lit": {"authority": "A.b", "label": "a", "attribute": "0"}}"#,
+        r#"{"lit": {"authority": "A", "label": "a"}}"#,
+    ];
+    for json in invalid {
+        assert!(serde_json::from_str::<Policy>(json).is_err(), "{json}");
+    }
+}
@@ -1,23 +1,47 @@
-use anyhow::Result;
+use crate::error::Result;
+use ark_ff::PrimeField;
 use ark_std::iterable::Iterable;
 use core::fmt;
 use serde::{Deserialize, Serialize};
-use std::fmt::{Debug, Write};
+use std::{
+    collections::BTreeSet,
+    fmt::{Debug, Write},
+};
 
+mod canonical;
+mod diagnostic;
+mod epoch;
+mod evaluate;
+mod json;
+mod normalize;
+mod numeric;
 mod parser;
 mod secret_sharing;
 
+pub use canonical::POLICY_ENCODING_VERSION;
+pub use diagnostic::{PolicyDiagnostic, Span};
+pub use epoch::EPOCH_LABEL;
+pub use evaluate::Evaluation;
+pub(crate) use numeric::is_bit_label;
+pub use numeric::{Comparison, NUMERIC_BITS};
+
 // -----------------------------------------------------------------------------------------------
 // Structure And Enum Definitions
 // -----------------------------------------------------------------------------------------------
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 enum Expr<T> {
     Lit(T),
     And(Box<Expr<T>>, Box<Expr<T>>),
     Or(Box<Expr<T>>, Box<Expr<T>>),
+    /// Threshold gate satisfied by at least `k` of the sub-expressions.
+    Threshold(usize, Vec<Expr<T>>),
 }
 
+/// Policy expression in negation normal form, where each literal records
+/// whether it is negated.
+type PolicyExpr = Expr<(bool, UserAttribute)>;
+
 /// Structure representing a user attribute in decentralized CP-ABE. A user
 /// attribute is a triple of strings: (authority, label, attribute) indicating
 /// the authority that provides keys for this attribute, the attribute label,
@@ -71,6 +95,29 @@ impl UserAttribute {
         })
     }
 
+    /// # Description
+    ///
+    /// Expands the numeric attribute `auth.label = value` into the user
+    /// attributes that hold its bits, as expected by comparison literals such
+    /// as `auth.label >= 42`.
+    ///
+    /// # Returns
+    ///
+    /// `NUMERIC_BITS` user attributes `auth.label#<i>:<b>`, where `b` is
+    /// the `i`-th bit of `value`.
+    ///
+    /// A key may only hold one value of a numeric attribute: the bits of 3
+    /// (`011`) and 4 (`100`) together hold both values of every bit, which
+    /// satisfies any comparison. Key generation rejects such keys.
+    pub fn numeric(auth: &str, label: &str, value: u32) -> Vec<Self> {
+        (0..NUMERIC_BITS)
+            .map(|bit| {
+                let b = (value >> bit) & 1;
+                UserAttribute::new(auth, &numeric::bit_label(label, bit), &b.to_string())
+            })
+            .collect()
+    }
+
     pub fn auth_lbl_attr(&self) -> (String, String, String) {
         (
             self.authority.clone(),
@@ -88,13 +135,50 @@ impl UserAttribute {
     }
 }
 
+/// Returns whether `s` can be written in a policy without quotes.
+fn is_ident(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// Checks that the authority and label of `user_attr` are identifiers, or
+/// for the label the bit label of a numeric attribute, as the parser
+/// requires. Attribute values may contain any character, as they can be
+/// quoted in the string grammar.
+fn check_idents(user_attr: &UserAttribute) -> Result<()> {
+    let label = user_attr.label();
+    for (part, valid) in [
+        (user_attr.authority(), is_ident(user_attr.authority())),
+        (label, is_ident(label) || numeric::is_bit_label(label)),
+    ] {
+        if !valid {
+            return Err(PolicyDiagnostic::new(format!(
+                "invalid identifier '{part}' in user attribute"
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
 impl Debug for UserAttribute {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&self.authority)?;
         f.write_char('.')?;
         f.write_str(&self.label)?;
         f.write_char(':')?;
-        f.write_str(&self.attribute)
+        if is_ident(&self.attribute) {
+            return f.write_str(&self.attribute);
+        }
+        // Quote the attribute value, so that the output parses back into the
+        // same attribute.
+        f.write_char('"')?;
+        for c in self.attribute.chars() {
+            if c == '"' || c == '\\' {
+                f.write_char('\\')?;
+            }
+            f.write_char(c)?;
+        }
+        f.write_char('"')
     }
 }
 impl Policy {
@@ -115,6 +199,107 @@ impl Policy {
         Ok(Policy { expr, attrs, negs })
     }
 
+    /// Builds a policy made of a single, non-negated, user attribute.
+    ///
+    /// # Returns
+    ///
+    /// The policy, or an error if the authority or the label of `user_attr`
+    /// is not an identifier, as `Policy::parse` would reject it.
+    pub fn lit(user_attr: UserAttribute) -> Result<Self> {
+        check_idents(&user_attr)?;
+        Ok(Policy {
+            expr: Expr::Lit((false, user_attr.clone())),
+            attrs: vec![user_attr],
+            negs: vec![false],
+        })
+    }
+
+    /// Builds the conjunction of two policies.
+    pub fn and(lhs: Policy, rhs: Policy) -> Self {
+        Self::combine(lhs, rhs, Expr::And)
+    }
+
+    /// Builds the disjunction of two policies.
+    pub fn or(lhs: Policy, rhs: Policy) -> Self {
+        Self::combine(lhs, rhs, Expr::Or)
+    }
+
+    /// Builds the negation of a policy.
+    ///
+    /// As with `Policy::parse`, the negation is pushed down to the literals,
+    /// so `Policy::not` of `a & b` is the policy `!a | !b`.
+    ///
+    /// # Returns
+    ///
+    /// The negated policy, or the errors of normalising it, as in
+    /// `Policy::parse`.
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(policy: Policy) -> Result<Self> {
+        let raw = normalize::RawExpr::Not(Box::new(normalize::RawExpr::from_expr(&policy.expr)));
+        let (expr, attrs, negs) = normalize::normalize(&raw)?;
+        Ok(Policy { expr, attrs, negs })
+    }
+
+    /// Builds a threshold gate satisfied by at least `k` of `children`.
+    ///
+    /// # Arguments
+    ///
+    /// * `k`: The number of children that must be satisfied.
+    /// * `children`: The sub-policies.
+    ///
+    /// # Returns
+    ///
+    /// The threshold policy, or an error if `k` is not between 1 and the
+    /// number of children.
+    pub fn threshold(k: usize, children: Vec<Policy>) -> Result<Self> {
+        let raw = normalize::RawExpr::Threshold(
+            k,
+            children
+                .iter()
+                .map(|child| normalize::RawExpr::from_expr(&child.expr))
+                .collect(),
+        );
+        let (expr, attrs, negs) = normalize::normalize(&raw)?;
+        Ok(Policy { expr, attrs, negs })
+    }
+
+    /// Builds the comparison `auth.label <op> value` over a numeric
+    /// attribute, see `UserAttribute::numeric`.
+    ///
+    /// # Returns
+    ///
+    /// The comparison policy, or an error if `auth` or `label` is not an
+    /// identifier, or if no value satisfies it.
+    pub fn compare(auth: &str, label: &str, op: Comparison, value: u32) -> Result<Self> {
+        for part in [auth, label] {
+            if !is_ident(part) {
+                return Err(PolicyDiagnostic::new(format!(
+                    "invalid identifier '{part}' in comparison"
+                ))
+                .into());
+            }
+        }
+        let raw = numeric::compare(auth, label, op, value).map_err(PolicyDiagnostic::new)?;
+        let (expr, attrs, negs) = normalize::normalize(&raw)?;
+        Ok(Policy { expr, attrs, negs })
+    }
+
+    fn combine(
+        lhs: Policy,
+        rhs: Policy,
+        op: fn(Box<PolicyExpr>, Box<PolicyExpr>) -> PolicyExpr,
+    ) -> Self {
+        let mut attrs = lhs.attrs;
+        attrs.extend(rhs.attrs);
+        let mut negs = lhs.negs;
+        negs.extend(rhs.negs);
+        Policy {
+            expr: op(Box::new(lhs.expr), Box::new(rhs.expr)),
+            attrs,
+            negs,
+        }
+    }
+
     pub fn conjunction_of(user_attrs: &Vec<UserAttribute>, num_negs: usize) -> Self {
         if num_negs > user_attrs.len() {
             panic!("Cannot have more negated attributes than total length of policy");
@@ -143,13 +328,53 @@ impl Policy {
         Policy { expr, attrs, negs }
     }
 
-    pub fn share_secret(&self) -> Vec<(UserAttribute, Vec<i64>)> {
+    pub fn share_secret(&self) -> Vec<(UserAttribute, Vec<(usize, i64)>)> {
         secret_sharing::share_secret(self)
     }
 
-    pub fn reconstruct_secret(&self, user_attrs: &Vec<UserAttribute>) -> Option<Vec<usize>> {
+    pub fn share_secret_in<F: PrimeField>(&self) -> Vec<(UserAttribute, Vec<(usize, F)>)> {
+        secret_sharing::share_secret_in(self)
+    }
+
+    pub fn reconstruct_secret(&self, user_attrs: &[UserAttribute]) -> Option<Vec<usize>> {
         secret_sharing::reconstruct_secret(user_attrs, self)
     }
+
+    /// Checks whether a set of user attributes satisfies the policy, without
+    /// computing any pairings.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_attrs`: The user attributes to evaluate the policy against.
+    ///
+    /// # Returns
+    ///
+    /// An `Evaluation` with either the literals that would be used to decrypt,
+    /// or the sub-expressions that are not satisfied.
+    pub fn evaluate(&self, user_attrs: &[UserAttribute]) -> Evaluation {
+        evaluate::evaluate(user_attrs, self)
+    }
+
+    /// Like `reconstruct_secret`, but also returns the coefficient each
+    /// selected share must be multiplied by. All coefficients are one unless
+    /// the policy contains threshold gates.
+    pub fn reconstruct_secret_with_coeffs<F: PrimeField>(
+        &self,
+        user_attrs: &[UserAttribute],
+    ) -> Option<Vec<(usize, F)>> {
+        secret_sharing::reconstruct_secret_with_coeffs(user_attrs, self)
+    }
+
+    /// Returns the authorities referenced in the policy, sorted and without
+    /// duplicates.
+    pub fn authorities(&self) -> Vec<String> {
+        let authorities: BTreeSet<String> = self
+            .attrs
+            .iter()
+            .map(|user_attr| user_attr.authority().to_string())
+            .collect();
+        authorities.into_iter().collect()
+    }
 }
 
 /// Helper method to format an expression.
@@ -178,6 +403,16 @@ fn fmt_expr(
             fmt_expr(rhs, f)?;
             write!(f, ")")
         }
+        Expr::Threshold(k, children) => {
+            write!(f, "{k}of(")?;
+            for (i, child) in children.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                fmt_expr(child, f)?;
+            }
+            write!(f, ")")
+        }
     }
 }
 
@@ -186,3 +421,97 @@ impl fmt::Debug for Policy {
         fmt_expr(&self.expr, f)
     }
 }
+
+#[test]
+fn test_policy_builder() {
+    let a = || Policy::lit(UserAttribute::new("A", "wf", "0")).unwrap();
+    let b = || Policy::lit(UserAttribute::new("B", "node", "1")).unwrap();
+    let c = || Policy::lit(UserAttribute::new("C", "anc", "2")).unwrap();
+
+    let policy = Policy::and(a(), Policy::or(b(), Policy::not(c()).unwrap()));
+    assert_eq!(
+        policy,
+        Policy::parse("A.wf:0 & (B.node:1 | !C.anc:2)").unwrap()
+    );
+    assert_eq!(policy.len(), 3);
+    assert!(policy.get(2).1);
+
+    let policy = Policy::not(Policy::and(Policy::and(a(), b()), c())).unwrap();
+    assert_eq!(
+        policy,
+        Policy::parse("!(A.wf:0 & B.node:1 & C.anc:2)").unwrap()
+    );
+
+    let policy = Policy::threshold(2, vec![a(), b(), Policy::and(c(), a())]).unwrap();
+    assert_eq!(
+        policy,
+        Policy::parse("2of(A.wf:0, B.node:1, C.anc:2 & A.wf:0)").unwrap()
+    );
+    let policy = Policy::not(policy).unwrap();
+    assert_eq!(
+        policy,
+        Policy::parse("!2of(A.wf:0, B.node:1, C.anc:2 & A.wf:0)").unwrap()
+    );
+
+    assert!(Policy::threshold(0, vec![a(), b()]).is_err());
+    assert!(Policy::threshold(3, vec![a(), b()]).is_err());
+    assert!(Policy::threshold(1, vec![]).is_err());
+
+    // The builder rejects what the parser would.
+    assert!(Policy::lit(UserAttribute::new("A.x", "wf", "0")).is_err());
+    assert!(Policy::lit(UserAttribute::new("A", "", "0")).is_err());
+    assert!(Policy::lit(UserAttribute::new("A", "wf", "a b")).is_ok());
+    assert!(Policy::lit(UserAttribute::new("A", "tcb#3", "1")).is_ok());
+    assert!(Policy::lit(UserAttribute::new("A", "tcb#32", "1")).is_err());
+    assert!(Policy::lit(UserAttribute::new("A", "tcb#03", "1")).is_err());
+    assert!(Policy::compare("A", "tcb#3", Comparison::Ge, 1).is_err());
+
+    // The dual of 2of(20 literals) is 19of20, whose coefficients 20^18 only
+    // fit in the scalar field.
+    let lits = (0..20)
+        .map(|i| Policy::lit(UserAttribute::new("A", "wf", &i.to_string())).unwrap())
+        .collect();
+    let policy = Policy::threshold(2, lits).unwrap();
+    let negated = Policy::not(policy).unwrap();
+    assert_eq!(
+        negated.share_secret_in::<crate::curve::ScalarField>().len(),
+        20
+    );
+}
+
+#[test]
+fn test_quoted_values_round_trip() {
+    for value in [
+        "team-a",
+        "alice@example.com",
+        "a.b:c",
+        r#"say "hi""#,
+        r"C:\dir",
+        "é",
+        "",
+    ] {
+        let user_attr = UserAttribute::new("A", "a", value);
+        let formatted = format!("{user_attr:?}");
+        assert_eq!(
+            UserAttribute::parse(&formatted).unwrap(),
+            user_attr,
+            "{formatted}"
+        );
+
+        let policy = Policy::and(
+            Policy::lit(user_attr).unwrap(),
+            Policy::not(Policy::lit(UserAttribute::new("B", "b", "0")).unwrap()).unwrap(),
+        );
+        assert_eq!(Policy::parse(&format!("{policy:?}")).unwrap(), policy);
+    }
+
+    // Plain identifiers are not quoted.
+    assert_eq!(
+        format!("{:?}", UserAttribute::new("A", "a", "x_0")),
+        "A.a:x_0"
+    );
+    assert_eq!(
+        format!("{:?}", UserAttribute::new("A", "a", "team-a")),
+        r#"A.a:"team-a""#
+    );
+}
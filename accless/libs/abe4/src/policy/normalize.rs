@@ -0,0 +1,193 @@
+use crate::{
+    error::Result,
+    policy::{Expr, PolicyDiagnostic, UserAttribute},
+};
+
+/// Policy expression as written by the user, before normalisation.
+///
+/// Unlike `Expr`, negations may appear in front of arbitrary sub-expressions.
+/// The secret sharing scheme only supports negated literals, so every
+/// `RawExpr` must go through `normalize` before we can use it.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum RawExpr {
+    Lit(UserAttribute),
+    Not(Box<RawExpr>),
+    And(Box<RawExpr>, Box<RawExpr>),
+    Or(Box<RawExpr>, Box<RawExpr>),
+    Threshold(usize, Vec<RawExpr>),
+}
+
+impl RawExpr {
+    /// Converts a normalised expression back into a raw one.
+    pub(crate) fn from_expr(expr: &Expr<(bool, UserAttribute)>) -> Self {
+        match expr {
+            Expr::Lit((false, user_attr)) => RawExpr::Lit(user_attr.clone()),
+            Expr::Lit((true, user_attr)) => RawExpr::Not(Box::new(RawExpr::Lit(user_attr.clone()))),
+            Expr::And(lhs, rhs) => RawExpr::And(
+                Box::new(RawExpr::from_expr(lhs)),
+                Box::new(RawExpr::from_expr(rhs)),
+            ),
+            Expr::Or(lhs, rhs) => RawExpr::Or(
+                Box::new(RawExpr::from_expr(lhs)),
+                Box::new(RawExpr::from_expr(rhs)),
+            ),
+            Expr::Threshold(k, children) => {
+                RawExpr::Threshold(*k, children.iter().map(RawExpr::from_expr).collect())
+            }
+        }
+    }
+}
+
+pub type NormalizedPolicy = (Expr<(bool, UserAttribute)>, Vec<UserAttribute>, Vec<bool>);
+
+/// Converts a raw policy expression into negation normal form.
+///
+/// Negations are pushed down to the literals using De Morgan's laws, and
+/// double negations cancel out. A negated threshold gate becomes its dual:
+/// not (at least `k` of `n`) is equivalent to at least `n - k + 1` of the `n`
+/// negated sub-expressions. Threshold gates that degenerate to a plain
+/// disjunction (`k = 1`) or conjunction (`k = n`) are emitted as such, as they
+/// are cheaper to share.
+///
+/// # Arguments
+///
+/// * `raw`: The expression to normalise.
+///
+/// # Returns
+///
+/// The normalised expression, together with the list of literals and whether
+/// each of them is negated, in left-to-right order.
+pub(crate) fn normalize(raw: &RawExpr) -> Result<NormalizedPolicy> {
+    let mut attrs = Vec::new();
+    let mut negs = Vec::new();
+    let expr = helper(raw, false, &mut attrs, &mut negs)?;
+    Ok((expr, attrs, negs))
+}
+
+fn helper(
+    raw: &RawExpr,
+    is_neg: bool,
+    attrs: &mut Vec<UserAttribute>,
+    negs: &mut Vec<bool>,
+) -> Result<Expr<(bool, UserAttribute)>> {
+    match raw {
+        RawExpr::Lit(user_attr) => {
+            attrs.push(user_attr.clone());
+            negs.push(is_neg);
+            Ok(Expr::Lit((is_neg, user_attr.clone())))
+        }
+        RawExpr::Not(inner) => helper(inner, !is_neg, attrs, negs),
+        RawExpr::And(lhs, rhs) => {
+            let lhs = Box::new(helper(lhs, is_neg, attrs, negs)?);
+            let rhs = Box::new(helper(rhs, is_neg, attrs, negs)?);
+            if is_neg {
+                Ok(Expr::Or(lhs, rhs))
+            } else {
+                Ok(Expr::And(lhs, rhs))
+            }
+        }
+        RawExpr::Or(lhs, rhs) => {
+            let lhs = Box::new(helper(lhs, is_neg, attrs, negs)?);
+            let rhs = Box::new(helper(rhs, is_neg, attrs, negs)?);
+            if is_neg {
+                Ok(Expr::And(lhs, rhs))
+            } else {
+                Ok(Expr::Or(lhs, rhs))
+            }
+        }
+        RawExpr::Threshold(k, children) => {
+            let n = children.len();
+            if *k == 0 || *k > n {
+                return Err(PolicyDiagnostic::new(format!(
+                    "threshold gate requires between 1 and {n} sub-expressions, but got k={k}"
+                ))
+                .into());
+            }
+            let k = if is_neg { n - k + 1 } else { *k };
+            let mut children = children
+                .iter()
+                .map(|child| helper(child, is_neg, attrs, negs))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter();
+
+            if k == 1 || k == n {
+                let Some(mut lhs) = children.next() else {
+                    return Err(PolicyDiagnostic::new(
+                        "threshold gate must have at least one sub-expression",
+                    )
+                    .into());
+                };
+                for rhs in children {
+                    lhs = if k == 1 {
+                        Expr::Or(Box::new(lhs), Box::new(rhs))
+                    } else {
+                        Expr::And(Box::new(lhs), Box::new(rhs))
+                    };
+                }
+                return Ok(lhs);
+            }
+
+            Ok(Expr::Threshold(k, children.collect()))
+        }
+    }
+}
+
+#[test]
+fn test_normalize_de_morgan() {
+    let a = RawExpr::Lit(UserAttribute::new("a", "x", "1"));
+    let b = RawExpr::Lit(UserAttribute::new("b", "y", "2"));
+    let c = RawExpr::Lit(UserAttribute::new("c", "z", "3"));
+
+    // !(a & (b | !c)) == !a | (!b & c)
+    let raw = RawExpr::Not(Box::new(RawExpr::And(
+        Box::new(a),
+        Box::new(RawExpr::Or(
+            Box::new(b),
+            Box::new(RawExpr::Not(Box::new(c))),
+        )),
+    )));
+    let (expr, attrs, negs) = normalize(&raw).unwrap();
+    assert_eq!(
+        expr,
+        Expr::Or(
+            Box::new(Expr::Lit((true, UserAttribute::new("a", "x", "1")))),
+            Box::new(Expr::And(
+                Box::new(Expr::Lit((true, UserAttribute::new("b", "y", "2")))),
+                Box::new(Expr::Lit((false, UserAttribute::new("c", "z", "3")))),
+            )),
+        )
+    );
+    assert_eq!(attrs.len(), 3);
+    assert_eq!(negs, vec![true, true, false]);
+}
+
+#[test]
+fn test_normalize_double_negation() {
+    let a = RawExpr::Lit(UserAttribute::new("a", "x", "1"));
+    let raw = RawExpr::Not(Box::new(RawExpr::Not(Box::new(a))));
+    let (expr, _, negs) = normalize(&raw).unwrap();
+    assert_eq!(expr, Expr::Lit((false, UserAttribute::new("a", "x", "1"))));
+    assert_eq!(negs, vec![false]);
+}
+
+#[test]
+fn test_normalize_threshold() {
+    let lits: Vec<RawExpr> = ["1", "2", "3"]
+        .iter()
+        .map(|v| RawExpr::Lit(UserAttribute::new("a", "x", v)))
+        .collect();
+
+    // !2of(a, b, c) == 2of(!a, !b, !c)
+    let raw = RawExpr::Not(Box::new(RawExpr::Threshold(2, lits.clone())));
+    let (expr, _, negs) = normalize(&raw).unwrap();
+    assert!(matches!(expr, Expr::Threshold(2, _)));
+    assert_eq!(negs, vec![true; 3]);
+
+    // !1of(a, b, c) == 3of(!a, !b, !c) == !a & !b & !c
+    let raw = RawExpr::Not(Box::new(RawExpr::Threshold(1, lits.clone())));
+    let (expr, _, _) = normalize(&raw).unwrap();
+    assert!(matches!(expr, Expr::And(_, _)));
+
+    assert!(normalize(&RawExpr::Threshold(0, lits.clone())).is_err());
+    assert!(normalize(&RawExpr::Threshold(4, lits)).is_err());
+}
@@ -0,0 +1,197 @@
+//! Integer comparisons over numeric attributes.
+//!
+//! A numeric attribute `auth.label = v` is issued as one user attribute per
+//! bit of `v`: `auth.label#<i>:<b>` where `b` is the `i`-th bit of `v`
+//! (see `UserAttribute::numeric`). A comparison such as `auth.label >= c` is
+//! then compiled into a formula over the bits, where "bit `i` is set" is the
+//! literal `auth.label#<i>:1`, and "bit `i` is not set" its negation. As `#`
+//! is not an identifier character, no other label collides with a bit label,
+//! and the parser only accepts it in this form. As a
+//! negated literal is only satisfied by a user that holds another value for
+//! the same label, only users that hold the numeric attribute can satisfy a
+//! comparison.
+//!
+//! The formula for `x >= c` is built from the least significant set bit of
+//! `c` upwards: if bit `i` of `c` is set, bit `i` of `x` must be set and the
+//! lower bits of `x` must be at least those of `c`; otherwise, either bit `i`
+//! of `x` is set, or the lower bits of `x` must be at least those of `c`. All
+//! other comparisons reduce to this one.
+
+use crate::policy::{UserAttribute, is_ident, normalize::RawExpr};
+
+/// Number of bits of a numeric attribute.
+pub const NUMERIC_BITS: u32 = 32;
+
+/// Comparison operators supported in numeric literals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+/// Label of the user attribute that holds bit `bit` of the numeric attribute
+/// `label`.
+pub(crate) fn bit_label(label: &str, bit: u32) -> String {
+    format!("{label}#{bit}")
+}
+
+/// Returns whether `label` is of the form returned by `bit_label`.
+pub(crate) fn is_bit_label(label: &str) -> bool {
+    let Some((label, bit)) = label.split_once('#') else {
+        return false;
+    };
+    is_ident(label)
+        && bit
+            .parse::<u32>()
+            .is_ok_and(|b| b < NUMERIC_BITS && b.to_string() == bit)
+}
+
+/// Literal that is satisfied iff bit `bit` of the numeric attribute is `set`.
+fn bit_is(auth: &str, label: &str, bit: u32, set: bool) -> RawExpr {
+    let lit = RawExpr::Lit(UserAttribute::new(auth, &bit_label(label, bit), "1"));
+    if set {
+        lit
+    } else {
+        RawExpr::Not(Box::new(lit))
+    }
+}
+
+/// Formula that is satisfied iff the numeric attribute is at least `c`.
+fn at_least(auth: &str, label: &str, c: u32) -> RawExpr {
+    if c == 0 {
+        // Any user that holds the numeric attribute.
+        let top = NUMERIC_BITS - 1;
+        return RawExpr::Or(
+            Box::new(bit_is(auth, label, top, true)),
+            Box::new(bit_is(auth, label, top, false)),
+        );
+    }
+
+    // Bits below the least significant set bit of `c` do not matter.
+    let low = c.trailing_zeros();
+    let mut expr = bit_is(auth, label, low, true);
+    for bit in low + 1..NUMERIC_BITS {
+        let lhs = Box::new(bit_is(auth, label, bit, true));
+        expr = if (c >> bit) & 1 == 1 {
+            RawExpr::And(lhs, Box::new(expr))
+        } else {
+            RawExpr::Or(lhs, Box::new(expr))
+        };
+    }
+    expr
+}
+
+/// # Description
+///
+/// Compiles the comparison `auth.label <op> value` into a formula over the
+/// bit attributes of `auth.label`.
+///
+/// # Returns
+///
+/// The formula, or an error message if no value satisfies the comparison
+/// (e.g. `< 0`).
+pub(crate) fn compare(
+    auth: &str,
+    label: &str,
+    op: Comparison,
+    value: u32,
+) -> Result<RawExpr, String> {
+    let never = || format!("no {NUMERIC_BITS}-bit value satisfies the comparison");
+    match op {
+        Comparison::Ge => Ok(at_least(auth, label, value)),
+        Comparison::Gt => {
+            let value = value.checked_add(1).ok_or_else(never)?;
+            Ok(at_least(auth, label, value))
+        }
+        Comparison::Lt => match value {
+            0 => Err(never()),
+            _ => Ok(RawExpr::Not(Box::new(at_least(auth, label, value)))),
+        },
+        Comparison::Le => match value.checked_add(1) {
+            Some(value) => Ok(RawExpr::Not(Box::new(at_least(auth, label, value)))),
+            None => Ok(at_least(auth, label, 0)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Policy, normalize::normalize};
+
+    fn satisfies(policy: &Policy, value: u32) -> bool {
+        policy
+            .evaluate(&UserAttribute::numeric("A", "n", value))
+            .is_satisfied()
+    }
+
+    #[test]
+    fn test_compare_matches_integer_comparison() {
+        let interesting = [
+            0,
+            1,
+            2,
+            3,
+            7,
+            8,
+            0xd0,
+            0xd1,
+            0xd2,
+            0xff,
+            0x100,
+            u32::MAX - 1,
+            u32::MAX,
+        ];
+        let ops = [
+            (Comparison::Lt, u32::lt as fn(&u32, &u32) -> bool),
+            (Comparison::Le, u32::le),
+            (Comparison::Gt, u32::gt),
+            (Comparison::Ge, u32::ge),
+        ];
+        for c in interesting {
+            for (op, cmp) in ops {
+                let Ok(raw) = compare("A", "n", op, c) else {
+                    assert!(interesting.iter().all(|x| !cmp(x, &c)), "{op:?} {c}");
+                    continue;
+                };
+                let (expr, attrs, negs) = normalize(&raw).unwrap();
+                let policy = Policy { expr, attrs, negs };
+                for x in interesting {
+                    assert_eq!(satisfies(&policy, x), cmp(&x, &c), "{x} {op:?} {c}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_compare_requires_attribute() {
+        // A user without the numeric attribute satisfies no comparison, not
+        // even one that holds for every value.
+        let raw = compare("A", "n", Comparison::Ge, 0).unwrap();
+        let (expr, attrs, negs) = normalize(&raw).unwrap();
+        let policy = Policy { expr, attrs, negs };
+        assert!(satisfies(&policy, 0));
+        assert!(
+            !policy
+                .evaluate(&[UserAttribute::new("A", "m", "0")])
+                .is_satisfied()
+        );
+    }
+
+    #[test]
+    fn test_compare_size() {
+        // Bits below the least significant set bit are skipped.
+        let raw = compare("A", "n", Comparison::Ge, 0x80000000).unwrap();
+        let (_, attrs, _) = normalize(&raw).unwrap();
+        assert_eq!(attrs.len(), 1);
+        let raw = compare("A", "n", Comparison::Ge, 0xd1).unwrap();
+        let (_, attrs, _) = normalize(&raw).unwrap();
+        assert_eq!(attrs.len(), NUMERIC_BITS as usize);
+    }
+}
@@ -1,6 +1,6 @@
-use anyhow::Result;
+use crate::policy::{PolicyDiagnostic, Span};
 use log::error;
-use std::str::from_utf8;
+use std::fmt;
 
 #[derive(PartialEq, Debug, Clone)]
 pub enum Token {
@@ -10,84 +10,161 @@ pub enum Token {
     Or,
     Not,
     Colon,
+    Comma,
     Dot,
+    Hash,
+    Lt,
+    Le,
+    Gt,
+    Ge,
     Ident(String),
+    /// Quoted string, with its escape sequences already resolved.
+    Str(String),
 }
 
-pub fn lex(input: &str) -> Result<Vec<Token>> {
-    if !input.is_ascii() {
-        error!("lexing error: policy must only contain ASCII character");
-        anyhow::bail!("lexing error: policy must only contain ASCII character");
+impl Token {
+    /// Name of the token kind, as listed in the expected tokens of a
+    /// `PolicyDiagnostic`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Token::LParen => "'('",
+            Token::RParen => "')'",
+            Token::And => "'&'",
+            Token::Or => "'|'",
+            Token::Not => "'!'",
+            Token::Colon => "':'",
+            Token::Comma => "','",
+            Token::Dot => "'.'",
+            Token::Hash => "'#'",
+            Token::Lt => "'<'",
+            Token::Le => "'<='",
+            Token::Gt => "'>'",
+            Token::Ge => "'>='",
+            Token::Ident(_) => "identifier",
+            Token::Str(_) => "string",
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(ident) => write!(f, "identifier '{ident}'"),
+            Token::Str(value) => write!(f, "string {value:?}"),
+            token => f.write_str(token.kind()),
+        }
     }
+}
+
+/// Splits `input` into tokens, each with its byte span in `input`.
+///
+/// Outside of quoted strings, only ASCII identifiers, operators and
+/// whitespace are allowed.
+pub fn lex(input: &str) -> Result<Vec<(Token, Span)>, PolicyDiagnostic> {
     let mut tokens = Vec::new();
     let mut idx = 0;
-    let input = input.as_bytes();
-    while idx < input.len() {
-        match input[idx] {
-            b' ' | b'\r' | b'\t' | b'\n' => idx += 1,
-            b'(' => {
-                tokens.push(Token::LParen);
-                idx += 1
-            }
-            b')' => {
-                tokens.push(Token::RParen);
-                idx += 1
+    let bytes = input.as_bytes();
+    while idx < bytes.len() {
+        let token = match bytes[idx] {
+            b' ' | b'\r' | b'\t' | b'\n' => {
+                idx += 1;
+                continue;
             }
-            b':' => {
-                tokens.push(Token::Colon);
-                idx += 1
+            b'(' => Token::LParen,
+            b')' => Token::RParen,
+            b':' => Token::Colon,
+            b',' => Token::Comma,
+            b'.' => Token::Dot,
+            b'#' => Token::Hash,
+            b'!' => Token::Not,
+            b'&' => Token::And,
+            b'|' => Token::Or,
+            b'<' | b'>' => {
+                let or_equal = bytes.get(idx + 1) == Some(&b'=');
+                let token = match (bytes[idx], or_equal) {
+                    (b'<', false) => Token::Lt,
+                    (b'<', true) => Token::Le,
+                    (_, false) => Token::Gt,
+                    (_, true) => Token::Ge,
+                };
+                let end = idx + 1 + or_equal as usize;
+                tokens.push((token, idx..end));
+                idx = end;
+                continue;
             }
-            b'.' => {
-                tokens.push(Token::Dot);
-                idx += 1
-            }
-            b'!' => {
-                tokens.push(Token::Not);
-                idx += 1
-            }
-            b'&' => {
-                tokens.push(Token::And);
-                idx += 1
-            }
-            b'|' => {
-                tokens.push(Token::Or);
-                idx += 1
+            b'"' => {
+                let (token, end) = string(input, idx)?;
+                tokens.push((token, idx..end));
+                idx = end;
+                continue;
             }
             _ => {
-                let (token, i) = ident(input, idx)?;
-                tokens.push(token);
-                idx = i
+                let (token, end) = ident(input, idx)?;
+                tokens.push((token, idx..end));
+                idx = end;
+                continue;
             }
-        }
+        };
+        tokens.push((token, idx..idx + 1));
+        idx += 1;
     }
 
     Ok(tokens)
 }
 
-fn ident(input: &[u8], start: usize) -> Result<(Token, usize)> {
+fn ident(input: &str, start: usize) -> Result<(Token, usize), PolicyDiagnostic> {
+    let bytes = input.as_bytes();
     let mut end = start;
-    while end < input.len() && (input[end].is_ascii_alphanumeric() || input[end] == b'_') {
+    while end < bytes.len() && (bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_') {
         end += 1;
     }
-    let str = from_utf8(&input[start..end]).unwrap();
-    if str.is_empty() {
-        error!(
-            "Illegal character '{}' found at index {}",
-            from_utf8(&input[start..start + 1]).unwrap(),
-            end
-        );
-        anyhow::bail!(
-            "illegal character found in policy (char={})",
-            from_utf8(&input[start..start + 1]).unwrap(),
+    if end == start {
+        let c = input[start..].chars().next().unwrap_or_default();
+        error!("ident(): illegal character found in policy (char={c}, index={start})");
+        return Err(
+            PolicyDiagnostic::new(format!("illegal character '{c}' outside of quotes"))
+                .at(start..start + c.len_utf8()),
         );
     }
-    Ok((Token::Ident(String::from(str)), end))
+    Ok((Token::Ident(String::from(&input[start..end])), end))
+}
+
+/// Lexes the quoted string that starts at `start`. Within the quotes, `\"`
+/// and `\\` escape a quote and a backslash, and any other character stands
+/// for itself.
+fn string(input: &str, start: usize) -> Result<(Token, usize), PolicyDiagnostic> {
+    let mut value = String::new();
+    let mut chars = input[start + 1..]
+        .char_indices()
+        .map(|(i, c)| (start + 1 + i, c));
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' => return Ok((Token::Str(value), idx + 1)),
+            '\\' => match chars.next() {
+                Some((_, c @ ('"' | '\\'))) => value.push(c),
+                Some((end, c)) => {
+                    error!("string(): invalid escape sequence in policy (index={idx})");
+                    return Err(
+                        PolicyDiagnostic::new(format!("invalid escape sequence '\\{c}'"))
+                            .at(idx..end + c.len_utf8())
+                            .expecting(&[r#"'\"'"#, r"'\\'"]),
+                    );
+                }
+                None => break,
+            },
+            c => value.push(c),
+        }
+    }
+    error!("string(): unterminated string in policy (index={start})");
+    Err(PolicyDiagnostic::new("unterminated string")
+        .at(start..start + 1)
+        .expecting(&["'\"'"]))
 }
 
 #[test]
 fn test_lexer() {
     let input = "x.b:a & (!x.b:a2 | orr.y:u) | anda.z:z";
-    let tokens = lex(input).unwrap();
+    let tokens: Vec<Token> = lex(input).unwrap().into_iter().map(|(t, _)| t).collect();
     assert_eq!(tokens.len(), 26);
     assert_eq!(tokens[0], Token::Ident(String::from("x")));
     assert_eq!(tokens[1], Token::Dot);
@@ -116,3 +193,54 @@ fn test_lexer() {
     assert_eq!(tokens[24], Token::Colon);
     assert_eq!(tokens[25], Token::Ident(String::from("z")));
 }
+
+#[test]
+fn test_lexer_threshold() {
+    let input = "2of(a.b:c, d.e:f)";
+    let tokens: Vec<Token> = lex(input).unwrap().into_iter().map(|(t, _)| t).collect();
+    assert_eq!(tokens.len(), 14);
+    assert_eq!(tokens[0], Token::Ident(String::from("2of")));
+    assert_eq!(tokens[1], Token::LParen);
+    assert_eq!(tokens[7], Token::Comma);
+    assert_eq!(tokens[13], Token::RParen);
+}
+
+#[test]
+fn test_lexer_spans() {
+    let tokens = lex("ab.c : d").unwrap();
+    let spans: Vec<Span> = tokens.into_iter().map(|(_, span)| span).collect();
+    assert_eq!(spans, vec![0..2, 2..3, 3..4, 5..6, 7..8]);
+
+    let tokens = lex("a.b>=1 & a.b<2").unwrap();
+    assert_eq!(tokens[3], (Token::Ge, 3..5));
+    assert_eq!(tokens[9], (Token::Lt, 12..13));
+
+    let diagnostic = lex("A.a:0 & B-b:0").unwrap_err();
+    assert_eq!(
+        diagnostic.message,
+        "illegal character '-' outside of quotes"
+    );
+    assert_eq!(diagnostic.span, Some(9..10));
+
+    let diagnostic = lex("A.a:\u{e9}").unwrap_err();
+    assert_eq!(diagnostic.span, Some(4..6));
+}
+
+#[test]
+fn test_lexer_strings() {
+    let tokens = lex(r#"corp.email:"alice@example.com" & k8s.ns:"t\"e\\am-é""#).unwrap();
+    assert_eq!(tokens.len(), 11);
+    assert_eq!(
+        tokens[4],
+        (Token::Str(String::from("alice@example.com")), 11..30)
+    );
+    assert_eq!(tokens[10].0, Token::Str(String::from(r#"t"e\am-é"#)));
+
+    let diagnostic = lex(r#"A.a:"team-a"#).unwrap_err();
+    assert_eq!(diagnostic.message, "unterminated string");
+    assert_eq!(diagnostic.span, Some(4..5));
+
+    let diagnostic = lex(r#"A.a:"a\nb""#).unwrap_err();
+    assert_eq!(diagnostic.message, r"invalid escape sequence '\n'");
+    assert_eq!(diagnostic.span, Some(6..8));
+}
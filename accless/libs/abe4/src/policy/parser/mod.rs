@@ -1,52 +1,66 @@
-use crate::policy::{
-    Expr, UserAttribute,
-    parser::lexer::{Token, lex},
+#[cfg(test)]
+use crate::policy::Expr;
+use crate::{
+    error::Result,
+    policy::{
+        Comparison, NUMERIC_BITS, PolicyDiagnostic, Span, UserAttribute,
+        normalize::{NormalizedPolicy, RawExpr, normalize},
+        numeric,
+        parser::lexer::{Token, lex},
+    },
 };
-use anyhow::Result;
 use log::error;
 
 mod lexer;
 
-type ParsedPolicy = (Expr<(bool, UserAttribute)>, Vec<UserAttribute>, Vec<bool>);
+/// Tokens that may start a primary expression.
+const EXPECTED_PRIM: &[&str] = &["'!'", "'('", "identifier"];
 
+/// Recursive-descent parser for the policy grammar.
+///
+/// The parser only checks the syntax of the policy, and produces a `RawExpr`
+/// that mirrors what the user wrote (including negations of arbitrary
+/// sub-expressions). Turning it into the negation normal form expected by the
+/// secret sharing scheme is done in `normalize`.
+///
+/// On a syntax error, the parser records a `PolicyDiagnostic` pointing at the
+/// offending token, and unwinds by returning `None`. Only the first error is
+/// kept.
 pub struct Parser {
-    tokens: Vec<Token>,
-    attrs: Vec<UserAttribute>,
-    negs: Vec<bool>,
+    tokens: Vec<(Token, Span)>,
     curr: usize,
-    is_neg: bool,
-    err_msg: Option<String>,
-    had_error: bool,
+    /// Empty span just past the end of the input, for errors at EOF.
+    eof: Span,
+    err: Option<PolicyDiagnostic>,
 }
 
 impl Parser {
-    fn new(tokens: Vec<Token>) -> Self {
+    fn new(tokens: Vec<(Token, Span)>, input_len: usize) -> Self {
         Parser {
             tokens,
-            attrs: Vec::new(),
-            negs: Vec::new(),
             curr: 0,
-            is_neg: false,
-            err_msg: None,
-            had_error: false,
+            eof: input_len..input_len,
+            err: None,
         }
     }
 
-    fn set_err_msg(&mut self, msg: &str) {
-        if !self.had_error {
-            self.err_msg = Some(String::from(msg));
+    fn fail(&mut self, message: String, span: Span, expected: &[&str]) {
+        if self.err.is_none() {
+            self.err = Some(PolicyDiagnostic::new(message).at(span).expecting(expected));
         }
-        self.had_error = true;
     }
 
-    fn advance(&mut self) {
-        self.curr += 1;
+    /// Records that the next token is not one of `expected`.
+    fn unexpected(&mut self, expected: &[&str]) {
+        let (message, span) = match self.tokens.get(self.curr) {
+            Some((token, span)) => (format!("unexpected {token}"), span.clone()),
+            None => (String::from("unexpected end of policy"), self.eof.clone()),
+        };
+        self.fail(message, span, expected);
     }
 
-    fn next(&mut self) -> Option<Token> {
-        let t = self.peek();
-        self.advance();
-        t
+    fn advance(&mut self) {
+        self.curr += 1;
     }
 
     fn try_next(&mut self, token: Token) -> Option<()> {
@@ -59,128 +73,298 @@ impl Parser {
         }
     }
 
-    fn peek(&mut self) -> Option<Token> {
-        if self.curr >= self.tokens.len() {
-            None
-        } else {
-            let t = self.tokens[self.curr].clone();
-            Some(t)
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.curr).map(|(t, _)| t.clone())
+    }
+
+    /// Returns the span of the next token.
+    fn span(&self) -> Span {
+        match self.tokens.get(self.curr) {
+            Some((_, span)) => span.clone(),
+            None => self.eof.clone(),
         }
     }
 
     fn require(&mut self, token: Token) -> Option<()> {
-        let t = self.next()?;
-        if t == token {
-            Some(())
-        } else {
-            self.set_err_msg(&format!(
-                "Found token '{:?}' but '{:?}' was expected",
-                t, token
-            ));
-            None
+        if self.try_next(token.clone()).is_some() {
+            return Some(());
         }
+        self.unexpected(&[token.kind()]);
+        None
     }
 
-    pub fn parse_policy(input: &str) -> Result<ParsedPolicy> {
-        let tokens = lex(input)?;
-        let mut parser = Parser::new(tokens);
+    /// Like `require`, but lists the operators that could also continue the
+    /// preceding expression as valid alternatives.
+    fn require_after_expr(&mut self, alternatives: &[&str]) -> Option<()> {
+        let Some(token) = alternatives.last() else {
+            return Some(());
+        };
+        if self.peek().is_some_and(|t| t.kind() == *token) {
+            self.advance();
+            return Some(());
+        }
+        let mut expected = vec!["'&'", "'|'"];
+        expected.extend_from_slice(alternatives);
+        self.unexpected(&expected);
+        None
+    }
 
-        let res = parser.or();
-        if parser.had_error {
-            match parser.err_msg.as_ref() {
-                Some(msg) => {
-                    return Err(anyhow::anyhow!(msg.clone()));
-                }
-                None => {
-                    return Err(anyhow::anyhow!("error parsing policy"));
-                }
-            }
+    /// Parses an attribute value, which is either an identifier or a quoted
+    /// string.
+    fn value(&mut self) -> Option<String> {
+        if let Some(Token::Ident(value) | Token::Str(value)) = self.peek() {
+            self.advance();
+            return Some(value);
         }
-        match res {
-            None => panic!("Unreachable"),
-            Some(exp) => Ok((exp, parser.attrs, parser.negs)),
+        self.unexpected(&["identifier", "string"]);
+        None
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        if let Some(Token::Ident(ident)) = self.peek() {
+            self.advance();
+            return Some(ident);
         }
+        self.unexpected(&["identifier"]);
+        None
     }
 
-    pub fn parse_user_attr(attr: &str) -> Result<(String, String, String)> {
-        let tokens = lex(attr)?;
-        let mut parser = Parser::new(tokens);
-        let res = parser.lit();
-        if parser.had_error {
-            if let Some(err_msg) = parser.err_msg.as_ref() {
-                error!("error parsing user attribute (attr={attr}, error={err_msg})");
-            }
-            anyhow::bail!("error parsing user attribute (attr={attr})");
+    /// Parses a label, which is either an identifier or the label `lbl#<bit>`
+    /// of a bit of the numeric attribute `lbl`.
+    fn label(&mut self) -> Option<String> {
+        let lbl = self.ident()?;
+        if self.try_next(Token::Hash).is_none() {
+            return Some(lbl);
         }
-        match res {
-            Some(Expr::Lit((_, user_attr))) => Ok((
-                user_attr.authority().to_string(),
-                user_attr.label().to_string(),
-                user_attr.attribute().to_string(),
-            )),
-            _ => panic!("Unreachable"),
+        let span = self.span();
+        let bit = self.number()?;
+        if bit >= NUMERIC_BITS {
+            self.fail(
+                format!("bit {bit} is out of range for a {NUMERIC_BITS}-bit numeric attribute"),
+                span,
+                &[],
+            );
+            return None;
         }
+        Some(numeric::bit_label(&lbl, bit))
+    }
+
+    /// Checks that all tokens have been consumed. `expected` lists the
+    /// tokens that could have continued the input instead.
+    fn end(&mut self, expected: &[&str]) -> Option<()> {
+        if self.curr < self.tokens.len() {
+            self.unexpected(expected);
+            return None;
+        }
+        Some(())
+    }
+
+    /// Runs `rule` over the tokens of `input`, and turns any syntax error
+    /// into a diagnostic that points into `input`.
+    fn run<T>(
+        input: &str,
+        rule: impl FnOnce(&mut Self) -> Option<T>,
+        expected_at_end: &[&str],
+    ) -> Result<T> {
+        let tokens = lex(input).map_err(|d| d.with_source(input))?;
+        let mut parser = Parser::new(tokens, input.len());
+        let res = rule(&mut parser).and_then(|t| parser.end(expected_at_end).map(|_| t));
+        match (res, parser.err) {
+            (Some(t), None) => Ok(t),
+            (_, Some(diagnostic)) => {
+                error!("run(): error parsing policy (input={input}, error={diagnostic})");
+                Err(diagnostic.with_source(input).into())
+            }
+            (None, None) => Err(PolicyDiagnostic::new("error parsing policy")
+                .with_source(input)
+                .into()),
+        }
+    }
+
+    pub fn parse_policy(input: &str) -> Result<NormalizedPolicy> {
+        let raw = Self::parse_raw_policy(input)?;
+        normalize(&raw)
+    }
+
+    /// Parses a policy without normalising it.
+    pub(crate) fn parse_raw_policy(input: &str) -> Result<RawExpr> {
+        Self::run(input, Self::or, &["'&'", "'|'", "end of policy"])
     }
 
-    fn or(&mut self) -> Option<Expr<(bool, UserAttribute)>> {
+    pub fn parse_user_attr(attr: &str) -> Result<(String, String, String)> {
+        let user_attr = Self::run(attr, Self::user_attr, &["end of attribute"])?;
+        Ok((
+            user_attr.authority().to_string(),
+            user_attr.label().to_string(),
+            user_attr.attribute().to_string(),
+        ))
+    }
+
+    fn or(&mut self) -> Option<RawExpr> {
         let mut lhs = self.and()?;
         while self.try_next(Token::Or).is_some() {
             let rhs = self.and()?;
-            if self.is_neg {
-                lhs = Expr::And(Box::new(lhs), Box::new(rhs));
-            } else {
-                lhs = Expr::Or(Box::new(lhs), Box::new(rhs));
-            }
+            lhs = RawExpr::Or(Box::new(lhs), Box::new(rhs));
         }
         Some(lhs)
     }
 
-    fn and(&mut self) -> Option<Expr<(bool, UserAttribute)>> {
+    fn and(&mut self) -> Option<RawExpr> {
         let mut lhs = self.not()?;
         while self.try_next(Token::And).is_some() {
             let rhs = self.not()?;
-            if self.is_neg {
-                lhs = Expr::Or(Box::new(lhs), Box::new(rhs))
-            } else {
-                lhs = Expr::And(Box::new(lhs), Box::new(rhs))
-            }
+            lhs = RawExpr::And(Box::new(lhs), Box::new(rhs))
         }
         Some(lhs)
     }
 
-    fn not(&mut self) -> Option<Expr<(bool, UserAttribute)>> {
+    fn not(&mut self) -> Option<RawExpr> {
         if self.try_next(Token::Not).is_some() {
-            self.is_neg = !self.is_neg;
             let exp = self.not()?;
-            self.is_neg = !self.is_neg;
-            return Some(exp);
+            return Some(RawExpr::Not(Box::new(exp)));
         }
         self.prim()
     }
 
-    fn prim(&mut self) -> Option<Expr<(bool, UserAttribute)>> {
+    fn prim(&mut self) -> Option<RawExpr> {
         if self.try_next(Token::LParen).is_some() {
             let exp = self.or()?;
-            self.require(Token::RParen);
+            self.require_after_expr(&["')'"])?;
             return Some(exp);
         }
-        self.lit()
+        if let Some(k) = self.threshold_prefix() {
+            return self.threshold(k);
+        }
+        if !matches!(self.peek(), Some(Token::Ident(_))) {
+            self.unexpected(EXPECTED_PRIM);
+            return None;
+        }
+        self.literal()
     }
 
-    fn lit(&mut self) -> Option<Expr<(bool, UserAttribute)>> {
-        if let Some(Token::Ident(auth)) = self.next() {
-            self.require(Token::Dot);
-            if let Some(Token::Ident(lbl)) = self.next() {
-                self.require(Token::Colon);
-                if let Some(Token::Ident(attr)) = self.next() {
-                    let user_attr = UserAttribute::new(&auth, &lbl, &attr);
-                    self.attrs.push(user_attr.clone());
-                    self.negs.push(self.is_neg);
-                    return Some(Expr::Lit((self.is_neg, user_attr)));
-                }
+    /// Parses an equality literal `auth.label:value`, or a comparison
+    /// `auth.label <op> number` over a numeric attribute.
+    fn literal(&mut self) -> Option<RawExpr> {
+        let auth = self.ident()?;
+        self.require(Token::Dot)?;
+        let lbl = self.label()?;
+        let op = match self.peek() {
+            Some(Token::Colon) => {
+                self.advance();
+                let attr = self.value()?;
+                return Some(RawExpr::Lit(UserAttribute::new(&auth, &lbl, &attr)));
+            }
+            // A single bit of a numeric attribute can not be compared.
+            _ if numeric::is_bit_label(&lbl) => {
+                self.unexpected(&["':'"]);
+                return None;
+            }
+            Some(Token::Lt) => Comparison::Lt,
+            Some(Token::Le) => Comparison::Le,
+            Some(Token::Gt) => Comparison::Gt,
+            Some(Token::Ge) => Comparison::Ge,
+            _ => {
+                self.unexpected(&["':'", "'<'", "'<='", "'>'", "'>='"]);
+                return None;
+            }
+        };
+        self.advance();
+
+        let span = self.span();
+        let value = self.number()?;
+        match numeric::compare(&auth, &lbl, op, value) {
+            Ok(expr) => Some(expr),
+            Err(reason) => {
+                self.fail(reason, span, &[]);
+                None
+            }
+        }
+    }
+
+    /// Parses an unsigned integer, in decimal or in hexadecimal with a `0x`
+    /// prefix.
+    fn number(&mut self) -> Option<u32> {
+        let Some(Token::Ident(ident)) = self.peek() else {
+            self.unexpected(&["number"]);
+            return None;
+        };
+        let value = match ident.strip_prefix("0x") {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => ident.parse::<u32>(),
+        };
+        match value {
+            Ok(value) => {
+                self.advance();
+                Some(value)
+            }
+            Err(e) => {
+                let span = self.span();
+                self.fail(format!("invalid number '{ident}' ({e})"), span, &[]);
+                None
             }
         }
-        None
+    }
+
+    /// Returns the threshold `k` if the next tokens are of the form `<k>of(`.
+    ///
+    /// A threshold gate starts with a single identifier made of a number and
+    /// the `of` suffix, directly followed by an opening parenthesis. We need
+    /// the look-ahead to tell the gate apart from an authority that happens to
+    /// be called e.g. `2of`.
+    fn threshold_prefix(&mut self) -> Option<usize> {
+        let Some(Token::Ident(ident)) = self.peek() else {
+            return None;
+        };
+        if self.tokens.get(self.curr + 1).map(|(t, _)| t) != Some(&Token::LParen) {
+            return None;
+        }
+        let k = ident.strip_suffix("of")?;
+        if k.is_empty() || !k.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        match k.parse::<usize>() {
+            Ok(k) => Some(k),
+            Err(_) => {
+                let span = self.span();
+                self.fail(format!("threshold '{k}' is out of range"), span, &[]);
+                Some(0)
+            }
+        }
+    }
+
+    /// Parses a threshold gate `<k>of(e_1, ..., e_n)`, which is satisfied if
+    /// at least `k` out of the `n` sub-expressions are satisfied.
+    fn threshold(&mut self, k: usize) -> Option<RawExpr> {
+        // Skip the `<k>of` identifier and the opening parenthesis.
+        let span = self.span();
+        self.advance();
+        self.advance();
+
+        let mut children = vec![self.or()?];
+        while self.try_next(Token::Comma).is_some() {
+            children.push(self.or()?);
+        }
+        self.require_after_expr(&["','", "')'"])?;
+
+        let n = children.len();
+        if k == 0 || k > n {
+            self.fail(
+                format!("threshold gate requires between 1 and {n} sub-expressions, but got k={k}"),
+                span,
+                &[],
+            );
+            return None;
+        }
+        Some(RawExpr::Threshold(k, children))
+    }
+
+    fn user_attr(&mut self) -> Option<UserAttribute> {
+        let auth = self.ident()?;
+        self.require(Token::Dot)?;
+        let lbl = self.label()?;
+        self.require(Token::Colon)?;
+        let attr = self.value()?;
+        Some(UserAttribute::new(&auth, &lbl, &attr))
     }
 }
 
@@ -215,3 +399,228 @@ fn test_parser() {
         )
     );
 }
+
+#[test]
+fn test_parser_threshold() {
+    fn pos(s: &str) -> Expr<(bool, UserAttribute)> {
+        let user_attr = UserAttribute::parse(s).unwrap();
+        Expr::Lit((false, user_attr))
+    }
+
+    fn neg(s: &str) -> Expr<(bool, UserAttribute)> {
+        let user_attr = UserAttribute::parse(s).unwrap();
+        Expr::Lit((true, user_attr))
+    }
+
+    let policy = "2of(a.wf:x, b.wf:x, c.wf:x & d.wf:y)";
+    let (expr, attrs, _) = Parser::parse_policy(policy).unwrap();
+    assert_eq!(attrs.len(), 4);
+    assert_eq!(
+        expr,
+        Expr::Threshold(
+            2,
+            vec![
+                pos("a.wf:x"),
+                pos("b.wf:x"),
+                Expr::And(Box::new(pos("c.wf:x")), Box::new(pos("d.wf:y")))
+            ]
+        )
+    );
+
+    // Negating a threshold gate flips it into its dual.
+    let policy = "!3of(a.wf:x, b.wf:x, c.wf:x, d.wf:x)";
+    let (expr, _, negs) = Parser::parse_policy(policy).unwrap();
+    assert_eq!(negs, vec![true; 4]);
+    assert_eq!(
+        expr,
+        Expr::Threshold(
+            2,
+            vec![neg("a.wf:x"), neg("b.wf:x"), neg("c.wf:x"), neg("d.wf:x")]
+        )
+    );
+
+    // Degenerate gates become plain conjunctions and disjunctions.
+    let (expr, _, _) = Parser::parse_policy("1of(a.wf:x, b.wf:x)").unwrap();
+    assert_eq!(
+        expr,
+        Expr::Or(Box::new(pos("a.wf:x")), Box::new(pos("b.wf:x")))
+    );
+    let (expr, _, _) = Parser::parse_policy("2of(a.wf:x, b.wf:x)").unwrap();
+    assert_eq!(
+        expr,
+        Expr::And(Box::new(pos("a.wf:x")), Box::new(pos("b.wf:x")))
+    );
+
+    // An authority called like a gate is still a literal.
+    let (expr, _, _) = Parser::parse_policy("2of.wf:x").unwrap();
+    assert_eq!(expr, pos("2of.wf:x"));
+
+    assert!(Parser::parse_policy("0of(a.wf:x, b.wf:x)").is_err());
+    assert!(Parser::parse_policy("3of(a.wf:x, b.wf:x)").is_err());
+    assert!(Parser::parse_policy("2of(a.wf:x, b.wf:x").is_err());
+}
+
+#[test]
+fn test_parser_negated_sub_expressions() {
+    let equivalent = [
+        ("!(a.x:1 & b.y:2)", "!a.x:1 | !b.y:2"),
+        ("!(a.x:1 | b.y:2)", "!a.x:1 & !b.y:2"),
+        ("!(a.x:1 & !(b.y:2 | c.z:3))", "!a.x:1 | (b.y:2 | c.z:3)"),
+        ("!!a.x:1 & !!!b.y:2", "a.x:1 & !b.y:2"),
+        ("!(!(a.x:1 & b.y:2))", "a.x:1 & b.y:2"),
+        ("!2of(a.x:1, b.y:2, c.z:3)", "2of(!a.x:1, !b.y:2, !c.z:3)"),
+    ];
+    for (lhs, rhs) in equivalent {
+        assert_eq!(
+            Parser::parse_policy(lhs).unwrap(),
+            Parser::parse_policy(rhs).unwrap(),
+            "{lhs} should normalise to {rhs}"
+        );
+    }
+
+    let raw = Parser::parse_raw_policy("!(a.x:1 & b.y:2)").unwrap();
+    assert!(matches!(raw, RawExpr::Not(_)));
+}
+
+#[test]
+fn test_parser_diagnostics() {
+    fn diagnose(policy: &str) -> PolicyDiagnostic {
+        match Parser::parse_raw_policy(policy) {
+            Err(crate::Abe4Error::PolicySyntax(diagnostic)) => *diagnostic,
+            res => panic!("expected a syntax error for {policy}, got {res:?}"),
+        }
+    }
+
+    let diagnostic = diagnose("A.a:0 & & B.b:0");
+    assert_eq!(diagnostic.message, "unexpected '&'");
+    assert_eq!(diagnostic.span, Some(8..9));
+    assert_eq!(diagnostic.expected, vec!["'!'", "'('", "identifier"]);
+    assert_eq!(diagnostic.source.as_deref(), Some("A.a:0 & & B.b:0"));
+
+    let diagnostic = diagnose("A.a:0 & B.b");
+    assert_eq!(diagnostic.message, "unexpected end of policy");
+    assert_eq!(diagnostic.span, Some(11..11));
+    assert_eq!(
+        diagnostic.expected,
+        vec!["':'", "'<'", "'<='", "'>'", "'>='"]
+    );
+
+    let diagnostic = diagnose("(A.a:0 | B.b:0 C.c:0");
+    assert_eq!(diagnostic.message, "unexpected identifier 'C'");
+    assert_eq!(diagnostic.span, Some(15..16));
+    assert_eq!(diagnostic.expected, vec!["'&'", "'|'", "')'"]);
+
+    // Trailing tokens are no longer silently ignored.
+    let diagnostic = diagnose("A.a:0 )");
+    assert_eq!(diagnostic.span, Some(6..7));
+    assert_eq!(diagnostic.expected, vec!["'&'", "'|'", "end of policy"]);
+
+    let diagnostic = diagnose("3of(a.wf:x, b.wf:x)");
+    assert!(diagnostic.message.starts_with("threshold gate requires"));
+    assert_eq!(diagnostic.span, Some(0..3));
+
+    let diagnostic = diagnose("A.a:0 | B.b:$");
+    assert_eq!(
+        diagnostic.message,
+        "illegal character '$' outside of quotes"
+    );
+    assert_eq!(diagnostic.span, Some(12..13));
+
+    assert!(Parser::parse_user_attr("A.a:0 & B.b:0").is_err());
+
+    // Only attribute values may be quoted.
+    let diagnostic = diagnose(r#""A".a:0"#);
+    assert_eq!(diagnostic.message, r#"unexpected string "A""#);
+    assert_eq!(diagnostic.expected, vec!["'!'", "'('", "identifier"]);
+    let diagnostic = diagnose("A.a:");
+    assert_eq!(diagnostic.expected, vec!["identifier", "string"]);
+}
+
+#[test]
+fn test_parser_quoted_values() {
+    let (auth, lbl, attr) = Parser::parse_user_attr(r#"corp.email:"alice@example.com""#).unwrap();
+    assert_eq!(
+        (auth.as_str(), lbl.as_str(), attr.as_str()),
+        ("corp", "email", "alice@example.com")
+    );
+
+    let policy = r#"k8s.ns:"team-a" & !corp.email:"bob@example.com""#;
+    let (expr, attrs, negs) = Parser::parse_policy(policy).unwrap();
+    assert_eq!(attrs[0], UserAttribute::new("k8s", "ns", "team-a"));
+    assert_eq!(
+        attrs[1],
+        UserAttribute::new("corp", "email", "bob@example.com")
+    );
+    assert_eq!(negs, vec![false, true]);
+    assert!(matches!(expr, Expr::And(_, _)));
+
+    // Quoting a plain identifier does not change the attribute.
+    assert_eq!(
+        Parser::parse_policy(r#"A.a:"x0""#).unwrap(),
+        Parser::parse_policy("A.a:x0").unwrap()
+    );
+}
+
+#[test]
+fn test_parser_comparisons() {
+    fn bits(policy: &str) -> Vec<(UserAttribute, bool)> {
+        let (_, attrs, negs) = Parser::parse_policy(policy).unwrap();
+        attrs.into_iter().zip(negs).collect()
+    }
+
+    // Bits below the least significant set bit of the constant are skipped.
+    let lits = bits("A.tcb >= 0x80000000");
+    assert_eq!(lits, vec![(UserAttribute::new("A", "tcb#31", "1"), false)]);
+    assert_eq!(bits("A.tcb > 0x7fffffff"), lits);
+    assert_eq!(bits("A.tcb >= 2147483648"), lits);
+
+    // Upper bounds are negated lower bounds.
+    let lits = bits("A.tcb < 0x80000000");
+    assert_eq!(lits, vec![(UserAttribute::new("A", "tcb#31", "1"), true)]);
+    assert_eq!(bits("A.tcb <= 0x7fffffff"), lits);
+
+    // Ranges are conjunctions of comparisons.
+    assert!(Parser::parse_policy("A.epoch > 42 & A.epoch <= 100 & B.b:0").is_ok());
+
+    // Bit labels can not be spelled as identifiers, so a plain attribute does
+    // not satisfy comparisons, but printed comparisons parse back.
+    assert_ne!(lits[0].0, UserAttribute::parse("A.tcb_bit31:1").unwrap());
+    assert_eq!(UserAttribute::parse("A.tcb#31:1").unwrap(), lits[0].0);
+    let policy = crate::policy::Policy::parse("A.tcb >= 42 & !A.tcb#0:1").unwrap();
+    assert_eq!(
+        crate::policy::Policy::parse(&format!("{policy:?}")).unwrap(),
+        policy
+    );
+
+    for (policy, message, span) in [
+        (
+            "A.epoch < 0",
+            "no 32-bit value satisfies the comparison",
+            10..11,
+        ),
+        (
+            "A.epoch > 0xffffffff",
+            "no 32-bit value satisfies the comparison",
+            10..20,
+        ),
+        (
+            "A.epoch > 0x1ffffffff",
+            "invalid number '0x1ffffffff'",
+            10..21,
+        ),
+        ("A.epoch >= abc", "invalid number 'abc'", 11..14),
+        ("A.tcb#32:1", "bit 32 is out of range", 6..8),
+        ("A.tcb#x:1", "invalid number 'x'", 6..7),
+        ("A.tcb#3 >= 1", "unexpected '>='", 8..10),
+        ("A.#3:1", "unexpected '#'", 2..3),
+    ] {
+        let Err(crate::Abe4Error::PolicySyntax(diagnostic)) = Parser::parse_policy(policy) else {
+            panic!("expected a syntax error for {policy}");
+        };
+        assert!(
+            diagnostic.message.starts_with(message),
+            "{policy}: {diagnostic}"
+        );
+        assert_eq!(diagnostic.span, Some(span), "{policy}");
+    }
+}
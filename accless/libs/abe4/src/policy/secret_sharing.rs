@@ -1,37 +1,108 @@
 use super::UserAttribute;
-use crate::policy::{Expr, Policy};
+use crate::{
+    curve::ScalarField,
+    policy::{Expr, Policy},
+};
+use ark_ff::PrimeField;
 
-pub fn share_secret(policy: &Policy) -> Vec<(UserAttribute, Vec<i64>)> {
+/// Computes the linear secret sharing scheme (LSSS) for a policy.
+///
+/// For each literal in the policy (in order), we return the attribute together
+/// with its share, expressed as a linear combination of the sharing vector
+/// `v`. Each term in the combination is a `(column, coefficient)` pair, and
+/// column zero holds the secret. Conjunctions and disjunctions only produce
+/// coefficients of one or minus one, whereas threshold gates use the
+/// evaluations of a random polynomial (Shamir) at `x = 1, ..., n`.
+///
+/// # Panics
+///
+/// If a threshold gate `kof(n ...)` has a coefficient `n^(k-1)` that does not
+/// fit in an `i64`. `share_secret_in` computes the same sharing in a prime
+/// field, where every gate fits.
+pub fn share_secret(policy: &Policy) -> Vec<(UserAttribute, Vec<(usize, i64)>)> {
+    share_secret_with(policy, 1, -1, &|x, d| {
+        (x as i64)
+            .checked_pow(d)
+            .expect("threshold gate coefficient does not fit in an i64")
+    })
+}
+
+/// Computes the same sharing as `share_secret`, with the coefficients
+/// reduced in the field `F`.
+pub fn share_secret_in<F: PrimeField>(policy: &Policy) -> Vec<(UserAttribute, Vec<(usize, F)>)> {
+    share_secret_with(policy, F::one(), -F::one(), &|x, d| {
+        F::from(x as u64).pow([d as u64])
+    })
+}
+
+/// Computes the sharing of `policy`, where `pow(x, d)` is the coefficient
+/// `x^d` of the `d`-th column of a threshold gate at the evaluation point `x`.
+fn share_secret_with<T: Clone>(
+    policy: &Policy,
+    one: T,
+    minus_one: T,
+    pow: &dyn Fn(usize, u32) -> T,
+) -> Vec<(UserAttribute, Vec<(usize, T)>)> {
     let mut n = 0;
     let mut result = Vec::new();
-    helper(&mut n, &mut result, vec![0], &policy.expr);
+    let coeffs = Coeffs {
+        one: one.clone(),
+        minus_one,
+        pow,
+    };
+    helper(&mut n, &mut result, vec![(0, one)], &policy.expr, &coeffs);
     result
 }
 
-fn helper(
-    n: &mut i64,
-    result: &mut Vec<(UserAttribute, Vec<i64>)>,
-    idcs: Vec<i64>,
+struct Coeffs<'a, T> {
+    one: T,
+    minus_one: T,
+    pow: &'a dyn Fn(usize, u32) -> T,
+}
+
+fn helper<T: Clone>(
+    n: &mut usize,
+    result: &mut Vec<(UserAttribute, Vec<(usize, T)>)>,
+    idcs: Vec<(usize, T)>,
     expr: &Expr<(bool, UserAttribute)>,
+    coeffs: &Coeffs<T>,
 ) {
     match expr {
         Expr::Lit((_, user_attr)) => result.push((user_attr.clone(), idcs)),
         Expr::Or(lhs, rhs) => {
-            helper(n, result, idcs.clone(), lhs);
-            helper(n, result, idcs, rhs)
+            helper(n, result, idcs.clone(), lhs, coeffs);
+            helper(n, result, idcs, rhs, coeffs)
         }
         Expr::And(lhs, rhs) => {
             let mut idcs_l = idcs.clone();
             *n += 1;
-            idcs_l.push(*n);
-            let idcs_r = vec![-*n];
-            helper(n, result, idcs_l, lhs);
-            helper(n, result, idcs_r, rhs)
+            idcs_l.push((*n, coeffs.one.clone()));
+            let idcs_r = vec![(*n, coeffs.minus_one.clone())];
+            helper(n, result, idcs_l, lhs, coeffs);
+            helper(n, result, idcs_r, rhs, coeffs)
+        }
+        Expr::Threshold(k, children) => {
+            // The polynomial p(x) = s + a_1 x + ... + a_{k-1} x^{k-1}, where s
+            // is our share and each a_d takes a fresh column.
+            let first_col = *n + 1;
+            *n += k - 1;
+            for (i, child) in children.iter().enumerate() {
+                let x = i + 1;
+                let mut idcs_x = idcs.clone();
+                for d in 0..(k - 1) {
+                    idcs_x.push((first_col + d, (coeffs.pow)(x, (d + 1) as u32)));
+                }
+                helper(n, result, idcs_x, child, coeffs);
+            }
         }
     }
 }
 
-fn satisfies(user_attrs: &Vec<UserAttribute>, curr: &UserAttribute, is_neg: bool) -> Option<usize> {
+pub(super) fn satisfies(
+    user_attrs: &[UserAttribute],
+    curr: &UserAttribute,
+    is_neg: bool,
+) -> Option<usize> {
     let mut matches = 0;
     let mut others = 0;
     for user_attr in user_attrs {
@@ -53,17 +124,43 @@ fn satisfies(user_attrs: &Vec<UserAttribute>, curr: &UserAttribute, is_neg: bool
     }
 }
 
-pub fn reconstruct_secret(user_attrs: &Vec<UserAttribute>, policy: &Policy) -> Option<Vec<usize>> {
+pub fn reconstruct_secret(user_attrs: &[UserAttribute], policy: &Policy) -> Option<Vec<usize>> {
+    // The choice of field is irrelevant, as we drop the coefficients.
+    let idcs = reconstruct_secret_with_coeffs::<ScalarField>(user_attrs, policy)?;
+    Some(idcs.into_iter().map(|(idx, _)| idx).collect())
+}
+
+/// Finds the cheapest set of shares that reconstructs the secret, together
+/// with the coefficient that each share must be multiplied by.
+pub fn reconstruct_secret_with_coeffs<F: PrimeField>(
+    user_attrs: &[UserAttribute],
+    policy: &Policy,
+) -> Option<Vec<(usize, F)>> {
     let mut idx = 0;
     let (_, idcs) = aux(&mut idx, user_attrs, &policy.expr)?;
     Some(idcs)
 }
 
-fn aux(
+/// Lagrange coefficient at zero for the evaluation point `x`, given the set of
+/// evaluation points `xs` used for interpolation.
+fn lagrange_coeff<F: PrimeField>(x: usize, xs: &[usize]) -> F {
+    let mut num = F::one();
+    let mut den = F::one();
+    for &y in xs.iter().filter(|&&y| y != x) {
+        num *= F::from(y as u64);
+        den *= F::from(y as u64) - F::from(x as u64);
+    }
+    // Evaluation points are distinct, so the denominator is never zero.
+    num * den.inverse().unwrap_or_default()
+}
+
+type Reconstruction<F> = (usize, Vec<(usize, F)>);
+
+fn aux<F: PrimeField>(
     idx: &mut usize,
-    user_attrs: &Vec<UserAttribute>,
+    user_attrs: &[UserAttribute],
     expr: &Expr<(bool, UserAttribute)>,
-) -> Option<(usize, Vec<usize>)> {
+) -> Option<Reconstruction<F>> {
     match expr {
         Expr::Lit((is_neg, user_attr)) => match satisfies(user_attrs, user_attr, *is_neg) {
             None => {
@@ -71,7 +168,7 @@ fn aux(
                 None
             }
             Some(cost) => {
-                let idcs = vec![*idx];
+                let idcs = vec![(*idx, F::one())];
                 *idx += 1;
                 Some((cost, idcs))
             }
@@ -103,32 +200,57 @@ fn aux(
                 }
             }
         }
+        Expr::Threshold(k, children) => {
+            // Evaluate all children (to keep `idx` in sync) and keep the `k`
+            // cheapest ones that are satisfied.
+            let mut satisfied: Vec<(usize, Reconstruction<F>)> = children
+                .iter()
+                .enumerate()
+                .filter_map(|(i, child)| aux(idx, user_attrs, child).map(|rec| (i + 1, rec)))
+                .collect();
+            if satisfied.len() < *k {
+                return None;
+            }
+            satisfied.sort_by_key(|(_, (cost, _))| *cost);
+            satisfied.truncate(*k);
+
+            let xs: Vec<usize> = satisfied.iter().map(|(x, _)| *x).collect();
+            let mut cost = 0;
+            let mut idcs = Vec::new();
+            for (x, (cost_x, idcs_x)) in satisfied {
+                let coeff = lagrange_coeff::<F>(x, &xs);
+                cost += cost_x;
+                idcs.extend(idcs_x.into_iter().map(|(j, c)| (j, c * coeff)));
+            }
+            idcs.sort_by_key(|(j, _)| *j);
+            Some((cost, idcs))
+        }
     }
 }
 
 #[test]
 fn test_secret_reconstruction() {
-    let user_1 = vec!["anda.z:z"]
+    let user_1: Vec<UserAttribute> = vec!["anda.z:z"]
         .iter()
         .map(|ua| UserAttribute::parse(ua).unwrap())
         .collect();
-    let user_2 = vec!["x.b:a"]
+    let user_2: Vec<UserAttribute> = vec!["x.b:a"]
         .iter()
         .map(|ua| UserAttribute::parse(ua).unwrap())
         .collect();
-    let user_3 = vec!["x.b:a", "orr.y:u"]
+    let user_3: Vec<UserAttribute> = vec!["x.b:a", "orr.y:u"]
         .iter()
         .map(|ua| UserAttribute::parse(ua).unwrap())
         .collect();
-    let user_4 = vec!["x.b:a", "x.b:a2", "orr.y:u", "anda.z:z"]
+    let user_4: Vec<UserAttribute> = vec!["x.b:a", "x.b:a2", "orr.y:u", "anda.z:z"]
         .iter()
         .map(|ua| UserAttribute::parse(ua).unwrap())
         .collect();
-    let user_5 = vec!["x.b:a", "x.b:a2", "orr.y:u", "anda.z:z2"]
+    let user_5: Vec<UserAttribute> = vec!["x.b:a", "x.b:a2", "orr.y:u", "anda.z:z2"]
         .iter()
         .map(|ua| UserAttribute::parse(ua).unwrap())
         .collect();
-    let user_6 = vec!["x.b:a", "x.b:a3", "orr.y:u", "anda.z:z"]
+    let user_6: Vec<UserAttribute> = vec!["x.b:a", "x.b:a3", "orr.y:u", "anda.z:z"]
         .iter()
         .map(|ua| UserAttribute::parse(ua).unwrap())
         .collect();
@@ -155,8 +277,48 @@ fn test_secret_sharing() {
     let policy = Policy::parse("x.b:a & !(!x.b:a2 | orr.y:u) | anda.z:z").unwrap();
     let sharing = share_secret(&policy);
     assert_eq!(sharing.len(), 4);
-    assert_eq!(sharing[0].1, vec![0, 1]);
-    assert_eq!(sharing[1].1, vec![-1, 2]);
-    assert_eq!(sharing[2].1, vec![-2]);
-    assert_eq!(sharing[3].1, vec![0]);
+    assert_eq!(sharing[0].1, vec![(0, 1), (1, 1)]);
+    assert_eq!(sharing[1].1, vec![(1, -1), (2, 1)]);
+    assert_eq!(sharing[2].1, vec![(2, -1)]);
+    assert_eq!(sharing[3].1, vec![(0, 1)]);
+}
+
+#[test]
+fn test_secret_sharing_threshold() {
+    let policy = Policy::parse("x.b:a & 3of(x.b:c, x.b:d, x.b:e, x.b:f)").unwrap();
+    let sharing = share_secret(&policy);
+    assert_eq!(sharing.len(), 5);
+    assert_eq!(sharing[0].1, vec![(0, 1), (1, 1)]);
+    assert_eq!(sharing[1].1, vec![(1, -1), (2, 1), (3, 1)]);
+    assert_eq!(sharing[2].1, vec![(1, -1), (2, 2), (3, 4)]);
+    assert_eq!(sharing[3].1, vec![(1, -1), (2, 3), (3, 9)]);
+    assert_eq!(sharing[4].1, vec![(1, -1), (2, 4), (3, 16)]);
+
+    // The field sharing has the same coefficients, reduced mod p.
+    let field_sharing = share_secret_in::<ScalarField>(&policy);
+    assert_eq!(field_sharing[2].1[0], (1, -ScalarField::from(1u64)));
+    assert_eq!(field_sharing[4].1[2], (3, ScalarField::from(16u64)));
+}
+
+#[test]
+fn test_secret_reconstruction_threshold() {
+    let policy = Policy::parse("2of(x.b:a, y.b:a, z.b:a)").unwrap();
+    let user_attrs: Vec<UserAttribute> = ["x.b:a", "z.b:a"]
+        .iter()
+        .map(|ua| UserAttribute::parse(ua).unwrap())
+        .collect();
+    assert_eq!(reconstruct_secret(&user_attrs, &policy), Some(vec![0, 2]));
+
+    // The coefficients must interpolate the polynomial at zero: the shares
+    // are p(1) and p(3), so p(0) = 3/2 p(1) - 1/2 p(3).
+    let coeffs = reconstruct_secret_with_coeffs::<ScalarField>(&user_attrs, &policy).unwrap();
+    let two_inv = ark_ff::Field::inverse(&ScalarField::from(2u64)).unwrap();
+    assert_eq!(coeffs[0], (0, ScalarField::from(3u64) * two_inv));
+    assert_eq!(coeffs[1], (2, -two_inv));
+
+    let user_attrs: Vec<UserAttribute> = ["x.b:a", "z.b:b"]
+        .iter()
+        .map(|ua| UserAttribute::parse(ua).unwrap())
+        .collect();
+    assert_eq!(reconstruct_secret(&user_attrs, &policy), None);
 }
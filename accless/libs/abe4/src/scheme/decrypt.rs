@@ -1,24 +1,71 @@
 use crate::{
-    curve::{G, Gt, H, ScalarField, pairing},
-    hashing::{hash_attr, hash_gid},
-    policy::Policy,
+    curve::Curve,
+    error::{Abe4Error, Result},
+    hashing::HashingContext,
+    policy::{Evaluation, Policy},
     scheme::{
         group_pairs::group_pairs,
         iota::Iota,
         tau::Tau,
-        types::{Ciphertext, USK},
+        types::{Ciphertext, PartialUSK, USK},
     },
 };
 use ark_ec::{CurveGroup, Group, VariableBaseMSM};
-use ark_ff::Field;
-use ark_std::{Zero, ops::Neg};
-use std::collections::HashSet;
+use ark_ff::One;
+use ark_std::{Zero, cfg_iter, ops::Neg};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 
-fn solve_lse(usk: &USK, policy: &Policy) -> Option<(Vec<usize>, Vec<usize>)> {
+type Coeffs<F> = HashMap<usize, F>;
+/// Shares used to decrypt: the non-negated ones, the negated ones, and the
+/// reconstruction coefficient of each.
+type Solution<F> = (Vec<usize>, Vec<usize>, Coeffs<F>);
+
+fn solve_lse<C: Curve>(usk: &USK<C>, policy: &Policy) -> Result<Solution<C::ScalarField>> {
     let user_attrs = usk.get_user_attributes();
-    let eps_all = policy.reconstruct_secret(&user_attrs)?;
-    let (eps_not_vec, eps_vec) = eps_all.into_iter().partition(|i| policy.get(*i).1);
-    Some((eps_vec, eps_not_vec))
+    let eps_all = policy
+        .reconstruct_secret_with_coeffs::<C::ScalarField>(&user_attrs)
+        .ok_or_else(|| Abe4Error::UnsatisfiedPolicy {
+            missing: match policy.evaluate(&user_attrs) {
+                Evaluation::Unsatisfied(missing) => {
+                    missing.iter().map(|p| format!("{p:?}")).collect()
+                }
+                Evaluation::Satisfied(_) => Vec::new(),
+            },
+        })?;
+    let coeffs: Coeffs<C::ScalarField> = eps_all.iter().cloned().collect();
+    let (eps_not_vec, eps_vec) = eps_all
+        .into_iter()
+        .map(|(i, _)| i)
+        .partition(|i| policy.get(*i).1);
+    Ok((eps_vec, eps_not_vec, coeffs))
+}
+
+fn partial_key<'a, C: Curve>(usk: &'a USK<C>, auth: &str) -> Result<&'a PartialUSK<C>> {
+    usk.get_partial_key(auth)
+        .ok_or_else(|| Abe4Error::MissingAuthorityKey(auth.to_string()))
+}
+
+/// Looks up a component of a partial key, failing if the key was not
+/// generated for the attribute.
+fn component<'a, K, V>(map: &'a HashMap<K, V>, key: &K) -> Result<&'a V>
+where
+    K: std::hash::Hash + Eq + std::fmt::Debug,
+{
+    map.get(key).ok_or_else(|| {
+        Abe4Error::InvalidInput(format!("user secret key has no component for {key:?}"))
+    })
+}
+
+/// Scales the contribution of the `j`-th share by its reconstruction
+/// coefficient. Policies without threshold gates only have unit coefficients,
+/// so we skip the scalar multiplication in that case.
+fn weigh<P: Group>(p: P, coeffs: &Coeffs<P::ScalarField>, j: usize) -> P {
+    match coeffs.get(&j) {
+        Some(coeff) if !coeff.is_one() => p * coeff,
+        _ => p,
+    }
 }
 
 /// # Description
@@ -28,7 +75,8 @@ fn solve_lse(usk: &USK, policy: &Policy) -> Option<(Vec<usize>, Vec<usize>)> {
 /// This function attempts to decrypt a `Ciphertext` using a user's secret key
 /// (`USK`). If the user's attributes (embedded in the `USK`) satisfy the policy
 /// associated with the `Ciphertext`, the original symmetric key (`Gt`) is
-/// recovered. Otherwise, decryption fails and `None` is returned.
+/// recovered. Otherwise, decryption fails with
+/// `Abe4Error::UnsatisfiedPolicy`.
 ///
 /// # Arguments
 ///
@@ -41,197 +89,353 @@ fn solve_lse(usk: &USK, policy: &Policy) -> Option<(Vec<usize>, Vec<usize>)> {
 ///
 /// # Returns
 ///
-/// An `Option<Gt>` containing the recovered symmetric key if decryption is
-/// successful, or `None` otherwise.
-pub fn decrypt(
-    usk: &USK,
+/// The recovered symmetric key if decryption is successful. Otherwise, an
+/// `Abe4Error::UnsatisfiedPolicy` if the key's attributes do not satisfy the
+/// policy, an `Abe4Error::GidMismatch` if the key was issued for another
+/// `gid`, or an `Abe4Error::InvalidInput` if `iota` and `tau` do not match the
+/// key and the policy.
+pub fn decrypt<C: Curve>(
+    usk: &USK<C>,
     gid: &str,
     iota: &Iota,
     tau: &Tau,
     policy: &Policy,
-    ct: &Ciphertext,
-) -> Option<Gt> {
-    let (eps_vec, eps_not_vec) = solve_lse(usk, policy)?;
-    let mut k = Gt::ONE;
-    let mut c_1 = H::zero();
-    let mut c_3 = H::zero();
-    for j in eps_vec.iter().chain(eps_not_vec.iter()) {
-        c_1 += ct.c_1_vec[*j];
-        c_3 += ct.c_3_vec[*j];
+    ct: &Ciphertext<C>,
+) -> Result<C::Gt> {
+    check_gid(usk, gid)?;
+    let hashing = HashingContext::new();
+    let gid_hashed = hashing.hash_gid(gid);
+    decrypt_with(usk, gid_hashed, &hashing, iota, tau, policy, ct)
+}
+
+/// Fails with `Abe4Error::GidMismatch` if a partial key in `usk` was issued
+/// for a GID other than `gid`. Partial keys with an unknown (empty) GID, as
+/// decoded from the legacy wire format, are not checked.
+pub fn check_gid<C: Curve>(usk: &USK<C>, gid: &str) -> Result<()> {
+    for partial_usk in usk.partial_keys.values() {
+        if !partial_usk.gid.is_empty() && partial_usk.gid != gid {
+            return Err(Abe4Error::GidMismatch {
+                expected: gid.to_string(),
+                found: partial_usk.gid.clone(),
+            });
+        }
     }
-    k *= pairing(G::generator(), c_3).0;
-    k *= pairing(hash_gid(gid), c_1).0;
-
-    let eps_by_auth_iota = group_pairs(&eps_vec, |j| {
-        let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
-        (auth.clone(), iota.get(&auth, &lbl, &attr))
-    });
-    let eps_by_tau = group_pairs(&eps_vec, |j| {
-        let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
-        tau.get(&auth, &lbl, &attr)
-    });
-    let eps_by_tau_tilde = group_pairs(&eps_vec, |j| {
-        let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
-        tau.get_tilde(&auth, &lbl, &attr)
-    });
-    let eps_not_by_tau_tilde = group_pairs(&eps_not_vec, |j| {
-        let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
-        tau.get_tilde(&auth, &lbl, &attr)
-    });
-    let eps_not_by_auth_lbl_attr = group_pairs(&eps_not_vec, |j| policy.get(j).0.auth_lbl_attr());
-
-    let mut domain_pos = HashSet::new();
-    for k in eps_by_tau.keys() {
-        domain_pos.insert(k);
+    Ok(())
+}
+
+/// Like `decrypt`, but takes the already hashed GID of the key, whose
+/// partial keys must have been checked with `check_gid`, and hashes
+/// attributes through `hashing`.
+pub fn decrypt_with<C: Curve>(
+    usk: &USK<C>,
+    gid_hashed: <C::G as CurveGroup>::Affine,
+    hashing: &HashingContext<C>,
+    iota: &Iota,
+    tau: &Tau,
+    policy: &Policy,
+    ct: &Ciphertext<C>,
+) -> Result<C::Gt> {
+    let plan = DecryptionPlan::new(usk, iota, tau, policy)?;
+    decrypt_planned(&plan, gid_hashed, hashing, ct)
+}
+
+/// # Description
+///
+/// The part of a decryption that only depends on the key and the policy:
+/// which shares of the policy the key decrypts, their reconstruction
+/// coefficients, and how they are grouped to share pairings.
+///
+/// Computing the plan once, and decrypting many times with
+/// `decrypt_planned`, amortises this work across ciphertexts encrypted under
+/// the same policy.
+pub struct DecryptionPlan<'a, C: Curve> {
+    usk: &'a USK<C>,
+    iota: &'a Iota,
+    tau: &'a Tau,
+    policy: &'a Policy,
+    eps_vec: Vec<usize>,
+    eps_not_vec: Vec<usize>,
+    coeffs: Coeffs<C::ScalarField>,
+    eps_by_auth_iota: HashMap<(String, usize), Vec<usize>>,
+    eps_by_tau: HashMap<usize, Vec<usize>>,
+    eps_by_tau_tilde: HashMap<usize, Vec<usize>>,
+    eps_not_by_tau_tilde: HashMap<usize, Vec<usize>>,
+    eps_not_by_auth_lbl_attr: HashMap<(String, String, String), Vec<usize>>,
+    domain_pos: HashSet<usize>,
+}
+
+impl<'a, C: Curve> DecryptionPlan<'a, C> {
+    /// # Returns
+    ///
+    /// The plan, or an `Abe4Error::UnsatisfiedPolicy` if the key's attributes
+    /// do not satisfy the policy, or an `Abe4Error::InvalidInput` if `iota`
+    /// and `tau` do not match the key and the policy.
+    pub fn new(usk: &'a USK<C>, iota: &'a Iota, tau: &'a Tau, policy: &'a Policy) -> Result<Self> {
+        let (eps_vec, eps_not_vec, coeffs) = solve_lse(usk, policy)?;
+
+        let eps_by_auth_iota = group_pairs(&eps_vec, |j| {
+            let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
+            Ok((auth.clone(), iota.get(&auth, &lbl, &attr)?))
+        })?;
+        let eps_by_tau = group_pairs(&eps_vec, |j| {
+            let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
+            tau.get(&auth, &lbl, &attr)
+        })?;
+        let eps_by_tau_tilde = group_pairs(&eps_vec, |j| {
+            let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
+            tau.get_tilde(&auth, &lbl, &attr)
+        })?;
+        let eps_not_by_tau_tilde = group_pairs(&eps_not_vec, |j| {
+            let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
+            tau.get_tilde(&auth, &lbl, &attr)
+        })?;
+        let eps_not_by_auth_lbl_attr =
+            group_pairs(&eps_not_vec, |j| Ok(policy.get(j).0.auth_lbl_attr()))?;
+
+        let mut domain_pos = HashSet::new();
+        for k in eps_by_tau.keys() {
+            domain_pos.insert(*k);
+        }
+        for k in eps_by_tau_tilde.keys() {
+            domain_pos.insert(*k);
+        }
+
+        Ok(Self {
+            usk,
+            iota,
+            tau,
+            policy,
+            eps_vec,
+            eps_not_vec,
+            coeffs,
+            eps_by_auth_iota,
+            eps_by_tau,
+            eps_by_tau_tilde,
+            eps_not_by_tau_tilde,
+            eps_not_by_auth_lbl_attr,
+            domain_pos,
+        })
     }
-    for k in eps_by_tau_tilde.keys() {
-        domain_pos.insert(k);
+}
+
+/// Like `decrypt_with`, for the key and the policy that `plan` was computed
+/// for.
+pub fn decrypt_planned<C: Curve>(
+    plan: &DecryptionPlan<'_, C>,
+    gid_hashed: <C::G as CurveGroup>::Affine,
+    hashing: &HashingContext<C>,
+    ct: &Ciphertext<C>,
+) -> Result<C::Gt> {
+    let pairs = decryption_pairs(plan, gid_hashed, hashing, ct)?;
+    let (p, q): (Vec<C::G>, Vec<C::H>) = pairs.into_iter().unzip();
+    Ok(C::multi_pairing(p, q))
+}
+
+/// Number of pairs, at the start of the result of `decryption_pairs`, that do
+/// not involve the key.
+pub const KEYLESS_PAIRS: usize = 2;
+
+/// # Description
+///
+/// Computes the arguments to the pairings whose product is the decrypted
+/// key.
+///
+/// # Returns
+///
+/// The pairs, where the first `KEYLESS_PAIRS` only depend on the ciphertext
+/// and the GID, and each of the others has exactly one element of the key.
+pub fn decryption_pairs<C: Curve>(
+    plan: &DecryptionPlan<'_, C>,
+    gid_hashed: <C::G as CurveGroup>::Affine,
+    hashing: &HashingContext<C>,
+    ct: &Ciphertext<C>,
+) -> Result<Vec<(C::G, C::H)>> {
+    let DecryptionPlan {
+        usk,
+        iota,
+        tau,
+        policy,
+        eps_vec,
+        eps_not_vec,
+        coeffs,
+        eps_by_auth_iota,
+        eps_by_tau,
+        eps_by_tau_tilde,
+        eps_not_by_tau_tilde,
+        eps_not_by_auth_lbl_attr,
+        domain_pos,
+    } = plan;
+    let (usk, iota, tau, policy) = (*usk, *iota, *tau, *policy);
+    // Arguments to the pairings whose product is the decrypted key. We
+    // collect them all to compute a single multi-pairing at the end.
+    let mut pairs: Vec<(C::G, C::H)> = Vec::new();
+    let mut c_1 = C::H::zero();
+    let mut c_3 = C::H::zero();
+    for j in eps_vec.iter().chain(eps_not_vec.iter()) {
+        c_1 += weigh(ct.c_1_vec[*j], coeffs, *j);
+        c_3 += weigh(ct.c_3_vec[*j], coeffs, *j);
     }
+    pairs.push((C::G::generator(), c_3));
+    pairs.push((gid_hashed.into(), c_1));
 
     let cost_a_pos = eps_by_auth_iota.len() + eps_by_tau.len();
     let cost_b_pos = domain_pos.len();
 
     if cost_a_pos < cost_b_pos {
-        for ((auth, iota), js) in eps_by_auth_iota.iter() {
-            let k_1_1 = usk.get_partial_key(auth).unwrap().k_1_1_vec[*iota].neg();
-            let mut c_4 = H::zero();
-            for &j in js {
-                let ua = policy.get(j).0;
-                let auth = ua.authority();
-                let lbl = ua.label();
-                let attr = ua.attribute();
-                let s_tilde = tau.get_tilde(auth, lbl, attr);
-                c_4 += ct.c_4_vec[s_tilde];
-            }
-            k *= pairing(k_1_1, c_4).0;
-        }
+        pairs.extend(
+            cfg_iter!(eps_by_auth_iota)
+                .map(|((auth, iota), js)| {
+                    let k_1_1 = partial_key(usk, auth)?.k_1_1_vec[*iota].neg();
+                    let mut c_4 = C::H::zero();
+                    for &j in js {
+                        let ua = policy.get(j).0;
+                        let auth = ua.authority();
+                        let lbl = ua.label();
+                        let attr = ua.attribute();
+                        let s_tilde = tau.get_tilde(auth, lbl, attr)?;
+                        c_4 += weigh(ct.c_4_vec[s_tilde], coeffs, j);
+                    }
+                    Ok((k_1_1, c_4))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        );
 
-        for (j_under_tau, js) in eps_by_tau {
-            let c_4 = ct.c_4_vec[j_under_tau];
-            let mut k_1 = G::zero();
-            for j in js {
-                let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
-                let usk = usk.get_partial_key(&auth).unwrap();
-                k_1 += usk.k_1_2_map.get(&(lbl, attr)).unwrap().neg();
-            }
-            k *= pairing(k_1, c_4).0;
-        }
+        pairs.extend(
+            cfg_iter!(eps_by_tau)
+                .map(|(j_under_tau, js)| {
+                    let c_4 = ct.c_4_vec[*j_under_tau];
+                    let mut k_1 = C::G::zero();
+                    for &j in js {
+                        let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
+                        let usk = partial_key(usk, &auth)?;
+                        k_1 += weigh(component(&usk.k_1_2_map, &(lbl, attr))?.neg(), coeffs, j);
+                    }
+                    Ok((k_1, c_4))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        );
     } else {
-        for j_under_tau_or_tau_tilde in domain_pos {
-            let c_4 = ct.c_4_vec[*j_under_tau_or_tau_tilde];
-
-            let tmp = Vec::new();
-            let js = eps_by_tau.get(j_under_tau_or_tau_tilde).unwrap_or(&tmp);
-            let mut k_1_2 = G::zero();
-            for j in js {
-                let (auth, lbl, attr) = policy.get(*j).0.auth_lbl_attr();
-                let usk = usk.get_partial_key(&auth).unwrap();
-                k_1_2 += usk.k_1_2_map.get(&(lbl, attr)).unwrap().neg();
-            }
-
-            let js = eps_by_tau_tilde
-                .get(j_under_tau_or_tau_tilde)
-                .unwrap_or(&tmp);
-            let mut k_1_1 = G::zero();
-            for j in js {
-                let (auth, lbl, attr) = policy.get(*j).0.auth_lbl_attr();
-                let iota = iota.get(&auth, &lbl, &attr);
-                k_1_1 += usk.get_partial_key(&auth).unwrap().k_1_1_vec[iota].neg();
-            }
-            k *= pairing(k_1_1 + k_1_2, c_4).0;
-        }
-    }
+        pairs.extend(
+            cfg_iter!(domain_pos)
+                .map(|j_under_tau_or_tau_tilde| {
+                    let c_4 = ct.c_4_vec[*j_under_tau_or_tau_tilde];
 
-    for ((auth, iota), js) in eps_by_auth_iota {
-        let mut c_2 = G::zero();
-        for j in js {
-            c_2 += ct.c_2_vec[j];
-        }
-        let usk = usk.get_partial_key(&auth).unwrap();
-        let k_4 = usk.k_4_vec[iota];
-        k *= pairing(c_2, k_4).0;
+                    let tmp = Vec::new();
+                    let js = eps_by_tau.get(j_under_tau_or_tau_tilde).unwrap_or(&tmp);
+                    let mut k_1_2 = C::G::zero();
+                    for j in js {
+                        let (auth, lbl, attr) = policy.get(*j).0.auth_lbl_attr();
+                        let usk = partial_key(usk, &auth)?;
+                        k_1_2 += weigh(component(&usk.k_1_2_map, &(lbl, attr))?.neg(), coeffs, *j);
+                    }
+
+                    let js = eps_by_tau_tilde
+                        .get(j_under_tau_or_tau_tilde)
+                        .unwrap_or(&tmp);
+                    let mut k_1_1 = C::G::zero();
+                    for j in js {
+                        let (auth, lbl, attr) = policy.get(*j).0.auth_lbl_attr();
+                        let iota = iota.get(&auth, &lbl, &attr)?;
+                        let k_1_1_j = partial_key(usk, &auth)?.k_1_1_vec[iota].neg();
+                        k_1_1 += weigh(k_1_1_j, coeffs, *j);
+                    }
+                    Ok((k_1_1 + k_1_2, c_4))
+                })
+                .collect::<Result<Vec<_>>>()?,
+        );
     }
 
-    for (j_under_tau, js) in eps_not_by_tau_tilde {
-        let c_4 = ct.c_4_vec[j_under_tau];
+    pairs.extend(
+        cfg_iter!(eps_by_auth_iota)
+            .map(|((auth, iota), js)| {
+                let mut c_2 = C::G::zero();
+                for &j in js {
+                    c_2 += weigh(ct.c_2_vec[j], coeffs, j);
+                }
+                let k_4 = partial_key(usk, auth)?.k_4_vec[*iota];
+                Ok((c_2, k_4))
+            })
+            .collect::<Result<Vec<_>>>()?,
+    );
 
-        let mut k_2 = G::zero();
-        for j in js.iter() {
-            let (auth, lbl) = policy.get(*j).0.auth_lbl();
-            let usk = usk.get_partial_key(&auth).unwrap();
-            k_2 += usk.k_2_map.get(&lbl).unwrap().neg();
-        }
+    pairs.extend(
+        cfg_iter!(eps_not_by_tau_tilde)
+            .map(|(j_under_tau, js)| {
+                let c_4 = ct.c_4_vec[*j_under_tau];
 
-        let mut k_3 = G::zero();
-        for j in js {
-            let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
-            let x_attr_not = hash_attr(&attr);
-            let usk = usk.get_partial_key(&auth).unwrap();
-
-            let attrs: Vec<String> = usk
-                .k_1_2_map
-                .keys()
-                .filter_map(|k| {
-                    if k.0.eq(&lbl) {
-                        Some(k.1.clone())
-                    } else {
-                        None
+                let mut k_2 = C::G::zero();
+                for j in js.iter() {
+                    let (auth, lbl) = policy.get(*j).0.auth_lbl();
+                    let usk = partial_key(usk, &auth)?;
+                    k_2 += weigh(component(&usk.k_2_map, &lbl)?.neg(), coeffs, *j);
+                }
+
+                let mut k_3 = C::G::zero();
+                for &j in js {
+                    let (auth, lbl, attr) = policy.get(j).0.auth_lbl_attr();
+                    let x_attr_not = hashing.hash_attr(&attr);
+                    let usk = partial_key(usk, &auth)?;
+
+                    let attrs: Vec<String> = usk
+                        .k_1_2_map
+                        .keys()
+                        .filter_map(|k| {
+                            if k.0.eq(&lbl) {
+                                Some(k.1.clone())
+                            } else {
+                                None
+                            }
+                        })
+                        .collect();
+
+                    let mut k_3_bases = Vec::with_capacity(attrs.len());
+                    let mut k_3_exps = Vec::with_capacity(attrs.len());
+                    let one = C::ScalarField::one();
+                    for attr in attrs {
+                        let x_attr = hashing.hash_attr(&attr);
+                        let e = -one / (x_attr_not - x_attr);
+                        k_3_exps.push(e);
+                        k_3_bases
+                            .push(component(&usk.k_3_map, &(lbl.clone(), attr))?.into_affine());
                     }
-                })
-                .collect();
-
-            let mut k_3_bases = Vec::with_capacity(attrs.len());
-            let mut k_3_exps = Vec::with_capacity(attrs.len());
-            let one = ScalarField::from(1);
-            for attr in attrs {
-                let x_attr = hash_attr(&attr);
-                let e = -one / (x_attr_not - x_attr);
-                k_3_exps.push(e);
-                k_3_bases.push(
-                    usk.k_3_map
-                        .get(&(lbl.clone(), attr.clone()))
-                        .unwrap()
-                        .into_affine(),
-                );
-            }
-            k_3 += G::msm(&k_3_bases, &k_3_exps).unwrap();
-        }
-        k *= pairing(k_2 + k_3, c_4).0;
-    }
+                    k_3 += weigh(C::G::msm(&k_3_bases, &k_3_exps).unwrap(), coeffs, j);
+                }
+                Ok((k_2 + k_3, c_4))
+            })
+            .collect::<Result<Vec<_>>>()?,
+    );
 
-    for ((auth, lbl, attr), js) in eps_not_by_auth_lbl_attr {
-        let mut c_2 = G::zero();
-        for j in js {
-            c_2 += ct.c_2_vec[j];
-        }
+    pairs.extend(
+        cfg_iter!(eps_not_by_auth_lbl_attr)
+            .map(|((auth, lbl, attr), js)| {
+                let mut c_2 = C::G::zero();
+                for &j in js {
+                    c_2 += weigh(ct.c_2_vec[j], coeffs, j);
+                }
+
+                let x_attr_not = hashing.hash_attr(attr);
+                let usk = partial_key(usk, auth)?;
 
-        let x_attr_not = hash_attr(&attr);
-        let usk = usk.get_partial_key(&auth).unwrap();
-
-        let attrs: Vec<String> = usk
-            .k_1_2_map
-            .keys()
-            .filter_map(|k| {
-                if k.0.eq(&lbl) {
-                    Some(k.1.clone())
-                } else {
-                    None
+                let attrs: Vec<String> = usk
+                    .k_1_2_map
+                    .keys()
+                    .filter_map(|k| if k.0.eq(lbl) { Some(k.1.clone()) } else { None })
+                    .collect();
+                let mut k_5_bases = Vec::with_capacity(attrs.len());
+                let mut k_5_exps = Vec::with_capacity(attrs.len());
+                let one = C::ScalarField::one();
+                for attr in attrs {
+                    let x_attr = hashing.hash_attr(&attr);
+                    let e = one / (x_attr_not - x_attr);
+                    let iota = iota.get(auth, lbl, &attr)?;
+                    k_5_exps.push(e);
+                    k_5_bases.push(usk.k_5_vec[iota].into_affine());
                 }
+                let k_5 = C::H::msm(&k_5_bases, &k_5_exps).unwrap();
+                Ok((c_2, k_5))
             })
-            .collect();
-        let mut k_5_bases = Vec::with_capacity(attrs.len());
-        let mut k_5_exps = Vec::with_capacity(attrs.len());
-        let one = ScalarField::from(1);
-        for attr in attrs {
-            let x_attr = hash_attr(&attr);
-            let e = one / (x_attr_not - x_attr);
-            let iota = iota.get(&auth, &lbl, &attr);
-            k_5_exps.push(e);
-            k_5_bases.push(usk.k_5_vec[iota].into_affine());
-        }
-        let k_5 = H::msm(&k_5_bases, &k_5_exps).unwrap();
-        k *= pairing(c_2, k_5).0;
-    }
+            .collect::<Result<Vec<_>>>()?,
+    );
 
-    Some(k)
+    Ok(pairs)
 }
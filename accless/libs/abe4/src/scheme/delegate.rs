@@ -0,0 +1,178 @@
+//! Delegation of user secret keys.
+//!
+//! Every component of a partial user secret key is randomised by the `r` and
+//! `r_not` of the index of its attribute, and each of these can be shifted
+//! using only public values: `k_1_1[i]` by `b' * δ`, `k_4[i]` by `h * δ`,
+//! `k_1_2[lbl:x]` by `(H(lbl, 0) + H(x) * H(lbl, 1)) * δ`, and likewise for
+//! the negated components. A key holder can therefore derive a fresh key for
+//! a subset of their attributes, without contacting the authorities.
+//!
+//! `k_2[lbl]` is randomised by the sum of the `r_not` of all the attributes of
+//! `lbl`, so it cannot be restricted to some of them: a delegated key holds,
+//! for each of its labels, every value that the original key holds.
+
+use crate::{
+    curve::Curve,
+    error::{Abe4Error, Result},
+    hashing::{
+        HashSign::{Neg, Pos},
+        HashingContext,
+    },
+    policy::UserAttribute,
+    scheme::{
+        decrypt::check_gid,
+        iota::Iota,
+        types::{MPK, PartialMPK, PartialUSK, USK},
+    },
+};
+use ark_ec::Group;
+use ark_ff::UniformRand;
+use ark_std::rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+pub fn delegate<C: Curve>(
+    mut rng: impl Rng,
+    mpk: &MPK<C>,
+    usk: &USK<C>,
+    gid: &str,
+    iota: &Iota,
+    subset: &[UserAttribute],
+) -> Result<USK<C>> {
+    check_gid(usk, gid)?;
+
+    // Group the given array of `UserAttribute`s by authority, and find out
+    // how many indices the delegated key needs, as `keygen` does.
+    let mut user_attr_by_auth: HashMap<&str, Vec<&UserAttribute>> = HashMap::new();
+    let mut n = 0;
+    for ua in subset {
+        n = std::cmp::max(n, iota.get(ua.authority(), ua.label(), ua.attribute())? + 1);
+        user_attr_by_auth
+            .entry(ua.authority())
+            .or_default()
+            .push(ua);
+    }
+
+    let hashing = HashingContext::new();
+    let mut delegated = USK::<C>::new();
+    for (auth, uas) in user_attr_by_auth {
+        let partial_usk = usk.get_partial_key(auth).ok_or_else(|| {
+            Abe4Error::InvalidInput(format!("user secret key has no partial key for {auth}"))
+        })?;
+        let partial_mpk = mpk
+            .get_partial_key(auth)
+            .ok_or_else(|| Abe4Error::MissingAuthorityKey(auth.to_string()))?;
+        let partial_usk =
+            delegate_partial(&mut rng, partial_mpk, partial_usk, &uas, iota, n, &hashing)?;
+        delegated.add_partial_key(partial_usk);
+    }
+    Ok(delegated)
+}
+
+fn delegate_partial<C: Curve>(
+    mut rng: impl Rng,
+    mpk: &PartialMPK<C>,
+    usk: &PartialUSK<C>,
+    user_attrs: &[&UserAttribute],
+    iota: &Iota,
+    n: usize,
+    hashing: &HashingContext<C>,
+) -> Result<PartialUSK<C>> {
+    // The delegated key must hold attributes of `usk`, and all the values of
+    // each of its labels.
+    let kept: HashSet<(&str, &str)> = user_attrs
+        .iter()
+        .map(|ua| (ua.label(), ua.attribute()))
+        .collect();
+    for (lbl, attr) in kept.iter() {
+        if !usk
+            .k_1_2_map
+            .contains_key(&(lbl.to_string(), attr.to_string()))
+        {
+            return Err(Abe4Error::InvalidInput(format!(
+                "user secret key does not hold {}.{lbl}:{attr}",
+                usk.auth
+            )));
+        }
+    }
+    let labels: HashSet<&str> = kept.iter().map(|(lbl, _)| *lbl).collect();
+    for (lbl, attr) in usk.k_1_2_map.keys() {
+        if labels.contains(lbl.as_str()) && !kept.contains(&(lbl.as_str(), attr.as_str())) {
+            return Err(Abe4Error::InvalidInput(format!(
+                "cannot delegate {}.{lbl} without {}.{lbl}:{attr}",
+                usk.auth, usk.auth
+            )));
+        }
+    }
+    if usk.k_1_1_vec.len() < n || usk.k_4_vec.len() < n || usk.k_5_vec.len() < n {
+        return Err(Abe4Error::InvalidKey(format!(
+            "{}: key has fewer than {n} indices",
+            usk.auth
+        )));
+    }
+
+    let mut delta_vec = Vec::with_capacity(n);
+    let mut delta_not_vec = Vec::with_capacity(n);
+    for _ in 0..n {
+        delta_vec.push(C::ScalarField::rand(&mut rng));
+        delta_not_vec.push(C::ScalarField::rand(&mut rng));
+    }
+
+    let h = C::H::generator();
+    let mut k_1_1_vec = Vec::with_capacity(n);
+    let mut k_4_vec = Vec::with_capacity(n);
+    let mut k_5_vec = Vec::with_capacity(n);
+    for i in 0..n {
+        k_1_1_vec.push(usk.k_1_1_vec[i] + mpk.b_prime * delta_vec[i]);
+        k_4_vec.push(usk.k_4_vec[i] + h * delta_vec[i]);
+        k_5_vec.push(usk.k_5_vec[i] + h * delta_not_vec[i]);
+    }
+
+    let mut k_1_2_map = HashMap::new();
+    let mut k_3_map = HashMap::new();
+    let mut delta_lab_map: HashMap<&str, C::ScalarField> = HashMap::new();
+    for (lbl, attr) in kept {
+        let key = (lbl.to_string(), attr.to_string());
+        let i = iota.get(&usk.auth, lbl, attr)?;
+        let delta = delta_vec[i];
+        let delta_not = delta_not_vec[i];
+        let x_attr = hashing.hash_attr(attr);
+
+        let lbl_pos_0: C::G = hashing.hash_lbl(&usk.auth, lbl, Pos, 0).into();
+        let lbl_pos_1: C::G = hashing.hash_lbl(&usk.auth, lbl, Pos, 1).into();
+        let k_1_2 = usk.k_1_2_map[&key] + (lbl_pos_0 + lbl_pos_1 * x_attr) * delta;
+
+        let k_3 = usk.k_3_map.get(&key).ok_or_else(|| {
+            Abe4Error::InvalidKey(format!(
+                "{}: key has no k_3 component for {lbl}:{attr}",
+                usk.auth
+            ))
+        })?;
+        let lbl_neg_0: C::G = hashing.hash_lbl(&usk.auth, lbl, Neg, 0).into();
+        let lbl_neg_1: C::G = hashing.hash_lbl(&usk.auth, lbl, Neg, 1).into();
+        let k_3 = *k_3 + (lbl_neg_0 + lbl_neg_1 * x_attr) * delta_not;
+
+        k_1_2_map.insert(key.clone(), k_1_2);
+        k_3_map.insert(key, k_3);
+        *delta_lab_map.entry(lbl).or_default() += delta_not;
+    }
+
+    let mut k_2_map = HashMap::new();
+    for (lbl, delta_lab) in delta_lab_map {
+        let k_2 = usk.k_2_map.get(lbl).ok_or_else(|| {
+            Abe4Error::InvalidKey(format!("{}: key has no k_2 component for {lbl}", usk.auth))
+        })?;
+        let lbl_neg_1: C::G = hashing.hash_lbl(&usk.auth, lbl, Neg, 1).into();
+        k_2_map.insert(lbl.to_string(), *k_2 + lbl_neg_1 * delta_lab);
+    }
+
+    Ok(PartialUSK {
+        auth: usk.auth.clone(),
+        gid: usk.gid.clone(),
+        k_1_1_vec,
+        k_1_2_map,
+        k_2_map,
+        k_3_map,
+        k_4_vec,
+        k_5_vec,
+    })
+}
@@ -1,8 +1,9 @@
 use crate::{
-    curve::{G, Gt, H, ScalarField, pairing},
+    curve::Curve,
+    error::{Abe4Error, Result},
     hashing::{
         HashSign::{Neg, Pos},
-        hash_attr, hash_lbl,
+        HashingContext,
     },
     policy::Policy,
     scheme::{
@@ -10,45 +11,106 @@ use crate::{
         types::{Ciphertext, MPK},
     },
 };
-use ark_ec::{Group, VariableBaseMSM};
-use ark_ff::UniformRand;
-use ark_std::{ops::Mul, rand::Rng};
-use std::collections::HashMap;
+use ark_ec::{CurveGroup, Group, VariableBaseMSM};
+use ark_ff::{Field, PrimeField, UniformRand};
+use ark_std::{cfg_into_iter, cfg_iter, ops::Mul, rand::Rng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-fn share_secret(
+/// Draws the secret shares of the rows of a policy, given the split of the
+/// policy's secret into the columns of its share-generating matrix.
+fn share_secret<F: PrimeField>(
     mut rng: impl Rng,
-    secret: ScalarField,
-    policy: &Policy,
-) -> (Vec<ScalarField>, Vec<ScalarField>, usize) {
-    let n = policy.len();
-    let splits = policy.share_secret();
-    let mut v_vec = Vec::with_capacity(n);
-    let mut v_prime_vec = Vec::with_capacity(n);
-    let mut lambda_vec = Vec::with_capacity(n);
-    let mut mu_vec = Vec::with_capacity(n);
-    let zero = ScalarField::from(0);
-    for _ in 0..n {
-        v_vec.push(ScalarField::rand(&mut rng));
-        v_prime_vec.push(ScalarField::rand(&mut rng));
-        lambda_vec.push(zero);
-        mu_vec.push(zero);
+    secret: F,
+    splits: &[Vec<(usize, F)>],
+    num_cols: usize,
+) -> (Vec<F>, Vec<F>) {
+    let mut v_vec = Vec::with_capacity(num_cols);
+    let mut v_prime_vec = Vec::with_capacity(num_cols);
+    let mut lambda_vec = Vec::with_capacity(splits.len());
+    let mut mu_vec = Vec::with_capacity(splits.len());
+    let zero = F::zero();
+    for _ in 0..num_cols {
+        v_vec.push(F::rand(&mut rng));
+        v_prime_vec.push(F::rand(&mut rng));
     }
     v_vec[0] = secret;
     v_prime_vec[0] = zero;
-    for (i, (_, idcs)) in splits.iter().enumerate() {
-        for j in idcs {
-            if *j >= 0 {
-                let k = *j as usize;
-                lambda_vec[i] += v_vec[k];
-                mu_vec[i] += v_prime_vec[k];
-            } else {
-                let k = (0 - *j) as usize;
-                lambda_vec[i] -= v_vec[k];
-                mu_vec[i] -= v_prime_vec[k];
-            }
+    for idcs in splits {
+        let mut lambda = zero;
+        let mut mu = zero;
+        for (col, coeff) in idcs {
+            lambda += *coeff * v_vec[*col];
+            mu += *coeff * v_prime_vec[*col];
         }
+        lambda_vec.push(lambda);
+        mu_vec.push(mu);
+    }
+    (lambda_vec, mu_vec)
+}
+
+/// Policy-dependent inputs of one row of the ciphertext.
+struct Row<C: Curve> {
+    auth: String,
+    is_neg: bool,
+    s: usize,
+    s_tilde: usize,
+    x_attr: C::ScalarField,
+    lbl_0: <C::G as CurveGroup>::Affine,
+    lbl_1: <C::G as CurveGroup>::Affine,
+}
+
+/// # Description
+///
+/// The part of an encryption that only depends on the policy: the split of
+/// the secret over the policy's share-generating matrix, the `Tau` indices,
+/// and the hashes of the labels and attributes of each row.
+///
+/// Computing the plan once, and encrypting many times with
+/// `encrypt_planned`, amortises this work across encryptions under the same
+/// policy.
+pub struct EncryptionPlan<C: Curve> {
+    splits: Vec<Vec<(usize, C::ScalarField)>>,
+    num_cols: usize,
+    num_s: usize,
+    rows: Vec<Row<C>>,
+}
+
+impl<C: Curve> EncryptionPlan<C> {
+    pub fn new(policy: &Policy, tau: &Tau, hashing: &HashingContext<C>) -> Result<Self> {
+        let splits: Vec<Vec<_>> = policy
+            .share_secret_in::<C::ScalarField>()
+            .into_iter()
+            .map(|(_, idcs)| idcs)
+            .collect();
+        let num_cols = splits
+            .iter()
+            .flat_map(|idcs| idcs.iter().map(|(col, _)| col + 1))
+            .max()
+            .unwrap_or(1);
+        let rows = (0..policy.len())
+            .map(|j| {
+                let (user_attr, is_neg) = policy.get(j);
+                let (auth, lbl, attr) = user_attr.auth_lbl_attr();
+                let sign = if is_neg { Neg } else { Pos };
+                Ok(Row {
+                    s: tau.get(&auth, &lbl, &attr)?,
+                    s_tilde: tau.get_tilde(&auth, &lbl, &attr)?,
+                    x_attr: hashing.hash_attr(&attr),
+                    lbl_0: hashing.hash_lbl(&auth, &lbl, sign, 0),
+                    lbl_1: hashing.hash_lbl(&auth, &lbl, sign, 1),
+                    auth,
+                    is_neg,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            splits,
+            num_cols,
+            num_s: std::cmp::max(tau.get_max(), tau.get_tilde_max()) + 1,
+            rows,
+        })
     }
-    (lambda_vec, mu_vec, n)
 }
 
 /// # Description
@@ -74,75 +136,85 @@ fn share_secret(
 /// A tuple containing:
 /// - `Gt`: The symmetric key (plaintext) that was encrypted.
 /// - `Ciphertext`: The ciphertext of the symmetric key.
-pub fn encrypt(mut rng: impl Rng, mpk: &MPK, policy: &Policy, tau: &Tau) -> (Gt, Ciphertext) {
-    let s = ScalarField::rand(&mut rng);
-    let m = std::cmp::max(tau.get_max(), tau.get_tilde_max());
-    let mut s_vec = Vec::with_capacity(m + 1);
-    for _ in 0..=m {
-        s_vec.push(ScalarField::rand(&mut rng));
-    }
-    let (lambda_vec, mu_vec, n) = share_secret(&mut rng, s, policy);
-    let mut lbl_pos_0 = HashMap::new();
-    let mut lbl_pos_1 = HashMap::new();
-    let mut lbl_neg_0 = HashMap::new();
-    let mut lbl_neg_1 = HashMap::new();
-    for j in 0..n {
-        let (user_attr, is_neg) = policy.get(j);
-        let auth = user_attr.authority().to_string();
-        let lbl = user_attr.label().to_string();
-        let key = (auth.clone(), lbl.clone());
-        if is_neg && !lbl_neg_0.contains_key(&key) {
-            lbl_neg_0.insert(key.clone(), hash_lbl(&auth, &lbl, Neg, 0));
-            lbl_neg_1.insert(key, hash_lbl(&auth, &lbl, Neg, 1));
-        } else if !lbl_pos_0.contains_key(&key) {
-            lbl_pos_0.insert(key.clone(), hash_lbl(&auth, &lbl, Pos, 0));
-            lbl_pos_1.insert(key.clone(), hash_lbl(&auth, &lbl, Pos, 1));
-        }
+///
+/// Or `Abe4Error::MissingAuthorityKey` if the policy mentions an authority
+/// that has no partial key in `mpk`.
+pub fn encrypt<C: Curve>(
+    rng: impl Rng,
+    mpk: &MPK<C>,
+    policy: &Policy,
+    tau: &Tau,
+) -> Result<(C::Gt, Ciphertext<C>)> {
+    encrypt_with(rng, mpk, &HashingContext::new(), policy, tau)
+}
+
+/// Like `encrypt`, but hashes labels and attributes through `hashing`, so
+/// that repeated encryptions can re-use its memoised points.
+pub fn encrypt_with<C: Curve>(
+    rng: impl Rng,
+    mpk: &MPK<C>,
+    hashing: &HashingContext<C>,
+    policy: &Policy,
+    tau: &Tau,
+) -> Result<(C::Gt, Ciphertext<C>)> {
+    encrypt_planned(rng, mpk, &EncryptionPlan::new(policy, tau, hashing)?)
+}
+
+/// Like `encrypt`, for the policy that `plan` was computed for.
+pub fn encrypt_planned<C: Curve>(
+    mut rng: impl Rng,
+    mpk: &MPK<C>,
+    plan: &EncryptionPlan<C>,
+) -> Result<(C::Gt, Ciphertext<C>)> {
+    let s = C::ScalarField::rand(&mut rng);
+    let mut s_vec = Vec::with_capacity(plan.num_s);
+    for _ in 0..plan.num_s {
+        s_vec.push(C::ScalarField::rand(&mut rng));
     }
+    let (lambda_vec, mu_vec) = share_secret(&mut rng, s, &plan.splits, plan.num_cols);
+    let n = plan.rows.len();
+    // Each row of the ciphertext only depends on its own attribute, so we
+    // can compute them independently (and in parallel, if enabled).
+    let h = C::H::generator();
+    let rows: Vec<(C::H, C::G, C::H)> = cfg_into_iter!(0..n)
+        .map(|j| {
+            let row = &plan.rows[j];
+            let mu = mu_vec[j];
+            let lambda = lambda_vec[j];
+            let s_tilde = s_vec[row.s_tilde];
+            let s = s_vec[row.s];
+            let x_attr = row.x_attr;
+            let mpk = mpk
+                .get_partial_key(&row.auth)
+                .ok_or_else(|| Abe4Error::MissingAuthorityKey(row.auth.clone()))?;
+            let b = if row.is_neg { mpk.b_not } else { mpk.b };
+            let (lbl_0, lbl_1) = (row.lbl_0, row.lbl_1);
+            let c_1 = h.mul(mu) + b.mul(s_tilde);
+            let c_2 = if row.is_neg {
+                C::G::msm(&[lbl_0, lbl_1], &[s_tilde, s_tilde * x_attr]).unwrap()
+            } else {
+                let c_2 = C::G::msm(&[lbl_0, lbl_1], &[s, s * x_attr]).unwrap();
+                mpk.b_prime.mul(s_tilde) + c_2
+            };
+            let c_3 = h.mul(lambda) + mpk.a.mul(s_tilde);
+            Ok((c_1, c_2, c_3))
+        })
+        .collect::<Result<_>>()?;
     let mut c_1_vec = Vec::with_capacity(n);
     let mut c_2_vec = Vec::with_capacity(n);
     let mut c_3_vec = Vec::with_capacity(n);
-    for j in 0..n {
-        let (user_attr, is_neg) = policy.get(j);
-        let auth = user_attr.authority().to_string();
-        let attr = user_attr.attribute().to_string();
-        let lbl = user_attr.label().to_string();
-        let mu = mu_vec[j];
-        let lambda = lambda_vec[j];
-        let s_tilde = s_vec[tau.get_tilde(&auth, &lbl, &attr)];
-        let s = s_vec[tau.get(&auth, &lbl, &attr)];
-        let x_attr = hash_attr(&attr);
-        let mpk = mpk.get_partial_key(&auth).unwrap();
-        let b = if is_neg { mpk.b_not } else { mpk.b };
-        let h = H::generator();
-        let key = (auth.clone(), lbl.clone());
-        let (lbl_0, lbl_1) = if is_neg {
-            (*lbl_neg_0.get(&key).unwrap(), *lbl_neg_1.get(&key).unwrap())
-        } else {
-            (*lbl_pos_0.get(&key).unwrap(), *lbl_pos_1.get(&key).unwrap())
-        };
-        if is_neg {
-            c_1_vec.push(h.mul(mu) + b.mul(s_tilde));
-            let c_2 = G::msm(&[lbl_0, lbl_1], &[s_tilde, s_tilde * x_attr]).unwrap();
-            c_2_vec.push(c_2);
-        } else {
-            c_1_vec.push(h.mul(mu) + b.mul(s_tilde));
-            let c_2 = G::msm(&[lbl_0, lbl_1], &[s, s * x_attr]).unwrap();
-            c_2_vec.push(mpk.b_prime.mul(s_tilde) + c_2);
-        }
-        c_3_vec.push(h.mul(lambda) + mpk.a.mul(s_tilde));
-    }
-    let mut c_4_vec = Vec::with_capacity(m + 1);
-    for s in s_vec.iter() {
-        let h = H::generator();
-        c_4_vec.push(h.mul(s));
+    for (c_1, c_2, c_3) in rows {
+        c_1_vec.push(c_1);
+        c_2_vec.push(c_2);
+        c_3_vec.push(c_3);
     }
-    let k = pairing(G::generator(), H::generator()).mul(s).0;
+    let c_4_vec: Vec<C::H> = cfg_iter!(s_vec).map(|s| h.mul(s)).collect();
+    let k = C::pairing(C::G::generator(), h).pow(s.into_bigint());
     let ct = Ciphertext {
         c_1_vec,
         c_2_vec,
         c_3_vec,
         c_4_vec,
     };
-    (k, ct)
+    Ok((k, ct))
 }
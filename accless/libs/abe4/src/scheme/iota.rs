@@ -1,4 +1,7 @@
-use crate::policy::UserAttribute;
+use crate::{
+    error::{Abe4Error, Result},
+    policy::UserAttribute,
+};
 use std::collections::HashMap;
 
 pub struct Iota {
@@ -44,12 +47,31 @@ impl Iota {
         self.m
     }
 
-    pub fn get(&self, auth: &str, lbl: &str, attr: &str) -> usize {
+    /// Returns the index of the given attribute, or an error if the attribute
+    /// was not among the ones this `Iota` was built from.
+    pub fn get(&self, auth: &str, lbl: &str, attr: &str) -> Result<usize> {
         let key = (String::from(lbl), String::from(attr));
-        *self.storage.get(auth).unwrap().get(&key).unwrap()
+        self.storage
+            .get(auth)
+            .and_then(|inner| inner.get(&key))
+            .copied()
+            .ok_or_else(|| {
+                Abe4Error::InvalidInput(format!("attribute {auth}.{lbl}:{attr} not found in iota"))
+            })
     }
 }
 
+/// Sorts user attributes by authority, label and attribute.
+///
+/// `Iota::new` numbers the values of a label in the order they are given in,
+/// so a key must be issued and used with its attributes in the same order.
+/// Code that only has the attributes as a set, like the FFI, sorts them first.
+pub(crate) fn sort_user_attrs(user_attrs: &mut [UserAttribute]) {
+    user_attrs.sort_by(|a, b| {
+        (a.authority(), a.label(), a.attribute()).cmp(&(b.authority(), b.label(), b.attribute()))
+    });
+}
+
 #[test]
 fn test_iota_simple() {
     let user_attrs = vec![
@@ -66,16 +88,16 @@ fn test_iota_simple() {
     ];
     let iota = Iota::new(&user_attrs);
     assert_eq!(iota.m, 0);
-    assert_eq!(iota.get("0", "0", "0"), 0);
-    assert_eq!(iota.get("0", "1", "1"), 0);
-    assert_eq!(iota.get("0", "2", "2"), 0);
-    assert_eq!(iota.get("0", "3", "3"), 0);
-    assert_eq!(iota.get("0", "4", "4"), 0);
-    assert_eq!(iota.get("1", "5", "5"), 0);
-    assert_eq!(iota.get("1", "6", "6"), 0);
-    assert_eq!(iota.get("1", "7", "7"), 0);
-    assert_eq!(iota.get("1", "8", "8"), 0);
-    assert_eq!(iota.get("1", "9", "9"), 0);
+    assert_eq!(iota.get("0", "0", "0").unwrap(), 0);
+    assert_eq!(iota.get("0", "1", "1").unwrap(), 0);
+    assert_eq!(iota.get("0", "2", "2").unwrap(), 0);
+    assert_eq!(iota.get("0", "3", "3").unwrap(), 0);
+    assert_eq!(iota.get("0", "4", "4").unwrap(), 0);
+    assert_eq!(iota.get("1", "5", "5").unwrap(), 0);
+    assert_eq!(iota.get("1", "6", "6").unwrap(), 0);
+    assert_eq!(iota.get("1", "7", "7").unwrap(), 0);
+    assert_eq!(iota.get("1", "8", "8").unwrap(), 0);
+    assert_eq!(iota.get("1", "9", "9").unwrap(), 0);
 }
 
 #[test]
@@ -94,14 +116,16 @@ fn test_iota_complex() {
     ];
     let iota = Iota::new(&user_attrs);
     assert_eq!(iota.m, 3);
-    assert_eq!(iota.get("0", "0", "0"), 0);
-    assert_eq!(iota.get("0", "0", "1"), 1);
-    assert_eq!(iota.get("0", "0", "2"), 2);
-    assert_eq!(iota.get("0", "1", "3"), 0);
-    assert_eq!(iota.get("0", "1", "4"), 1);
-    assert_eq!(iota.get("1", "1", "0"), 0);
-    assert_eq!(iota.get("1", "2", "1"), 0);
-    assert_eq!(iota.get("1", "2", "2"), 1);
-    assert_eq!(iota.get("1", "2", "3"), 2);
-    assert_eq!(iota.get("1", "2", "4"), 3);
+    assert_eq!(iota.get("0", "0", "0").unwrap(), 0);
+    assert_eq!(iota.get("0", "0", "1").unwrap(), 1);
+    assert_eq!(iota.get("0", "0", "2").unwrap(), 2);
+    assert_eq!(iota.get("0", "1", "3").unwrap(), 0);
+    assert_eq!(iota.get("0", "1", "4").unwrap(), 1);
+    assert_eq!(iota.get("1", "1", "0").unwrap(), 0);
+    assert_eq!(iota.get("1", "2", "1").unwrap(), 0);
+    assert_eq!(iota.get("1", "2", "2").unwrap(), 1);
+    assert_eq!(iota.get("1", "2", "3").unwrap(), 2);
+    assert_eq!(iota.get("1", "2", "4").unwrap(), 3);
+    assert!(iota.get("1", "2", "5").is_err());
+    assert!(iota.get("2", "2", "4").is_err());
 }
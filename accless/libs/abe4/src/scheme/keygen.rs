@@ -1,10 +1,11 @@
 use crate::{
-    curve::{G, H, ScalarField},
+    curve::Curve,
+    error::{Abe4Error, Result},
     hashing::{
         HashSign::{Neg, Pos},
-        hash_attr, hash_gid, hash_lbl,
+        HashingContext,
     },
-    policy::UserAttribute,
+    policy::{UserAttribute, is_bit_label},
     scheme::{
         iota::Iota,
         types::{MSK, PartialMSK, PartialUSK, USK},
@@ -15,13 +16,13 @@ use ark_ff::UniformRand;
 use ark_std::{ops::Mul, rand::Rng};
 use std::collections::{HashMap, HashSet};
 
-pub fn keygen(
+pub fn keygen<C: Curve>(
     mut rng: impl Rng,
     gid: &str,
-    msk: &MSK,
+    msk: &MSK<C>,
     user_attrs: &[UserAttribute],
     iota: &Iota,
-) -> USK {
+) -> Result<USK<C>> {
     // Group the given array of `UserAttribute`s by authority.
     let mut user_attr_by_auth: HashMap<&str, Vec<&UserAttribute>> = HashMap::new();
     for ua in user_attrs {
@@ -32,62 +33,102 @@ pub fn keygen(
     }
 
     // Run partial key generation for each authority.
-    let mut usk = USK::new();
+    let hashing = HashingContext::new();
+    let mut usk = USK::<C>::new();
     for (auth, uas) in user_attr_by_auth {
-        match msk.get_partial_key(auth) {
-            None => panic!("No partial MSK given for authority in user's attribute set"),
-            Some(partial_msk) => {
-                let partial_usk = keygen_partial(&mut rng, gid, partial_msk, &uas, iota);
-                usk.add_partial_key(partial_usk);
-            }
-        }
+        let partial_msk = msk
+            .get_partial_key(auth)
+            .ok_or_else(|| Abe4Error::MissingAuthorityKey(auth.to_string()))?;
+        let b_prime = C::G::generator().mul(partial_msk.b_prime);
+        let partial_usk =
+            keygen_partial_with(&mut rng, gid, partial_msk, b_prime, &uas, iota, &hashing)?;
+        usk.add_partial_key(partial_usk);
     }
-    usk
+    Ok(usk)
+}
+
+pub fn keygen_partial<C: Curve>(
+    rng: impl Rng,
+    gid: &str,
+    msk: &PartialMSK<C>,
+    user_attrs: &[&UserAttribute],
+    iota: &Iota,
+) -> Result<PartialUSK<C>> {
+    let b_prime = C::G::generator().mul(msk.b_prime);
+    keygen_partial_with(
+        rng,
+        gid,
+        msk,
+        b_prime,
+        user_attrs,
+        iota,
+        &HashingContext::new(),
+    )
 }
 
-pub fn keygen_partial(
+/// Like `keygen_partial`, but takes `g^b'` from the partial MPK instead of
+/// `b'` from `msk`, which is not read, and hashes through `hashing`.
+pub fn keygen_partial_with<C: Curve>(
     mut rng: impl Rng,
     gid: &str,
-    msk: &PartialMSK,
+    msk: &PartialMSK<C>,
+    b_prime: C::G,
     user_attrs: &[&UserAttribute],
     iota: &Iota,
-) -> PartialUSK {
-    let zero = ScalarField::from(0);
+    hashing: &HashingContext<C>,
+) -> Result<PartialUSK<C>> {
+    let zero = C::ScalarField::from(0u64);
     let mut r_vec = Vec::new();
     let mut r_not_vec = Vec::new();
     let mut r_lab_map = HashMap::new();
     let mut r_lab_done = HashSet::new();
 
     for _ in 0..=iota.get_max() {
-        r_vec.push(ScalarField::rand(&mut rng));
-        r_not_vec.push(ScalarField::rand(&mut rng));
+        r_vec.push(C::ScalarField::rand(&mut rng));
+        r_not_vec.push(C::ScalarField::rand(&mut rng));
     }
 
+    let mut bit_values = HashMap::new();
     for user_attr in user_attrs.iter() {
+        // The attribute is managed by a different authority, whose MSK we
+        // were not given.
         if user_attr.authority() != msk.auth {
-            panic!(
-                "Fatal error: cannot generate key for attribute which is managed by a different authority"
-            );
+            return Err(Abe4Error::MissingAuthorityKey(
+                user_attr.authority().to_string(),
+            ));
+        }
+        // Both values of a bit would satisfy every comparison over it.
+        if is_bit_label(user_attr.label())
+            && *bit_values
+                .entry(user_attr.label())
+                .or_insert(user_attr.attribute())
+                != user_attr.attribute()
+        {
+            return Err(Abe4Error::InvalidInput(format!(
+                "user attribute {}.{} holds more than one value of a numeric attribute",
+                user_attr.authority(),
+                user_attr.label()
+            )));
         }
         if !r_lab_done.contains(&(user_attr.label(), user_attr.attribute())) {
             let iota = iota.get(
                 user_attr.authority(),
                 user_attr.label(),
                 user_attr.attribute(),
-            );
+            )?;
             let r_not = r_not_vec[iota];
             let r_lab = *r_lab_map.get(&user_attr.label()).unwrap_or(&zero) + r_not;
             r_lab_map.insert(user_attr.label(), r_lab);
             r_lab_done.insert((user_attr.label(), user_attr.attribute()));
         }
     }
-    let g = G::generator().mul(msk.beta);
-    let gid_hashed = hash_gid(gid);
-    let gid = gid_hashed.mul(msk.b);
-    let gid_not = gid_hashed.mul(msk.b_not);
+    let g = C::G::generator().mul(msk.beta);
+    let gid_hashed = hashing.hash_gid(gid);
+    let gid_b = gid_hashed.mul(msk.b);
+    let gid_b_not = gid_hashed.mul(msk.b_not);
     let mut k_1_1_vec = Vec::new();
     for r_val in r_vec.iter().take(iota.get_max() + 1) {
-        let k_1 = g + gid + G::generator().mul(*r_val * msk.b_prime);
+        let k_1 = g + gid_b + b_prime.mul(*r_val);
         k_1_1_vec.push(k_1);
     }
     let mut k_1_2_map = HashMap::new();
@@ -99,10 +140,19 @@ pub fn keygen_partial(
     for user_attr in user_attrs.iter() {
         let key = (msk.auth.clone(), user_attr.label());
         if !lbl_pos_0.contains_key(&key) {
-            lbl_pos_0.insert(key.clone(), hash_lbl(&msk.auth, user_attr.label(), Pos, 0));
-            lbl_pos_1.insert(key.clone(), hash_lbl(&msk.auth, user_attr.label(), Pos, 1));
-            lbl_neg_0.insert(key.clone(), hash_lbl(&msk.auth, user_attr.label(), Neg, 0));
-            lbl_neg_1.insert(key, hash_lbl(&msk.auth, user_attr.label(), Neg, 1));
+            lbl_pos_0.insert(
+                key.clone(),
+                hashing.hash_lbl(&msk.auth, user_attr.label(), Pos, 0),
+            );
+            lbl_pos_1.insert(
+                key.clone(),
+                hashing.hash_lbl(&msk.auth, user_attr.label(), Pos, 1),
+            );
+            lbl_neg_0.insert(
+                key.clone(),
+                hashing.hash_lbl(&msk.auth, user_attr.label(), Neg, 0),
+            );
+            lbl_neg_1.insert(key, hashing.hash_lbl(&msk.auth, user_attr.label(), Neg, 1));
         }
     }
     for user_attr in user_attrs.iter() {
@@ -111,15 +161,15 @@ pub fn keygen_partial(
         let lbl_pos_1 = *lbl_pos_1.get(&key).unwrap();
         let lbl_neg_0 = *lbl_neg_0.get(&key).unwrap();
         let lbl_neg_1 = *lbl_neg_1.get(&key).unwrap();
-        let x_attr = hash_attr(user_attr.attribute());
+        let x_attr = hashing.hash_attr(user_attr.attribute());
         let iota = iota.get(
             user_attr.authority(),
             user_attr.label(),
             user_attr.attribute(),
-        );
+        )?;
         let r = r_vec[iota];
         let r_not = r_not_vec[iota];
-        let k_1 = G::msm(&[lbl_pos_0, lbl_pos_1], &[r, r * x_attr]).unwrap();
+        let k_1 = C::G::msm(&[lbl_pos_0, lbl_pos_1], &[r, r * x_attr]).unwrap();
         k_1_2_map.insert(
             (
                 user_attr.label().to_string(),
@@ -127,7 +177,7 @@ pub fn keygen_partial(
             ),
             k_1,
         );
-        let k_3 = G::msm(&[lbl_neg_0, lbl_neg_1], &[r_not, r_not * x_attr]).unwrap();
+        let k_3 = C::G::msm(&[lbl_neg_0, lbl_neg_1], &[r_not, r_not * x_attr]).unwrap();
         k_3_map.insert(
             (
                 user_attr.label().to_string(),
@@ -142,7 +192,7 @@ pub fn keygen_partial(
             let r_lab = r_lab_map.get(user_attr.label()).unwrap();
             let key = (msk.auth.clone(), user_attr.label());
             let k_2 = (*lbl_neg_1.get(&key).unwrap()).mul(r_lab);
-            k_2_map.insert(user_attr.label().to_string(), g + gid_not + k_2);
+            k_2_map.insert(user_attr.label().to_string(), g + gid_b_not + k_2);
         }
     }
     let mut k_4_vec = Vec::with_capacity(r_vec.len());
@@ -150,17 +200,18 @@ pub fn keygen_partial(
     for iota in 0..r_vec.len() {
         let r = r_vec[iota];
         let r_not = r_not_vec[iota];
-        k_4_vec.push(H::generator().mul(r));
-        k_5_vec.push(H::generator().mul(r_not));
+        k_4_vec.push(C::H::generator().mul(r));
+        k_5_vec.push(C::H::generator().mul(r_not));
     }
 
-    PartialUSK {
+    Ok(PartialUSK {
         auth: msk.auth.clone(),
+        gid: gid.to_string(),
         k_1_1_vec,
         k_1_2_map,
         k_2_map,
         k_3_map,
         k_4_vec,
         k_5_vec,
-    }
+    })
 }
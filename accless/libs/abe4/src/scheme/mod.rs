@@ -1,65 +1,288 @@
 use crate::{
-    curve::Gt,
+    curve::Curve,
+    error::Result,
     policy::{Policy, UserAttribute},
 };
 use iota::Iota;
+use prepared::{PreparedMpk, PreparedPolicy, PreparedUsk};
 use rand::Rng;
 use tau::Tau;
-use types::{Ciphertext, MPK, MSK, PartialMPK, PartialMSK, PartialUSK, USK};
+use types::{
+    Ciphertext, MPK, MSK, PartialMPK, PartialMSK, PartialUSK, RetrievalKey, TransformKey,
+    TransformedCiphertext, USK,
+};
 
 mod decrypt;
+mod delegate;
 mod encrypt;
 mod group_pairs;
 pub mod iota;
 mod keygen;
+mod outsource;
+pub mod prepared;
 mod setup;
 pub mod tau;
+pub mod threshold;
 pub mod types;
 
 pub fn setup_partial(rng: impl Rng, authority: &str) -> (PartialMSK, PartialMPK) {
     setup::setup_partial(rng, authority)
 }
 
+/// Like `setup_partial`, but generates keys over the given curve.
+pub fn setup_partial_with_curve<C: Curve>(
+    rng: impl Rng,
+    authority: &str,
+) -> (PartialMSK<C>, PartialMPK<C>) {
+    setup::setup_partial(rng, authority)
+}
+
 pub fn setup(rng: impl ark_std::rand::RngCore, auths: &Vec<&str>) -> (MSK, MPK) {
     setup::setup(rng, auths)
 }
 
-pub fn keygen_partial(
+/// Like `setup`, but generates keys over the given curve. Keys, and the
+/// ciphertexts encrypted with them, carry the curve in their type.
+pub fn setup_with_curve<C: Curve>(
+    rng: impl ark_std::rand::RngCore,
+    auths: &Vec<&str>,
+) -> (MSK<C>, MPK<C>) {
+    setup::setup(rng, auths)
+}
+
+pub fn keygen_partial<C: Curve>(
     rng: impl Rng,
     gid: &str,
-    msk: &PartialMSK,
+    msk: &PartialMSK<C>,
     user_attrs: &[&UserAttribute],
     iota: &Iota,
-) -> PartialUSK {
+) -> Result<PartialUSK<C>> {
     keygen::keygen_partial(rng, gid, msk, user_attrs, iota)
 }
 
-pub fn keygen(
+pub fn keygen<C: Curve>(
     rng: impl ark_std::rand::RngCore,
     gid: &str,
-    msk: &MSK,
+    msk: &MSK<C>,
     user_attrs: &[UserAttribute],
     iota: &Iota,
-) -> USK {
+) -> Result<USK<C>> {
     keygen::keygen(rng, gid, msk, user_attrs, iota)
 }
 
-pub fn encrypt(
+/// # Description
+///
+/// Derives the GID that keys issued at `epoch` to the user `gid` are bound to.
+///
+/// Components of keys issued for different GIDs can not be combined, so a user
+/// that holds keys from several epochs can not satisfy the epoch of a policy
+/// with one key and its other attributes with another.
+///
+/// # Returns
+///
+/// `gid#epoch<epoch>`, which is the GID to decrypt and verify the key with.
+pub fn epoch_gid(gid: &str, epoch: u32) -> String {
+    format!("{gid}#epoch{epoch}")
+}
+
+/// # Description
+///
+/// Like `keygen_partial`, but stamps the key with the epoch it is issued at,
+/// see `UserAttribute::with_epoch`. The key then satisfies policies restricted
+/// with `Policy::require_epoch` up to `epoch`.
+///
+/// The key holds `UserAttribute::with_epoch(user_attrs, epoch)`, which is
+/// also the list of attributes to verify it against, and is bound to the GID
+/// `epoch_gid(gid, epoch)`.
+pub fn keygen_partial_with_epoch<C: Curve>(
+    rng: impl Rng,
+    gid: &str,
+    msk: &PartialMSK<C>,
+    user_attrs: &[&UserAttribute],
+    epoch: u32,
+) -> Result<PartialUSK<C>> {
+    let user_attrs: Vec<UserAttribute> = user_attrs.iter().map(|ua| (*ua).clone()).collect();
+    let stamped = UserAttribute::with_epoch(&user_attrs, epoch);
+    let iota = Iota::new(&stamped);
+    let stamped_refs: Vec<&UserAttribute> = stamped.iter().collect();
+    keygen::keygen_partial(rng, &epoch_gid(gid, epoch), msk, &stamped_refs, &iota)
+}
+
+/// Like `keygen`, but stamps the key with the epoch it is issued at, and binds
+/// it to `epoch_gid(gid, epoch)`, see `keygen_partial_with_epoch`.
+pub fn keygen_with_epoch<C: Curve>(
+    rng: impl ark_std::rand::RngCore,
+    gid: &str,
+    msk: &MSK<C>,
+    user_attrs: &[UserAttribute],
+    epoch: u32,
+) -> Result<USK<C>> {
+    let stamped = UserAttribute::with_epoch(user_attrs, epoch);
+    let iota = Iota::new(&stamped);
+    keygen::keygen(rng, &epoch_gid(gid, epoch), msk, &stamped, &iota)
+}
+
+/// # Description
+///
+/// Derives a user secret key for a subset of the attributes of `usk`, e.g.
+/// to hand a narrower key to a child function.
+///
+/// The delegated key is re-randomised, so it is as good as a key issued by
+/// the authorities for `subset`, and does not reveal the components of `usk`.
+/// It keeps the indices of `usk`, and must be used with the same `Iota`.
+///
+/// # Arguments
+///
+/// * `rng`: A random number generator.
+/// * `mpk`: The master public key of the authorities that issued `usk`.
+/// * `usk`: The user secret key to delegate.
+/// * `gid`: The global identifier `usk` was issued for.
+/// * `iota`: The `Iota` object `usk` was issued with.
+/// * `subset`: The attributes to keep. For each label of `subset`, it must hold
+///   every value of the label that `usk` holds.
+///
+/// # Returns
+///
+/// The delegated key. Otherwise, `Abe4Error::GidMismatch` if `usk` was issued
+/// for another GID, `Abe4Error::MissingAuthorityKey` if `mpk` has no key for
+/// an authority of `subset`, or `Abe4Error::InvalidInput` if `subset` is not
+/// a subset of the attributes of `usk`, or splits one of its labels.
+pub fn delegate<C: Curve>(
+    rng: impl ark_std::rand::RngCore,
+    mpk: &MPK<C>,
+    usk: &USK<C>,
+    gid: &str,
+    iota: &Iota,
+    subset: &[UserAttribute],
+) -> Result<USK<C>> {
+    delegate::delegate(rng, mpk, usk, gid, iota, subset)
+}
+
+pub fn encrypt<C: Curve>(
     rng: impl ark_std::rand::RngCore,
-    mpk: &MPK,
+    mpk: &MPK<C>,
     policy: &Policy,
     tau: &Tau,
-) -> (Gt, Ciphertext) {
+) -> Result<(C::Gt, Ciphertext<C>)> {
     encrypt::encrypt(rng, mpk, policy, tau)
 }
 
-pub fn decrypt(
-    usk: &USK,
+/// Like `encrypt`, but re-uses the label points memoised in `mpk` by
+/// previous encryptions.
+pub fn encrypt_prepared<C: Curve>(
+    rng: impl ark_std::rand::RngCore,
+    mpk: &PreparedMpk<C>,
+    policy: &Policy,
+    tau: &Tau,
+) -> Result<(C::Gt, Ciphertext<C>)> {
+    encrypt::encrypt_with(rng, mpk.mpk(), &mpk.hashing, policy, tau)
+}
+
+/// # Description
+///
+/// Encapsulates `n` independent symmetric keys under a prepared policy.
+///
+/// Each key gets a fresh encapsulation, exactly as if `encrypt` had been
+/// called `n` times, but the policy-dependent setup is done once, when the
+/// policy is prepared.
+///
+/// # Returns
+///
+/// The `n` symmetric keys with their ciphertexts, or
+/// `Abe4Error::MissingAuthorityKey` if the policy mentions an authority that
+/// has no partial key in `mpk`.
+pub fn encrypt_many<C: Curve>(
+    mut rng: impl ark_std::rand::RngCore,
+    mpk: &PreparedMpk<C>,
+    policy: &PreparedPolicy<C>,
+    n: usize,
+) -> Result<Vec<(C::Gt, Ciphertext<C>)>> {
+    (0..n)
+        .map(|_| encrypt::encrypt_planned(&mut rng, mpk.mpk(), &policy.plan))
+        .collect()
+}
+
+pub fn decrypt<C: Curve>(
+    usk: &USK<C>,
     gid: &str,
     iota: &Iota,
     tau: &Tau,
     policy: &Policy,
-    ct: &Ciphertext,
-) -> Option<Gt> {
+    ct: &Ciphertext<C>,
+) -> Result<C::Gt> {
     decrypt::decrypt(usk, gid, iota, tau, policy, ct)
 }
+
+/// Blinds a user secret key into a transformation key, for outsourced
+/// decryption with `transform`. The user keeps the retrieval key.
+pub fn transform_keygen<C: Curve>(
+    rng: impl ark_std::rand::RngCore,
+    usk: &USK<C>,
+) -> (TransformKey<C>, RetrievalKey<C>) {
+    outsource::transform_keygen(rng, usk)
+}
+
+/// Like `decrypt`, but with a transformation key, for an untrusted helper.
+/// The user finishes the decryption with `decrypt_transformed`.
+pub fn transform<C: Curve>(
+    tk: &TransformKey<C>,
+    gid: &str,
+    iota: &Iota,
+    tau: &Tau,
+    policy: &Policy,
+    ct: &Ciphertext<C>,
+) -> Result<TransformedCiphertext<C>> {
+    outsource::transform(tk, gid, iota, tau, policy, ct)
+}
+
+/// Recovers the symmetric key of a ciphertext transformed with `transform`,
+/// with a single exponentiation.
+pub fn decrypt_transformed<C: Curve>(
+    rk: &RetrievalKey<C>,
+    transformed: &TransformedCiphertext<C>,
+) -> C::Gt {
+    outsource::decrypt_transformed(rk, transformed)
+}
+
+/// Like `decrypt`, for the GID the key was prepared with.
+pub fn decrypt_prepared<C: Curve>(
+    usk: &PreparedUsk<C>,
+    iota: &Iota,
+    tau: &Tau,
+    policy: &Policy,
+    ct: &Ciphertext<C>,
+) -> Result<C::Gt> {
+    decrypt::decrypt_with(
+        usk.usk(),
+        usk.gid_hashed,
+        &usk.hashing,
+        iota,
+        tau,
+        policy,
+        ct,
+    )
+}
+
+/// # Description
+///
+/// Decapsulates the symmetric keys of many ciphertexts encrypted under the
+/// same policy.
+///
+/// Which shares of the policy the key decrypts, and how they are combined,
+/// is worked out once for all the ciphertexts. The key's `Iota` is the one
+/// rebuilt from its attributes by `PreparedUsk::new`.
+///
+/// # Returns
+///
+/// The symmetric key of each ciphertext, or the first error, as returned by
+/// `decrypt`.
+pub fn decrypt_many<'a, C: Curve>(
+    usk: &PreparedUsk<C>,
+    policy: &PreparedPolicy<C>,
+    cts: impl IntoIterator<Item = &'a Ciphertext<C>>,
+) -> Result<Vec<C::Gt>> {
+    let plan = decrypt::DecryptionPlan::new(usk.usk(), usk.iota(), policy.tau(), policy.policy())?;
+    cts.into_iter()
+        .map(|ct| decrypt::decrypt_planned(&plan, usk.gid_hashed, &usk.hashing, ct))
+        .collect()
+}
@@ -0,0 +1,130 @@
+//! Outsourced decryption.
+//!
+//! Decryption is a product of pairings, all but `KEYLESS_PAIRS` of which
+//! pair a ciphertext element with exactly one element of the user secret key.
+//! Raising every element of the key to `1/z` therefore raises the part of the
+//! product that involves the key to `1/z`, and leaves the rest unchanged. A
+//! helper holding the blinded key computes both parts, and the user, who
+//! holds `z`, recovers the symmetric key with a single exponentiation.
+
+use crate::{
+    curve::Curve,
+    error::Result,
+    hashing::HashingContext,
+    policy::Policy,
+    scheme::{
+        decrypt::{DecryptionPlan, KEYLESS_PAIRS, check_gid, decryption_pairs},
+        iota::Iota,
+        tau::Tau,
+        types::{Ciphertext, PartialUSK, RetrievalKey, TransformKey, TransformedCiphertext, USK},
+    },
+};
+use ark_ff::{Field, PrimeField, UniformRand, Zero};
+use ark_std::rand::Rng;
+
+/// Raises each group element of a partial key to `z_inv`.
+fn blind<C: Curve>(usk: &PartialUSK<C>, z_inv: C::ScalarField) -> PartialUSK<C> {
+    PartialUSK {
+        auth: usk.auth.clone(),
+        gid: usk.gid.clone(),
+        k_1_1_vec: usk.k_1_1_vec.iter().map(|k| *k * z_inv).collect(),
+        k_1_2_map: usk
+            .k_1_2_map
+            .iter()
+            .map(|(key, k)| (key.clone(), *k * z_inv))
+            .collect(),
+        k_2_map: usk
+            .k_2_map
+            .iter()
+            .map(|(key, k)| (key.clone(), *k * z_inv))
+            .collect(),
+        k_3_map: usk
+            .k_3_map
+            .iter()
+            .map(|(key, k)| (key.clone(), *k * z_inv))
+            .collect(),
+        k_4_vec: usk.k_4_vec.iter().map(|k| *k * z_inv).collect(),
+        k_5_vec: usk.k_5_vec.iter().map(|k| *k * z_inv).collect(),
+    }
+}
+
+/// # Description
+///
+/// Blinds a user secret key into a transformation key.
+///
+/// # Returns
+///
+/// The transformation key, to hand to the helper, and the retrieval key,
+/// which the user keeps to finish decryptions with `decrypt_transformed`.
+pub fn transform_keygen<C: Curve>(
+    mut rng: impl Rng,
+    usk: &USK<C>,
+) -> (TransformKey<C>, RetrievalKey<C>) {
+    let z = loop {
+        let z = C::ScalarField::rand(&mut rng);
+        if !z.is_zero() {
+            break z;
+        }
+    };
+    let z_inv = z.inverse().expect("z is not zero");
+    let mut blinded = USK::new();
+    for partial_usk in usk.partial_keys.values() {
+        blinded.add_partial_key(blind(partial_usk, z_inv));
+    }
+    (TransformKey { usk: blinded }, RetrievalKey { z })
+}
+
+/// # Description
+///
+/// Partially decrypts a ciphertext with a transformation key.
+///
+/// This is the pairing-heavy part of `decrypt`, and can be run by a party
+/// that the user does not trust with their key nor with the plaintext.
+///
+/// # Arguments
+///
+/// * `tk`: The transformation key.
+/// * `gid`: The global identifier of the user.
+/// * `iota`: The `Iota` object derived from the user's attributes.
+/// * `tau`: The `Tau` object derived from the policy.
+/// * `policy`: The access control policy used for encryption.
+/// * `ct`: The ciphertext to be decrypted.
+///
+/// # Returns
+///
+/// The transformed ciphertext, or the errors of `decrypt`.
+pub fn transform<C: Curve>(
+    tk: &TransformKey<C>,
+    gid: &str,
+    iota: &Iota,
+    tau: &Tau,
+    policy: &Policy,
+    ct: &Ciphertext<C>,
+) -> Result<TransformedCiphertext<C>> {
+    check_gid(&tk.usk, gid)?;
+    let hashing = HashingContext::new();
+    let gid_hashed = hashing.hash_gid(gid);
+    let plan = DecryptionPlan::new(&tk.usk, iota, tau, policy)?;
+    let mut keyless = decryption_pairs(&plan, gid_hashed, &hashing, ct)?;
+    let blinded = keyless.split_off(KEYLESS_PAIRS);
+
+    let (p, q): (Vec<C::G>, Vec<C::H>) = keyless.into_iter().unzip();
+    let c_0 = C::multi_pairing(p, q);
+    let (p, q): (Vec<C::G>, Vec<C::H>) = blinded.into_iter().unzip();
+    let c_1 = C::multi_pairing(p, q);
+    Ok(TransformedCiphertext { c_0, c_1 })
+}
+
+/// # Description
+///
+/// Finishes the decryption of a transformed ciphertext.
+///
+/// The result is only the symmetric key if `transformed` was computed
+/// honestly, with the transformation key that matches `rk`. Callers should
+/// authenticate it, e.g. by decrypting an AEAD ciphertext under it.
+pub fn decrypt_transformed<C: Curve>(
+    rk: &RetrievalKey<C>,
+    transformed: &TransformedCiphertext<C>,
+) -> C::Gt {
+    transformed.c_0 * transformed.c_1.pow(rk.z.into_bigint())
+}
@@ -0,0 +1,150 @@
+use crate::{
+    curve::{Curve, DefaultCurve},
+    error::Result,
+    hashing::HashingContext,
+    policy::Policy,
+    scheme::{
+        decrypt::check_gid,
+        encrypt::EncryptionPlan,
+        iota::Iota,
+        tau::Tau,
+        types::{MPK, USK},
+    },
+};
+use ark_ec::CurveGroup;
+
+/// # Description
+///
+/// A master public key bundled with a `HashingContext`.
+///
+/// Encrypting with `encrypt` hashes every label of the policy onto the curve
+/// from scratch. Encrypting with `encrypt_prepared` instead memoises the label
+/// points in the prepared key, so repeated encryptions under the same
+/// authorities and labels skip the hash-to-curve work.
+pub struct PreparedMpk<C: Curve = DefaultCurve> {
+    mpk: MPK<C>,
+    pub(crate) hashing: HashingContext<C>,
+}
+
+impl<C: Curve> PreparedMpk<C> {
+    pub fn new(mpk: MPK<C>) -> Self {
+        Self {
+            mpk,
+            hashing: HashingContext::new(),
+        }
+    }
+
+    pub fn mpk(&self) -> &MPK<C> {
+        &self.mpk
+    }
+
+    pub fn into_inner(self) -> MPK<C> {
+        self.mpk
+    }
+}
+
+impl<C: Curve> From<MPK<C>> for PreparedMpk<C> {
+    fn from(mpk: MPK<C>) -> Self {
+        Self::new(mpk)
+    }
+}
+
+/// # Description
+///
+/// A policy bundled with everything an encryption under it needs that does
+/// not depend on the encryption's randomness: its `Tau`, the split of the
+/// secret over its share-generating matrix, and the hashes of its labels and
+/// attributes.
+///
+/// Encrypting many messages under the same policy with `encrypt_many` (or
+/// `encrypt_hybrid_many`) only pays for this setup once.
+pub struct PreparedPolicy<C: Curve = DefaultCurve> {
+    policy: Policy,
+    tau: Tau,
+    pub(crate) plan: EncryptionPlan<C>,
+}
+
+impl<C: Curve> PreparedPolicy<C> {
+    /// # Description
+    ///
+    /// Prepares `policy` for encryptions with `mpk`, hashing its labels
+    /// through the context of `mpk`.
+    ///
+    /// # Returns
+    ///
+    /// The prepared policy, or an error if the policy cannot be encrypted
+    /// under.
+    pub fn new(mpk: &PreparedMpk<C>, policy: Policy) -> Result<Self> {
+        let tau = Tau::new(&policy);
+        let plan = EncryptionPlan::new(&policy, &tau, &mpk.hashing)?;
+        Ok(Self { policy, tau, plan })
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    pub fn tau(&self) -> &Tau {
+        &self.tau
+    }
+
+    pub fn into_inner(self) -> Policy {
+        self.policy
+    }
+}
+
+/// # Description
+///
+/// A user secret key bound to its GID, bundled with a `HashingContext`.
+///
+/// The GID is checked against the partial keys, and hashed, once when the key
+/// is prepared, and the attribute hashes needed by `decrypt_prepared` are
+/// memoised across decryptions. The key's `Iota` is also rebuilt once from
+/// its attributes, for `decrypt_many`.
+pub struct PreparedUsk<C: Curve = DefaultCurve> {
+    usk: USK<C>,
+    gid: String,
+    iota: Iota,
+    pub(crate) gid_hashed: <C::G as CurveGroup>::Affine,
+    pub(crate) hashing: HashingContext<C>,
+}
+
+impl<C: Curve> PreparedUsk<C> {
+    /// # Description
+    ///
+    /// Prepares `usk` for decryptions as the user `gid`.
+    ///
+    /// # Returns
+    ///
+    /// The prepared key, or `Abe4Error::GidMismatch` if a partial key in
+    /// `usk` was issued for another GID.
+    pub fn new(usk: USK<C>, gid: &str) -> Result<Self> {
+        check_gid(&usk, gid)?;
+        let hashing = HashingContext::new();
+        let gid_hashed = hashing.hash_gid(gid);
+        let iota = Iota::new(&usk.get_user_attributes());
+        Ok(Self {
+            usk,
+            gid: gid.to_string(),
+            iota,
+            gid_hashed,
+            hashing,
+        })
+    }
+
+    pub fn usk(&self) -> &USK<C> {
+        &self.usk
+    }
+
+    pub fn gid(&self) -> &str {
+        &self.gid
+    }
+
+    pub fn iota(&self) -> &Iota {
+        &self.iota
+    }
+
+    pub fn into_inner(self) -> USK<C> {
+        self.usk
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    curve::{G, H, ScalarField},
+    curve::Curve,
     scheme::types::{MPK, MSK, PartialMPK, PartialMSK},
 };
 use ark_ec::Group;
@@ -33,9 +33,9 @@ use ark_std::{ops::Mul, rand::Rng};
 /// # Example usage
 ///
 /// TODO: add me
-pub fn setup(mut rng: impl Rng, authorities: &Vec<&str>) -> (MSK, MPK) {
-    let mut msk = MSK::new();
-    let mut mpk = MPK::new();
+pub fn setup<C: Curve>(mut rng: impl Rng, authorities: &Vec<&str>) -> (MSK<C>, MPK<C>) {
+    let mut msk = MSK::<C>::new();
+    let mut mpk = MPK::<C>::new();
 
     for auth in authorities {
         let (partial_msk, partial_mpk) = setup_partial(&mut rng, auth);
@@ -48,11 +48,14 @@ pub fn setup(mut rng: impl Rng, authorities: &Vec<&str>) -> (MSK, MPK) {
 
 /// Given an authority identified by its global identifier (GID), generate a
 /// parial keypair.
-pub fn setup_partial(mut rng: impl Rng, authority: &str) -> (PartialMSK, PartialMPK) {
-    let beta = ScalarField::rand(&mut rng);
-    let b = ScalarField::rand(&mut rng);
-    let b_not = ScalarField::rand(&mut rng);
-    let b_prime = ScalarField::rand(&mut rng);
+pub fn setup_partial<C: Curve>(
+    mut rng: impl Rng,
+    authority: &str,
+) -> (PartialMSK<C>, PartialMPK<C>) {
+    let beta = C::ScalarField::rand(&mut rng);
+    let b = C::ScalarField::rand(&mut rng);
+    let b_not = C::ScalarField::rand(&mut rng);
+    let b_prime = C::ScalarField::rand(&mut rng);
     let msk = PartialMSK {
         auth: authority.to_string(),
         beta,
@@ -60,18 +63,18 @@ pub fn setup_partial(mut rng: impl Rng, authority: &str) -> (PartialMSK, Partial
         b_not,
         b_prime,
     };
-
-    let a = H::generator().mul(beta);
-    let b = H::generator().mul(b);
-    let b_not = H::generator().mul(b_not);
-    let b_prime = G::generator().mul(b_prime);
-    let mpk = PartialMPK {
-        auth: authority.to_string(),
-        a,
-        b,
-        b_not,
-        b_prime,
-    };
+    let mpk = public_key(&msk);
 
     (msk, mpk)
 }
+
+/// Computes the partial MPK that matches a partial MSK.
+pub fn public_key<C: Curve>(msk: &PartialMSK<C>) -> PartialMPK<C> {
+    PartialMPK {
+        auth: msk.auth.clone(),
+        a: C::H::generator().mul(msk.beta),
+        b: C::H::generator().mul(msk.b),
+        b_not: C::H::generator().mul(msk.b_not),
+        b_prime: C::G::generator().mul(msk.b_prime),
+    }
+}
@@ -1,4 +1,7 @@
-use crate::policy::{Policy, UserAttribute};
+use crate::{
+    error::{Abe4Error, Result},
+    policy::{Policy, UserAttribute},
+};
 use std::collections::HashMap;
 
 pub struct Tau {
@@ -71,18 +74,30 @@ impl Tau {
         self.m_tilde
     }
 
-    pub fn get_tilde(&self, auth: &str, lbl: &str, attr: &str) -> usize {
-        let key = (String::from(auth), String::from(lbl), String::from(attr));
-        *self.storage_tilde.get(&key).unwrap()
+    pub fn get_tilde(&self, auth: &str, lbl: &str, attr: &str) -> Result<usize> {
+        Self::lookup(&self.storage_tilde, auth, lbl, attr)
     }
 
     pub fn get_max(&self) -> usize {
         self.m
     }
 
-    pub fn get(&self, auth: &str, lbl: &str, attr: &str) -> usize {
+    pub fn get(&self, auth: &str, lbl: &str, attr: &str) -> Result<usize> {
+        Self::lookup(&self.storage, auth, lbl, attr)
+    }
+
+    /// Returns the index of the given attribute, or an error if the attribute
+    /// does not appear in the policy this `Tau` was built from.
+    fn lookup(
+        storage: &HashMap<(String, String, String), usize>,
+        auth: &str,
+        lbl: &str,
+        attr: &str,
+    ) -> Result<usize> {
         let key = (String::from(auth), String::from(lbl), String::from(attr));
-        *self.storage.get(&key).unwrap()
+        storage.get(&key).copied().ok_or_else(|| {
+            Abe4Error::InvalidInput(format!("attribute {auth}.{lbl}:{attr} not found in tau"))
+        })
     }
 }
 
@@ -99,12 +114,12 @@ fn test_tau_simple() {
     let policy = Policy::conjunction_of(&user_attrs, 0);
     let tau = Tau::new(&policy);
     assert_eq!(tau.m, 3);
-    assert_eq!(tau.get("0", "0", "0"), 0);
-    assert_eq!(tau.get("0", "0", "1"), 1);
-    assert_eq!(tau.get("0", "0", "2"), 2);
-    assert_eq!(tau.get("0", "0", "3"), 3);
-    assert_eq!(tau.get("1", "0", "0"), 0);
-    assert_eq!(tau.get("2", "0", "0"), 0);
+    assert_eq!(tau.get("0", "0", "0").unwrap(), 0);
+    assert_eq!(tau.get("0", "0", "1").unwrap(), 1);
+    assert_eq!(tau.get("0", "0", "2").unwrap(), 2);
+    assert_eq!(tau.get("0", "0", "3").unwrap(), 3);
+    assert_eq!(tau.get("1", "0", "0").unwrap(), 0);
+    assert_eq!(tau.get("2", "0", "0").unwrap(), 0);
 }
 
 #[test]
@@ -124,16 +139,16 @@ fn test_tau_complex() {
     let policy = Policy::conjunction_of(&user_attrs, 0);
     let tau = Tau::new(&policy);
     assert_eq!(tau.m, 2);
-    assert_eq!(tau.get("0", "0", "0"), 0);
-    assert_eq!(tau.get("0", "0", "1"), 1);
-    assert_eq!(tau.get("0", "1", "2"), 0);
-    assert_eq!(tau.get("0", "1", "3"), 1);
-    assert_eq!(tau.get("0", "1", "4"), 2);
-    assert_eq!(tau.get("1", "0", "5"), 0);
-    assert_eq!(tau.get("1", "1", "6"), 0);
-    assert_eq!(tau.get("1", "2", "7"), 0);
-    assert_eq!(tau.get("1", "3", "8"), 0);
-    assert_eq!(tau.get("1", "3", "9"), 1);
+    assert_eq!(tau.get("0", "0", "0").unwrap(), 0);
+    assert_eq!(tau.get("0", "0", "1").unwrap(), 1);
+    assert_eq!(tau.get("0", "1", "2").unwrap(), 0);
+    assert_eq!(tau.get("0", "1", "3").unwrap(), 1);
+    assert_eq!(tau.get("0", "1", "4").unwrap(), 2);
+    assert_eq!(tau.get("1", "0", "5").unwrap(), 0);
+    assert_eq!(tau.get("1", "1", "6").unwrap(), 0);
+    assert_eq!(tau.get("1", "2", "7").unwrap(), 0);
+    assert_eq!(tau.get("1", "3", "8").unwrap(), 0);
+    assert_eq!(tau.get("1", "3", "9").unwrap(), 1);
 }
 
 #[test]
@@ -153,16 +168,16 @@ fn test_tau_tilde_simple() {
     let policy = Policy::conjunction_of(&user_attrs, 0);
     let tau = Tau::new(&policy);
     assert_eq!(tau.m_tilde, 4);
-    assert_eq!(tau.get_tilde("0", "0", "0"), 0);
-    assert_eq!(tau.get_tilde("0", "1", "1"), 1);
-    assert_eq!(tau.get_tilde("0", "2", "2"), 2);
-    assert_eq!(tau.get_tilde("0", "3", "3"), 3);
-    assert_eq!(tau.get_tilde("0", "4", "4"), 4);
-    assert_eq!(tau.get_tilde("1", "5", "5"), 0);
-    assert_eq!(tau.get_tilde("1", "6", "6"), 1);
-    assert_eq!(tau.get_tilde("1", "7", "7"), 2);
-    assert_eq!(tau.get_tilde("1", "8", "8"), 3);
-    assert_eq!(tau.get_tilde("1", "9", "9"), 4);
+    assert_eq!(tau.get_tilde("0", "0", "0").unwrap(), 0);
+    assert_eq!(tau.get_tilde("0", "1", "1").unwrap(), 1);
+    assert_eq!(tau.get_tilde("0", "2", "2").unwrap(), 2);
+    assert_eq!(tau.get_tilde("0", "3", "3").unwrap(), 3);
+    assert_eq!(tau.get_tilde("0", "4", "4").unwrap(), 4);
+    assert_eq!(tau.get_tilde("1", "5", "5").unwrap(), 0);
+    assert_eq!(tau.get_tilde("1", "6", "6").unwrap(), 1);
+    assert_eq!(tau.get_tilde("1", "7", "7").unwrap(), 2);
+    assert_eq!(tau.get_tilde("1", "8", "8").unwrap(), 3);
+    assert_eq!(tau.get_tilde("1", "9", "9").unwrap(), 4);
 }
 
 #[test]
@@ -186,18 +201,18 @@ fn test_tau_tilde_complex() {
     let policy = Policy::conjunction_of(&user_attrs, 0);
     let tau = Tau::new(&policy);
     assert_eq!(tau.m_tilde, 4);
-    assert_eq!(tau.get_tilde("0", "0", "0"), 0);
-    assert_eq!(tau.get_tilde("0", "0", "1"), 1);
-    assert_eq!(tau.get_tilde("0", "0", "2"), 2);
-    assert_eq!(tau.get_tilde("0", "1", "3"), 3);
-    assert_eq!(tau.get_tilde("1", "1", "0"), 0);
-    assert_eq!(tau.get_tilde("1", "2", "1"), 1);
-    assert_eq!(tau.get_tilde("1", "2", "2"), 2);
-    assert_eq!(tau.get_tilde("1", "2", "3"), 3);
-    assert_eq!(tau.get_tilde("1", "2", "4"), 4);
-    assert_eq!(tau.get_tilde("2", "1", "0"), 0);
-    assert_eq!(tau.get_tilde("2", "2", "1"), 1);
-    assert_eq!(tau.get_tilde("3", "1", "1"), 0);
-    assert_eq!(tau.get_tilde("3", "1", "2"), 1);
-    assert_eq!(tau.get_tilde("3", "2", "1"), 2);
+    assert_eq!(tau.get_tilde("0", "0", "0").unwrap(), 0);
+    assert_eq!(tau.get_tilde("0", "0", "1").unwrap(), 1);
+    assert_eq!(tau.get_tilde("0", "0", "2").unwrap(), 2);
+    assert_eq!(tau.get_tilde("0", "1", "3").unwrap(), 3);
+    assert_eq!(tau.get_tilde("1", "1", "0").unwrap(), 0);
+    assert_eq!(tau.get_tilde("1", "2", "1").unwrap(), 1);
+    assert_eq!(tau.get_tilde("1", "2", "2").unwrap(), 2);
+    assert_eq!(tau.get_tilde("1", "2", "3").unwrap(), 3);
+    assert_eq!(tau.get_tilde("1", "2", "4").unwrap(), 4);
+    assert_eq!(tau.get_tilde("2", "1", "0").unwrap(), 0);
+    assert_eq!(tau.get_tilde("2", "2", "1").unwrap(), 1);
+    assert_eq!(tau.get_tilde("3", "1", "1").unwrap(), 0);
+    assert_eq!(tau.get_tilde("3", "1", "2").unwrap(), 1);
+    assert_eq!(tau.get_tilde("3", "2", "1").unwrap(), 2);
 }
@@ -0,0 +1,708 @@
+//! Threshold replication of one authority.
+//!
+//! `n` replicas of an authority, e.g. of an attestation service, jointly hold
+//! its partial MSK, so that any `t` of them can issue keys, and fewer than
+//! `t` learn nothing about it.
+//!
+//! The replicas run a joint-Feldman distributed key generation (DKG), once
+//! for each of the four scalars of a `PartialMSK`. Each replica deals random
+//! polynomials of degree `t - 1`: it broadcasts a `DkgCommitment` to their
+//! coefficients, and sends every replica, itself included, a `DkgShare` with
+//! their value at the index of the recipient. Each replica then checks the
+//! shares it received against the commitments, and adds them up into its
+//! `MskShare`. The polynomials and the MSK are never held by any replica.
+//!
+//! Keygen does not need `b'` itself, only `g^b'`, which is public. The key
+//! components are otherwise linear in the other scalars of the MSK, so each
+//! replica issues a `UskShare` with its own randomness, and `t` of them
+//! combine into a partial USK by Lagrange interpolation, without any further
+//! interaction between replicas.
+//!
+//! Replicas must agree on the commitments they finalise the DKG with, e.g. by
+//! comparing the resulting `ThresholdMpk`s, and a dealer must commit before
+//! seeing the commitments of the others.
+
+use crate::{
+    curve::{Curve, DefaultCurve},
+    error::{Abe4Error, Result},
+    hashing::HashingContext,
+    policy::UserAttribute,
+    scheme::{
+        iota::Iota,
+        keygen::keygen_partial_with,
+        setup::{public_key, setup_partial},
+        types::{PartialMPK, PartialMSK, PartialUSK},
+    },
+};
+use ark_ff::{Field, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, Valid, Write};
+use ark_std::rand::Rng;
+use std::collections::{HashMap, HashSet};
+
+// -----------------------------------------------------------------------------------------------
+// Structure Definitions
+// -----------------------------------------------------------------------------------------------
+
+/// # Description
+///
+/// Parameters of a threshold-replicated authority: its identifier, the number
+/// of replicas `replicas`, indexed from 1 to `replicas`, and the number of them
+/// `threshold` that is needed to issue a key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DkgParams {
+    pub auth: String,
+    pub threshold: u64,
+    pub replicas: u64,
+}
+
+/// # Description
+///
+/// Message broadcast by a dealer in the DKG: the commitments to the
+/// coefficients of its polynomials, lowest degree first.
+///
+/// The commitment to each coefficient is the partial MPK of the coefficients
+/// of the same degree, as if they were a partial MSK. The first one is the
+/// contribution of the dealer to the MPK of the authority.
+#[derive(Debug, PartialEq)]
+pub struct DkgCommitment<C: Curve = DefaultCurve> {
+    pub dealer: u64,
+    pub coeffs: Vec<PartialMPK<C>>,
+}
+
+/// # Description
+///
+/// Message sent privately by a dealer to a replica in the DKG: the value of
+/// each of the polynomials of the dealer at the index of the recipient, as a
+/// partial MSK.
+///
+/// The share is wiped when dropped, and never printed, like `PartialMSK`.
+#[derive(Debug, PartialEq)]
+pub struct DkgShare<C: Curve = DefaultCurve> {
+    pub dealer: u64,
+    pub recipient: u64,
+    pub share: PartialMSK<C>,
+}
+
+/// # Description
+///
+/// Share of the partial MSK of an authority, held by the replica at `index`.
+#[derive(Debug, PartialEq)]
+pub struct MskShare<C: Curve = DefaultCurve> {
+    pub index: u64,
+    pub msk: PartialMSK<C>,
+}
+
+/// # Description
+///
+/// Public outcome of the DKG: the sum of the commitments of all the dealers,
+/// which commits to the polynomials the `MskShare`s lie on.
+///
+/// Every replica finalises the DKG to the same `ThresholdMpk`. Its `mpk` is
+/// the partial MPK of the authority, that encryptors use like any other.
+#[derive(Debug, PartialEq)]
+pub struct ThresholdMpk<C: Curve = DefaultCurve> {
+    coeffs: Vec<PartialMPK<C>>,
+}
+
+/// # Description
+///
+/// Share of a partial USK, issued by the replica at `index`.
+///
+/// A share is a partial USK on its own, under the public key of the replica
+/// (see `ThresholdMpk::replica_mpk`), and is kept as secret as one.
+#[derive(Debug, PartialEq)]
+pub struct UskShare<C: Curve = DefaultCurve> {
+    pub index: u64,
+    pub usk: PartialUSK<C>,
+}
+
+// -----------------------------------------------------------------------------------------------
+// Implementations
+// -----------------------------------------------------------------------------------------------
+
+impl DkgParams {
+    /// Returns the parameters of a `threshold`-of-`replicas` authority, or
+    /// `Abe4Error::InvalidInput` unless `1 <= threshold <= replicas`.
+    pub fn new(auth: &str, threshold: u64, replicas: u64) -> Result<Self> {
+        if threshold == 0 || threshold > replicas {
+            return Err(Abe4Error::InvalidInput(format!(
+                "cannot share a key {threshold}-of-{replicas}"
+            )));
+        }
+        Ok(Self {
+            auth: auth.to_string(),
+            threshold,
+            replicas,
+        })
+    }
+
+    fn check_index(&self, index: u64) -> Result<()> {
+        if index == 0 || index > self.replicas {
+            return Err(Abe4Error::InvalidInput(format!(
+                "{}: no replica with index {index}",
+                self.auth
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<C: Curve> ThresholdMpk<C> {
+    /// Returns the outcome of a DKG from its commitments, lowest degree first,
+    /// or `Abe4Error::InvalidInput` unless there is at least one, and all are
+    /// for the same authority.
+    pub fn new(coeffs: Vec<PartialMPK<C>>) -> Result<Self> {
+        let Some(first) = coeffs.first() else {
+            return Err(Abe4Error::InvalidInput(String::from(
+                "threshold MPK without coefficients",
+            )));
+        };
+        if coeffs.iter().any(|c| c.auth != first.auth) {
+            return Err(Abe4Error::InvalidInput(format!(
+                "{}: threshold MPK with coefficients of several authorities",
+                first.auth
+            )));
+        }
+        Ok(Self { coeffs })
+    }
+
+    /// Returns the commitments to the coefficients of the polynomials of the
+    /// DKG, lowest degree first.
+    pub fn coeffs(&self) -> &[PartialMPK<C>] {
+        &self.coeffs
+    }
+
+    /// Returns the number of replicas needed to issue a key.
+    pub fn threshold(&self) -> usize {
+        self.coeffs.len()
+    }
+
+    /// Returns the partial MPK of the authority.
+    pub fn mpk(&self) -> PartialMPK<C> {
+        eval_mpk(&self.coeffs, 0)
+    }
+
+    /// # Description
+    ///
+    /// Returns the public key of the replica at `index`, i.e. the partial MPK
+    /// of its `MskShare`, except for `b'`, which is the one of the authority.
+    ///
+    /// The `UskShare`s of the replica verify against it with
+    /// `PartialUSK::verify`.
+    pub fn replica_mpk(&self, index: u64) -> PartialMPK<C> {
+        let mut mpk = eval_mpk(&self.coeffs, index);
+        mpk.b_prime = self.coeffs[0].b_prime;
+        mpk
+    }
+}
+
+fn zero_msk<C: Curve>(auth: &str) -> PartialMSK<C> {
+    PartialMSK {
+        auth: auth.to_string(),
+        beta: C::ScalarField::zero(),
+        b: C::ScalarField::zero(),
+        b_not: C::ScalarField::zero(),
+        b_prime: C::ScalarField::zero(),
+    }
+}
+
+fn zero_mpk<C: Curve>(auth: &str) -> PartialMPK<C> {
+    PartialMPK {
+        auth: auth.to_string(),
+        a: C::H::zero(),
+        b: C::H::zero(),
+        b_not: C::H::zero(),
+        b_prime: C::G::zero(),
+    }
+}
+
+/// Evaluates at `x` the polynomials whose coefficients are the scalars of
+/// `coeffs`, lowest degree first.
+fn eval_msk<C: Curve>(coeffs: &[PartialMSK<C>], x: u64) -> PartialMSK<C> {
+    let x = C::ScalarField::from(x);
+    let mut msk = zero_msk(&coeffs[0].auth);
+    for coeff in coeffs.iter().rev() {
+        msk.beta = msk.beta * x + coeff.beta;
+        msk.b = msk.b * x + coeff.b;
+        msk.b_not = msk.b_not * x + coeff.b_not;
+        msk.b_prime = msk.b_prime * x + coeff.b_prime;
+    }
+    msk
+}
+
+/// Like `eval_msk`, in the exponent of the commitments `coeffs`.
+fn eval_mpk<C: Curve>(coeffs: &[PartialMPK<C>], x: u64) -> PartialMPK<C> {
+    let x = C::ScalarField::from(x);
+    let mut mpk = zero_mpk(&coeffs[0].auth);
+    for coeff in coeffs.iter().rev() {
+        mpk.a = mpk.a * x + coeff.a;
+        mpk.b = mpk.b * x + coeff.b;
+        mpk.b_not = mpk.b_not * x + coeff.b_not;
+        mpk.b_prime = mpk.b_prime * x + coeff.b_prime;
+    }
+    mpk
+}
+
+/// Returns the Lagrange coefficient of `index` to interpolate at 0 from the
+/// values at `indices`, which must be distinct and non-zero.
+fn lagrange_at_zero<F: Field>(indices: &[u64], index: u64) -> F {
+    let mut num = F::one();
+    let mut den = F::one();
+    for &other in indices.iter().filter(|other| **other != index) {
+        num *= F::from(other);
+        den *= F::from(other) - F::from(index);
+    }
+    num * den.inverse().expect("indices are distinct")
+}
+
+// -----------------------------------------------------------------------------------------------
+// Protocol
+// -----------------------------------------------------------------------------------------------
+
+/// # Description
+///
+/// First (and only) round of the DKG, run by each replica as a dealer.
+///
+/// # Arguments
+///
+/// * `rng`: A random number generator.
+/// * `params`: The parameters of the authority.
+/// * `dealer`: The index of the replica that runs it.
+///
+/// # Returns
+///
+/// The commitment to broadcast to all the replicas, and the share to send to
+/// each of them, in order of index.
+pub fn dkg_deal<C: Curve>(
+    mut rng: impl Rng,
+    params: &DkgParams,
+    dealer: u64,
+) -> Result<(DkgCommitment<C>, Vec<DkgShare<C>>)> {
+    params.check_index(dealer)?;
+    let mut poly = Vec::new();
+    let mut coeffs = Vec::new();
+    for _ in 0..params.threshold {
+        let (msk, mpk) = setup_partial(&mut rng, &params.auth);
+        poly.push(msk);
+        coeffs.push(mpk);
+    }
+    let shares = (1..=params.replicas)
+        .map(|recipient| DkgShare {
+            dealer,
+            recipient,
+            share: eval_msk(&poly, recipient),
+        })
+        .collect();
+    Ok((DkgCommitment { dealer, coeffs }, shares))
+}
+
+/// # Description
+///
+/// Finishes the DKG for the replica at `index`.
+///
+/// # Arguments
+///
+/// * `params`: The parameters of the authority.
+/// * `index`: The index of the replica that runs it.
+/// * `commitments`: The commitments of the dealers. All the replicas must use
+///   the same ones, e.g. all of them, or the ones of the dealers that no
+///   replica complained about.
+/// * `shares`: The shares the replica received from those dealers.
+///
+/// # Returns
+///
+/// The MSK share of the replica, and the public outcome of the DKG.
+/// Otherwise, `Abe4Error::InvalidInput` if the messages are malformed or do
+/// not match each other, or `Abe4Error::InvalidKey` naming the first dealer
+/// whose share does not match its commitment.
+pub fn dkg_finalize<C: Curve>(
+    params: &DkgParams,
+    index: u64,
+    commitments: &[DkgCommitment<C>],
+    shares: &[DkgShare<C>],
+) -> Result<(MskShare<C>, ThresholdMpk<C>)> {
+    params.check_index(index)?;
+    let invalid = |reason: String| Abe4Error::InvalidInput(format!("{}: {reason}", params.auth));
+    if commitments.is_empty() {
+        return Err(invalid(String::from("no commitments given")));
+    }
+
+    let mut shares_by_dealer: HashMap<u64, &DkgShare<C>> = HashMap::new();
+    for share in shares {
+        if share.recipient != index || share.share.auth != params.auth {
+            return Err(invalid(format!(
+                "share from dealer {} is not for this replica",
+                share.dealer
+            )));
+        }
+        if shares_by_dealer.insert(share.dealer, share).is_some() {
+            return Err(invalid(format!(
+                "several shares from dealer {}",
+                share.dealer
+            )));
+        }
+    }
+    if shares_by_dealer.len() != commitments.len() {
+        return Err(invalid(format!(
+            "{} shares given for {} commitments",
+            shares_by_dealer.len(),
+            commitments.len()
+        )));
+    }
+
+    let mut msk = zero_msk(&params.auth);
+    let mut sum = ThresholdMpk {
+        coeffs: (0..params.threshold)
+            .map(|_| zero_mpk(&params.auth))
+            .collect(),
+    };
+    let mut dealers = HashSet::new();
+    for commitment in commitments {
+        params.check_index(commitment.dealer)?;
+        if !dealers.insert(commitment.dealer) {
+            return Err(invalid(format!(
+                "several commitments from dealer {}",
+                commitment.dealer
+            )));
+        }
+        if commitment.coeffs.len() as u64 != params.threshold
+            || commitment.coeffs.iter().any(|c| c.auth != params.auth)
+        {
+            return Err(invalid(format!(
+                "commitment from dealer {} does not match the parameters",
+                commitment.dealer
+            )));
+        }
+        let share = shares_by_dealer
+            .get(&commitment.dealer)
+            .ok_or_else(|| invalid(format!("no share from dealer {}", commitment.dealer)))?;
+        if public_key(&share.share) != eval_mpk(&commitment.coeffs, index) {
+            return Err(Abe4Error::InvalidKey(format!(
+                "{}: share from dealer {} does not match its commitment",
+                params.auth, commitment.dealer
+            )));
+        }
+
+        msk.beta += share.share.beta;
+        msk.b += share.share.b;
+        msk.b_not += share.share.b_not;
+        msk.b_prime += share.share.b_prime;
+        for (acc, c) in sum.coeffs.iter_mut().zip(&commitment.coeffs) {
+            acc.a += c.a;
+            acc.b += c.b;
+            acc.b_not += c.b_not;
+            acc.b_prime += c.b_prime;
+        }
+    }
+    Ok((MskShare { index, msk }, sum))
+}
+
+/// # Description
+///
+/// Issues the share of a partial USK of the replica that holds `share`.
+///
+/// # Arguments
+///
+/// * `rng`: A random number generator.
+/// * `gid`: The global identifier of the user.
+/// * `tmpk`: The public outcome of the DKG.
+/// * `share`: The MSK share of the replica.
+/// * `user_attrs`: The attributes of the user, as for `keygen_partial`.
+/// * `iota`: The `Iota` object derived from the user's attributes.
+///
+/// # Returns
+///
+/// The key share, or the errors of `keygen_partial`.
+pub fn keygen_share<C: Curve>(
+    rng: impl Rng,
+    gid: &str,
+    tmpk: &ThresholdMpk<C>,
+    share: &MskShare<C>,
+    user_attrs: &[&UserAttribute],
+    iota: &Iota,
+) -> Result<UskShare<C>> {
+    let b_prime = tmpk.coeffs[0].b_prime;
+    let usk = keygen_partial_with(
+        rng,
+        gid,
+        &share.msk,
+        b_prime,
+        user_attrs,
+        iota,
+        &HashingContext::new(),
+    )?;
+    Ok(UskShare {
+        index: share.index,
+        usk,
+    })
+}
+
+/// # Description
+///
+/// Combines the key shares of at least `threshold` replicas into the partial
+/// USK of the authority.
+///
+/// Shares are not verified: a share of a misbehaving replica yields a key that
+/// does not verify. Check each share with `PartialUSK::verify` against
+/// `ThresholdMpk::replica_mpk` to find out which replica it is.
+///
+/// # Returns
+///
+/// The partial USK, or `Abe4Error::InvalidInput` if fewer than `threshold`
+/// shares are given, or if they were not issued by distinct replicas for the
+/// same GID and attributes.
+pub fn combine_usk_shares<C: Curve>(
+    tmpk: &ThresholdMpk<C>,
+    shares: &[UskShare<C>],
+) -> Result<PartialUSK<C>> {
+    let auth = &tmpk.coeffs[0].auth;
+    let invalid = |reason: String| Abe4Error::InvalidInput(format!("{auth}: {reason}"));
+    if shares.len() < tmpk.threshold() {
+        return Err(invalid(format!(
+            "{} key shares given, but {} are needed",
+            shares.len(),
+            tmpk.threshold()
+        )));
+    }
+    let indices: Vec<u64> = shares.iter().map(|share| share.index).collect();
+    if indices.contains(&0) || indices.iter().collect::<HashSet<_>>().len() != indices.len() {
+        return Err(invalid(String::from(
+            "key shares are not from distinct replicas",
+        )));
+    }
+    let first = &shares[0].usk;
+    for share in shares {
+        let usk = &share.usk;
+        if usk.auth != *auth
+            || usk.gid != first.gid
+            || usk.k_1_1_vec.len() != first.k_1_1_vec.len()
+            || usk.k_4_vec.len() != first.k_1_1_vec.len()
+            || usk.k_5_vec.len() != first.k_1_1_vec.len()
+            || usk.k_1_2_map.keys().collect::<HashSet<_>>()
+                != first.k_1_2_map.keys().collect::<HashSet<_>>()
+            || usk.k_2_map.keys().collect::<HashSet<_>>()
+                != first.k_2_map.keys().collect::<HashSet<_>>()
+            || usk.k_3_map.keys().collect::<HashSet<_>>()
+                != first.k_3_map.keys().collect::<HashSet<_>>()
+        {
+            return Err(invalid(String::from(
+                "key shares were not issued for the same GID and attributes",
+            )));
+        }
+    }
+
+    // Each component of the key is the same linear combination of the
+    // components of the shares.
+    let mut usk = PartialUSK {
+        auth: auth.clone(),
+        gid: first.gid.clone(),
+        k_1_1_vec: vec![C::G::zero(); first.k_1_1_vec.len()],
+        k_1_2_map: HashMap::new(),
+        k_2_map: HashMap::new(),
+        k_3_map: HashMap::new(),
+        k_4_vec: vec![C::H::zero(); first.k_1_1_vec.len()],
+        k_5_vec: vec![C::H::zero(); first.k_1_1_vec.len()],
+    };
+    for share in shares {
+        let lambda: C::ScalarField = lagrange_at_zero(&indices, share.index);
+        let share = &share.usk;
+        for (acc, k) in usk.k_1_1_vec.iter_mut().zip(&share.k_1_1_vec) {
+            *acc += *k * lambda;
+        }
+        for (key, k) in &share.k_1_2_map {
+            *usk.k_1_2_map.entry(key.clone()).or_default() += *k * lambda;
+        }
+        for (key, k) in &share.k_2_map {
+            *usk.k_2_map.entry(key.clone()).or_default() += *k * lambda;
+        }
+        for (key, k) in &share.k_3_map {
+            *usk.k_3_map.entry(key.clone()).or_default() += *k * lambda;
+        }
+        for (acc, k) in usk.k_4_vec.iter_mut().zip(&share.k_4_vec) {
+            *acc += *k * lambda;
+        }
+        for (acc, k) in usk.k_5_vec.iter_mut().zip(&share.k_5_vec) {
+            *acc += *k * lambda;
+        }
+    }
+    Ok(usk)
+}
+
+// -----------------------------------------------------------------------------------------------
+// Serialization
+// -----------------------------------------------------------------------------------------------
+
+impl<C: Curve> CanonicalSerialize for DkgCommitment<C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        mode: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        self.dealer.serialize_with_mode(&mut writer, mode)?;
+        self.coeffs.serialize_with_mode(&mut writer, mode)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self, mode: ark_serialize::Compress) -> usize {
+        self.dealer.serialized_size(mode) + self.coeffs.serialized_size(mode)
+    }
+}
+
+impl<C: Curve> CanonicalDeserialize for DkgCommitment<C> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        Ok(Self {
+            dealer: u64::deserialize_with_mode(&mut reader, compress, validate)?,
+            coeffs: Vec::<PartialMPK<C>>::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+impl<C: Curve> Valid for DkgCommitment<C> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        self.coeffs.check()
+    }
+}
+
+impl<C: Curve> CanonicalSerialize for DkgShare<C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        mode: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        self.dealer.serialize_with_mode(&mut writer, mode)?;
+        self.recipient.serialize_with_mode(&mut writer, mode)?;
+        self.share.serialize_with_mode(&mut writer, mode)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self, mode: ark_serialize::Compress) -> usize {
+        self.dealer.serialized_size(mode)
+            + self.recipient.serialized_size(mode)
+            + self.share.serialized_size(mode)
+    }
+}
+
+impl<C: Curve> CanonicalDeserialize for DkgShare<C> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        Ok(Self {
+            dealer: u64::deserialize_with_mode(&mut reader, compress, validate)?,
+            recipient: u64::deserialize_with_mode(&mut reader, compress, validate)?,
+            share: PartialMSK::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+impl<C: Curve> Valid for DkgShare<C> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        self.share.check()
+    }
+}
+
+impl<C: Curve> CanonicalSerialize for MskShare<C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        mode: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        self.index.serialize_with_mode(&mut writer, mode)?;
+        self.msk.serialize_with_mode(&mut writer, mode)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self, mode: ark_serialize::Compress) -> usize {
+        self.index.serialized_size(mode) + self.msk.serialized_size(mode)
+    }
+}
+
+impl<C: Curve> CanonicalDeserialize for MskShare<C> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        Ok(Self {
+            index: u64::deserialize_with_mode(&mut reader, compress, validate)?,
+            msk: PartialMSK::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+impl<C: Curve> Valid for MskShare<C> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        self.msk.check()
+    }
+}
+
+impl<C: Curve> CanonicalSerialize for ThresholdMpk<C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        mode: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        self.coeffs.serialize_with_mode(writer, mode)
+    }
+
+    fn serialized_size(&self, mode: ark_serialize::Compress) -> usize {
+        self.coeffs.serialized_size(mode)
+    }
+}
+
+impl<C: Curve> CanonicalDeserialize for ThresholdMpk<C> {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        let coeffs = Vec::<PartialMPK<C>>::deserialize_with_mode(reader, compress, validate)?;
+        Self::new(coeffs).map_err(|_| ark_serialize::SerializationError::InvalidData)
+    }
+}
+
+impl<C: Curve> Valid for ThresholdMpk<C> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        self.coeffs.check()
+    }
+}
+
+impl<C: Curve> CanonicalSerialize for UskShare<C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        mode: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        self.index.serialize_with_mode(&mut writer, mode)?;
+        self.usk.serialize_with_mode(&mut writer, mode)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self, mode: ark_serialize::Compress) -> usize {
+        self.index.serialized_size(mode) + self.usk.serialized_size(mode)
+    }
+}
+
+impl<C: Curve> CanonicalDeserialize for UskShare<C> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        Ok(Self {
+            index: u64::deserialize_with_mode(&mut reader, compress, validate)?,
+            usk: PartialUSK::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+impl<C: Curve> Valid for UskShare<C> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        self.usk.check()
+    }
+}
@@ -1,9 +1,25 @@
 use crate::{
-    curve::{G, H, ScalarField},
+    curve::{Curve, DefaultCurve},
+    error::{Abe4Error, Result},
+    hashing::{
+        HashSign::{Neg, Pos},
+        HashingContext,
+    },
     policy::UserAttribute,
+    scheme::{
+        decrypt::check_gid,
+        iota::{Iota, sort_user_attrs},
+    },
 };
+use ark_ec::Group;
+use ark_ff::One;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, Valid, Write};
-use std::collections::{HashMap, hash_map::Entry};
+use std::{
+    collections::{HashMap, HashSet, hash_map::Entry},
+    fmt,
+    ops::Neg as _,
+};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 // -----------------------------------------------------------------------------------------------
 // Structure and Trait Definitions
@@ -11,11 +27,50 @@ use std::collections::{HashMap, hash_map::Entry};
 
 /// Structure representing a cipher-text.
 #[derive(Clone, Debug, PartialEq)]
-pub struct Ciphertext {
-    pub c_1_vec: Vec<H>,
-    pub c_2_vec: Vec<G>,
-    pub c_3_vec: Vec<H>,
-    pub c_4_vec: Vec<H>,
+pub struct Ciphertext<C: Curve = DefaultCurve> {
+    pub c_1_vec: Vec<C::H>,
+    pub c_2_vec: Vec<C::G>,
+    pub c_3_vec: Vec<C::H>,
+    pub c_4_vec: Vec<C::H>,
+}
+
+/// # Description
+///
+/// Struct representing a transformation key.
+///
+/// A transformation key is a user secret key with each of its group elements
+/// raised to `1/z`, for a secret `z` that the user keeps as the matching
+/// `RetrievalKey`. It lets an untrusted helper partially decrypt cipher-texts
+/// on behalf of the user, without learning their symmetric keys.
+#[derive(Debug, PartialEq)]
+pub struct TransformKey<C: Curve = DefaultCurve> {
+    pub usk: USK<C>,
+}
+
+/// # Description
+///
+/// Struct representing a retrieval key: the secret `z` that blinds a
+/// `TransformKey`.
+///
+/// The secret scalar is wiped when the key is dropped, and is never printed by
+/// its `Debug` implementation.
+#[derive(PartialEq)]
+pub struct RetrievalKey<C: Curve = DefaultCurve> {
+    pub z: C::ScalarField,
+}
+
+/// # Description
+///
+/// Struct representing a cipher-text partially decrypted with a
+/// `TransformKey`.
+///
+/// The symmetric key is `c_0 * c_1^z`: `c_0` is the part of the decryption
+/// that does not involve the key, and `c_1` is the part that does, raised to
+/// `1/z` by the blinding of the transformation key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransformedCiphertext<C: Curve = DefaultCurve> {
+    pub c_0: C::Gt,
+    pub c_1: C::Gt,
 }
 
 /// Trait shared by all partial key structures.
@@ -31,13 +86,16 @@ pub trait PartialKey {
 /// This key belongs to one authority in decentralized CP-ABE. The authority is
 /// identified by the `auth` string, a unique identifier. This secret key is
 /// given to the authority during the setup phase.
-#[derive(PartialEq, Debug)]
-pub struct PartialMSK {
+///
+/// The secret scalars are wiped when the key is dropped, and are never printed
+/// by its `Debug` implementation.
+#[derive(PartialEq)]
+pub struct PartialMSK<C: Curve = DefaultCurve> {
     pub auth: String,
-    pub beta: ScalarField,
-    pub b: ScalarField,
-    pub b_not: ScalarField,
-    pub b_prime: ScalarField,
+    pub beta: C::ScalarField,
+    pub b: C::ScalarField,
+    pub b_not: C::ScalarField,
+    pub b_prime: C::ScalarField,
 }
 
 /// # Description
@@ -49,12 +107,12 @@ pub struct PartialMSK {
 /// meant to be publicly available, together with the `auth` String, so that
 /// users can encrypt policies with attributes from this authority.
 #[derive(Debug, PartialEq)]
-pub struct PartialMPK {
+pub struct PartialMPK<C: Curve = DefaultCurve> {
     pub auth: String,
-    pub a: H,
-    pub b: H,
-    pub b_not: H,
-    pub b_prime: G,
+    pub a: C::H,
+    pub b: C::H,
+    pub b_not: C::H,
+    pub b_prime: C::G,
 }
 
 /// # Description
@@ -65,15 +123,24 @@ pub struct PartialMPK {
 /// authority `auth`. It is called "partial", because it should be combined with
 /// other keys from all other necessary authoritties that can potentially be
 /// involved in decryption.
-#[derive(PartialEq, Debug)]
-pub struct PartialUSK {
+///
+/// The key is bound to the global identifier `gid` of the user it was issued
+/// to, and can only be used to decrypt on behalf of that user. Keys serialized
+/// before the GID was part of the wire format decode with an empty `gid`,
+/// which means the GID is unknown and is not checked.
+///
+/// The group elements are wiped when the key is dropped, and are never printed
+/// by its `Debug` implementation.
+#[derive(PartialEq)]
+pub struct PartialUSK<C: Curve = DefaultCurve> {
     pub auth: String,
-    pub k_1_1_vec: Vec<G>,
-    pub k_1_2_map: HashMap<(String, String), G>,
-    pub k_2_map: HashMap<String, G>,
-    pub k_3_map: HashMap<(String, String), G>,
-    pub k_4_vec: Vec<H>,
-    pub k_5_vec: Vec<H>,
+    pub gid: String,
+    pub k_1_1_vec: Vec<C::G>,
+    pub k_1_2_map: HashMap<(String, String), C::G>,
+    pub k_2_map: HashMap<String, C::G>,
+    pub k_3_map: HashMap<(String, String), C::G>,
+    pub k_4_vec: Vec<C::H>,
+    pub k_5_vec: Vec<C::H>,
 }
 
 /// Struct representing a full key given a set of partial keys.
@@ -84,13 +151,13 @@ pub struct FullKey<T> {
 
 /// Master Public Key.
 #[allow(clippy::upper_case_acronyms)]
-pub type MPK = FullKey<PartialMPK>;
+pub type MPK<C = DefaultCurve> = FullKey<PartialMPK<C>>;
 /// Master Secret Key.
 #[allow(clippy::upper_case_acronyms)]
-pub type MSK = FullKey<PartialMSK>;
+pub type MSK<C = DefaultCurve> = FullKey<PartialMSK<C>>;
 /// User Secret Key.
 #[allow(clippy::upper_case_acronyms)]
-pub type USK = FullKey<PartialUSK>;
+pub type USK<C = DefaultCurve> = FullKey<PartialUSK<C>>;
 
 // -----------------------------------------------------------------------------------------------
 // Serialization Helpers
@@ -98,7 +165,7 @@ pub type USK = FullKey<PartialUSK>;
 // See: https://docs.rs/ark-serialize/latest/ark_serialize/
 // -----------------------------------------------------------------------------------------------
 
-impl CanonicalSerialize for PartialMPK {
+impl<C: Curve> CanonicalSerialize for PartialMPK<C> {
     fn serialize_with_mode<W: Write>(
         &self,
         mut writer: W,
@@ -123,7 +190,7 @@ impl CanonicalSerialize for PartialMPK {
     }
 }
 
-impl CanonicalDeserialize for PartialMPK {
+impl<C: Curve> CanonicalDeserialize for PartialMPK<C> {
     fn deserialize_with_mode<R: Read>(
         mut reader: R,
         compress: ark_serialize::Compress,
@@ -136,15 +203,15 @@ impl CanonicalDeserialize for PartialMPK {
             String::from_utf8(bytes).map_err(|_| ark_serialize::SerializationError::InvalidData)?;
         Ok(Self {
             auth,
-            a: H::deserialize_with_mode(&mut reader, compress, validate)?,
-            b: H::deserialize_with_mode(&mut reader, compress, validate)?,
-            b_not: H::deserialize_with_mode(&mut reader, compress, validate)?,
-            b_prime: G::deserialize_with_mode(&mut reader, compress, validate)?,
+            a: C::H::deserialize_with_mode(&mut reader, compress, validate)?,
+            b: C::H::deserialize_with_mode(&mut reader, compress, validate)?,
+            b_not: C::H::deserialize_with_mode(&mut reader, compress, validate)?,
+            b_prime: C::G::deserialize_with_mode(&mut reader, compress, validate)?,
         })
     }
 }
 
-impl Valid for PartialMPK {
+impl<C: Curve> Valid for PartialMPK<C> {
     fn check(&self) -> Result<(), ark_serialize::SerializationError> {
         self.a.check()?;
         self.b.check()?;
@@ -154,7 +221,7 @@ impl Valid for PartialMPK {
     }
 }
 
-impl CanonicalSerialize for PartialMSK {
+impl<C: Curve> CanonicalSerialize for PartialMSK<C> {
     fn serialize_with_mode<W: Write>(
         &self,
         mut writer: W,
@@ -179,7 +246,7 @@ impl CanonicalSerialize for PartialMSK {
     }
 }
 
-impl CanonicalDeserialize for PartialMSK {
+impl<C: Curve> CanonicalDeserialize for PartialMSK<C> {
     fn deserialize_with_mode<R: Read>(
         mut reader: R,
         compress: ark_serialize::Compress,
@@ -192,15 +259,15 @@ impl CanonicalDeserialize for PartialMSK {
             String::from_utf8(bytes).map_err(|_| ark_serialize::SerializationError::InvalidData)?;
         Ok(Self {
             auth,
-            beta: ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
-            b: ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
-            b_not: ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
-            b_prime: ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
+            beta: C::ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
+            b: C::ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
+            b_not: C::ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
+            b_prime: C::ScalarField::deserialize_with_mode(&mut reader, compress, validate)?,
         })
     }
 }
 
-impl Valid for PartialMSK {
+impl<C: Curve> Valid for PartialMSK<C> {
     fn check(&self) -> Result<(), ark_serialize::SerializationError> {
         self.beta.check()?;
         self.b.check()?;
@@ -210,14 +277,24 @@ impl Valid for PartialMSK {
     }
 }
 
-impl CanonicalSerialize for PartialUSK {
+/// Value of the leading `u64` of a serialized `PartialUSK` in the versioned
+/// wire format. In the original format the leading `u64` is the length of the
+/// authority name, which can never be `u64::MAX`.
+const PARTIAL_USK_VERSION_MARKER: u64 = u64::MAX;
+/// Version of the `PartialUSK` wire format that follows the marker.
+const PARTIAL_USK_VERSION: u8 = 1;
+
+impl<C: Curve> CanonicalSerialize for PartialUSK<C> {
     fn serialize_with_mode<W: Write>(
         &self,
         mut writer: W,
         mode: ark_serialize::Compress,
     ) -> Result<(), ark_serialize::SerializationError> {
+        PARTIAL_USK_VERSION_MARKER.serialize_with_mode(&mut writer, mode)?;
+        PARTIAL_USK_VERSION.serialize_with_mode(&mut writer, mode)?;
         (self.auth.len() as u64).serialize_with_mode(&mut writer, mode)?;
         writer.write_all(self.auth.as_bytes())?;
+        self.gid.serialize_with_mode(&mut writer, mode)?;
         self.k_1_1_vec.serialize_with_mode(&mut writer, mode)?;
 
         // k_1_2_map
@@ -247,7 +324,10 @@ impl CanonicalSerialize for PartialUSK {
     }
 
     fn serialized_size(&self, mode: ark_serialize::Compress) -> usize {
-        let mut size = (self.auth.len() as u64).serialized_size(mode) + self.auth.len();
+        let mut size = PARTIAL_USK_VERSION_MARKER.serialized_size(mode);
+        size += PARTIAL_USK_VERSION.serialized_size(mode);
+        size += (self.auth.len() as u64).serialized_size(mode) + self.auth.len();
+        size += self.gid.serialized_size(mode);
         size += self.k_1_1_vec.serialized_size(mode);
 
         // k_1_2_map
@@ -277,26 +357,41 @@ impl CanonicalSerialize for PartialUSK {
     }
 }
 
-impl CanonicalDeserialize for PartialUSK {
+impl<C: Curve> CanonicalDeserialize for PartialUSK<C> {
     fn deserialize_with_mode<R: Read>(
         mut reader: R,
         compress: ark_serialize::Compress,
         validate: ark_serialize::Validate,
     ) -> Result<Self, ark_serialize::SerializationError> {
-        let auth_len = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+        // Keys serialized before the format was versioned start directly with
+        // the authority name, and carry no GID.
+        let mut auth_len = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+        let versioned = auth_len == PARTIAL_USK_VERSION_MARKER;
+        if versioned {
+            let version = u8::deserialize_with_mode(&mut reader, compress, validate)?;
+            if version != PARTIAL_USK_VERSION {
+                return Err(ark_serialize::SerializationError::InvalidData);
+            }
+            auth_len = u64::deserialize_with_mode(&mut reader, compress, validate)?;
+        }
         let mut auth_bytes = vec![0; auth_len as usize];
         reader.read_exact(&mut auth_bytes)?;
         let auth = String::from_utf8(auth_bytes)
             .map_err(|_| ark_serialize::SerializationError::InvalidData)?;
+        let gid = if versioned {
+            String::deserialize_with_mode(&mut reader, compress, validate)?
+        } else {
+            String::new()
+        };
 
-        let k_1_1_vec = Vec::<G>::deserialize_with_mode(&mut reader, compress, validate)?;
+        let k_1_1_vec = Vec::<C::G>::deserialize_with_mode(&mut reader, compress, validate)?;
 
         // k_1_2_map
         let k_1_2_map_len = u64::deserialize_with_mode(&mut reader, compress, validate)?;
         let mut k_1_2_map = HashMap::with_capacity(k_1_2_map_len as usize);
         for _ in 0..k_1_2_map_len {
             let key = <(String, String)>::deserialize_with_mode(&mut reader, compress, validate)?;
-            let value = G::deserialize_with_mode(&mut reader, compress, validate)?;
+            let value = C::G::deserialize_with_mode(&mut reader, compress, validate)?;
             k_1_2_map.insert(key, value);
         }
 
@@ -305,7 +400,7 @@ impl CanonicalDeserialize for PartialUSK {
         let mut k_2_map = HashMap::with_capacity(k_2_map_len as usize);
         for _ in 0..k_2_map_len {
             let key = String::deserialize_with_mode(&mut reader, compress, validate)?;
-            let value = G::deserialize_with_mode(&mut reader, compress, validate)?;
+            let value = C::G::deserialize_with_mode(&mut reader, compress, validate)?;
             k_2_map.insert(key, value);
         }
 
@@ -314,15 +409,16 @@ impl CanonicalDeserialize for PartialUSK {
         let mut k_3_map = HashMap::with_capacity(k_3_map_len as usize);
         for _ in 0..k_3_map_len {
             let key = <(String, String)>::deserialize_with_mode(&mut reader, compress, validate)?;
-            let value = G::deserialize_with_mode(&mut reader, compress, validate)?;
+            let value = C::G::deserialize_with_mode(&mut reader, compress, validate)?;
             k_3_map.insert(key, value);
         }
 
-        let k_4_vec = Vec::<H>::deserialize_with_mode(&mut reader, compress, validate)?;
-        let k_5_vec = Vec::<H>::deserialize_with_mode(&mut reader, compress, validate)?;
+        let k_4_vec = Vec::<C::H>::deserialize_with_mode(&mut reader, compress, validate)?;
+        let k_5_vec = Vec::<C::H>::deserialize_with_mode(&mut reader, compress, validate)?;
 
         Ok(Self {
             auth,
+            gid,
             k_1_1_vec,
             k_1_2_map,
             k_2_map,
@@ -333,7 +429,7 @@ impl CanonicalDeserialize for PartialUSK {
     }
 }
 
-impl Valid for PartialUSK {
+impl<C: Curve> Valid for PartialUSK<C> {
     fn check(&self) -> Result<(), ark_serialize::SerializationError> {
         self.k_1_1_vec.check()?;
         for (k, v) in &self.k_1_2_map {
@@ -358,7 +454,7 @@ impl Valid for PartialUSK {
 // Serialization Of Ciphertexts
 // -----------------------------------------------------------------------------------------------
 
-impl CanonicalSerialize for Ciphertext {
+impl<C: Curve> CanonicalSerialize for Ciphertext<C> {
     fn serialize_with_mode<W: Write>(
         &self,
         mut writer: W,
@@ -379,22 +475,22 @@ impl CanonicalSerialize for Ciphertext {
     }
 }
 
-impl CanonicalDeserialize for Ciphertext {
+impl<C: Curve> CanonicalDeserialize for Ciphertext<C> {
     fn deserialize_with_mode<R: Read>(
         mut reader: R,
         compress: ark_serialize::Compress,
         validate: ark_serialize::Validate,
     ) -> Result<Self, ark_serialize::SerializationError> {
         Ok(Self {
-            c_1_vec: Vec::<H>::deserialize_with_mode(&mut reader, compress, validate)?,
-            c_2_vec: Vec::<G>::deserialize_with_mode(&mut reader, compress, validate)?,
-            c_3_vec: Vec::<H>::deserialize_with_mode(&mut reader, compress, validate)?,
-            c_4_vec: Vec::<H>::deserialize_with_mode(&mut reader, compress, validate)?,
+            c_1_vec: Vec::<C::H>::deserialize_with_mode(&mut reader, compress, validate)?,
+            c_2_vec: Vec::<C::G>::deserialize_with_mode(&mut reader, compress, validate)?,
+            c_3_vec: Vec::<C::H>::deserialize_with_mode(&mut reader, compress, validate)?,
+            c_4_vec: Vec::<C::H>::deserialize_with_mode(&mut reader, compress, validate)?,
         })
     }
 }
 
-impl Valid for Ciphertext {
+impl<C: Curve> Valid for Ciphertext<C> {
     fn check(&self) -> Result<(), ark_serialize::SerializationError> {
         self.c_1_vec.check()?;
         self.c_2_vec.check()?;
@@ -404,6 +500,75 @@ impl Valid for Ciphertext {
     }
 }
 
+impl<C: Curve> CanonicalSerialize for RetrievalKey<C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        mode: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        self.z.serialize_with_mode(writer, mode)
+    }
+
+    fn serialized_size(&self, mode: ark_serialize::Compress) -> usize {
+        self.z.serialized_size(mode)
+    }
+}
+
+impl<C: Curve> CanonicalDeserialize for RetrievalKey<C> {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        Ok(Self {
+            z: C::ScalarField::deserialize_with_mode(reader, compress, validate)?,
+        })
+    }
+}
+
+impl<C: Curve> Valid for RetrievalKey<C> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        self.z.check()
+    }
+}
+
+impl<C: Curve> CanonicalSerialize for TransformedCiphertext<C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        mode: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        self.c_0.serialize_with_mode(&mut writer, mode)?;
+        self.c_1.serialize_with_mode(&mut writer, mode)?;
+        Ok(())
+    }
+
+    fn serialized_size(&self, mode: ark_serialize::Compress) -> usize {
+        self.c_0.serialized_size(mode) + self.c_1.serialized_size(mode)
+    }
+}
+
+impl<C: Curve> CanonicalDeserialize for TransformedCiphertext<C> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        Ok(Self {
+            c_0: C::Gt::deserialize_with_mode(&mut reader, compress, validate)?,
+            c_1: C::Gt::deserialize_with_mode(&mut reader, compress, validate)?,
+        })
+    }
+}
+
+impl<C: Curve> Valid for TransformedCiphertext<C> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        self.c_0.check()?;
+        self.c_1.check()?;
+        Ok(())
+    }
+}
+
 // -----------------------------------------------------------------------------------------------
 // Serialization Of Full Keys
 // -----------------------------------------------------------------------------------------------
@@ -484,28 +649,153 @@ impl<T: Valid> Valid for FullKey<T> {
     }
 }
 
+// A transformation key is serialized like the user secret key it blinds.
+impl<C: Curve> CanonicalSerialize for TransformKey<C> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        mode: ark_serialize::Compress,
+    ) -> Result<(), ark_serialize::SerializationError> {
+        self.usk.serialize_with_mode(writer, mode)
+    }
+
+    fn serialized_size(&self, mode: ark_serialize::Compress) -> usize {
+        self.usk.serialized_size(mode)
+    }
+}
+
+impl<C: Curve> CanonicalDeserialize for TransformKey<C> {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: ark_serialize::Compress,
+        validate: ark_serialize::Validate,
+    ) -> Result<Self, ark_serialize::SerializationError> {
+        Ok(Self {
+            usk: USK::deserialize_with_mode(reader, compress, validate)?,
+        })
+    }
+}
+
+impl<C: Curve> Valid for TransformKey<C> {
+    fn check(&self) -> Result<(), ark_serialize::SerializationError> {
+        self.usk.check()
+    }
+}
+
 // -----------------------------------------------------------------------------------------------
 // Implementations
 // -----------------------------------------------------------------------------------------------
 
-impl PartialKey for PartialMSK {
+impl<C: Curve> PartialKey for PartialMSK<C> {
     fn get_authority(&self) -> String {
         self.auth.clone()
     }
 }
 
-impl PartialKey for PartialMPK {
+impl<C: Curve> PartialKey for PartialMPK<C> {
     fn get_authority(&self) -> String {
         self.auth.clone()
     }
 }
 
-impl PartialKey for PartialUSK {
+impl<C: Curve> PartialKey for PartialUSK<C> {
     fn get_authority(&self) -> String {
         self.auth.clone()
     }
 }
 
+impl<C: Curve> fmt::Debug for PartialMSK<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PartialMSK")
+            .field("auth", &self.auth)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: Curve> fmt::Debug for PartialUSK<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PartialUSK")
+            .field("auth", &self.auth)
+            .field("gid", &self.gid)
+            .field("num_attrs", &self.k_1_2_map.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C: Curve> Zeroize for PartialMSK<C> {
+    fn zeroize(&mut self) {
+        self.beta.zeroize();
+        self.b.zeroize();
+        self.b_not.zeroize();
+        self.b_prime.zeroize();
+    }
+}
+
+impl<C: Curve> Drop for PartialMSK<C> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<C: Curve> ZeroizeOnDrop for PartialMSK<C> {}
+
+impl<C: Curve> fmt::Debug for RetrievalKey<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetrievalKey").finish_non_exhaustive()
+    }
+}
+
+impl<C: Curve> Zeroize for RetrievalKey<C> {
+    fn zeroize(&mut self) {
+        self.z.zeroize();
+    }
+}
+
+impl<C: Curve> Drop for RetrievalKey<C> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<C: Curve> ZeroizeOnDrop for RetrievalKey<C> {}
+
+/// Wipes the values of a map, and then empties it. The keys are attribute
+/// labels and values, which are not secret.
+fn zeroize_map<K, V: Zeroize>(map: &mut HashMap<K, V>) {
+    for value in map.values_mut() {
+        value.zeroize();
+    }
+    map.clear();
+}
+
+impl<C: Curve> Zeroize for PartialUSK<C> {
+    fn zeroize(&mut self) {
+        self.k_1_1_vec.zeroize();
+        zeroize_map(&mut self.k_1_2_map);
+        zeroize_map(&mut self.k_2_map);
+        zeroize_map(&mut self.k_3_map);
+        self.k_4_vec.zeroize();
+        self.k_5_vec.zeroize();
+    }
+}
+
+impl<C: Curve> Drop for PartialUSK<C> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<C: Curve> ZeroizeOnDrop for PartialUSK<C> {}
+
+impl<T: Zeroize> Zeroize for FullKey<T> {
+    fn zeroize(&mut self) {
+        zeroize_map(&mut self.partial_keys);
+    }
+}
+
+// Dropping a full key drops, and therefore wipes, each of its partial keys.
+impl<T: ZeroizeOnDrop> ZeroizeOnDrop for FullKey<T> {}
+
 impl<T: PartialKey> FullKey<T> {
     pub fn new() -> Self {
         FullKey {
@@ -536,7 +826,9 @@ impl<T: PartialKey> Default for FullKey<T> {
     }
 }
 
-impl FullKey<PartialUSK> {
+impl<C: Curve> FullKey<PartialUSK<C>> {
+    /// Returns the attributes of the key, sorted by authority, label and
+    /// attribute, so that an `Iota` built from them is the same on every call.
     pub fn get_user_attributes(&self) -> Vec<UserAttribute> {
         let mut user_attrs = Vec::new();
         for (auth, usk) in self.partial_keys.iter() {
@@ -544,15 +836,212 @@ impl FullKey<PartialUSK> {
                 user_attrs.push(UserAttribute::new(auth, lbl, attr));
             }
         }
+        sort_user_attrs(&mut user_attrs);
 
         user_attrs
     }
+
+    /// # Description
+    ///
+    /// Checks that every partial key of the user secret key was issued for
+    /// `gid`, and verifies each of them with `PartialUSK::verify`.
+    ///
+    /// # Arguments
+    ///
+    /// * `mpk`: The master public key of the authorities that issued the key.
+    /// * `gid`: The global identifier the key is expected to be issued for.
+    /// * `user_attrs`: The attributes the key is expected to hold, in the order
+    ///   they were given to `keygen`.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the key is well-formed. Otherwise,
+    /// `Abe4Error::GidMismatch` if a partial key was issued for another GID,
+    /// `Abe4Error::MissingAuthorityKey` if `mpk` has no key for an authority
+    /// of the user secret key, or `Abe4Error::InvalidKey` if a partial key
+    /// does not verify.
+    pub fn verify(
+        &self,
+        mpk: &FullKey<PartialMPK<C>>,
+        gid: &str,
+        user_attrs: &[UserAttribute],
+    ) -> Result<()> {
+        check_gid(self, gid)?;
+        for user_attr in user_attrs {
+            if self.get_partial_key(user_attr.authority()).is_none() {
+                return Err(Abe4Error::InvalidKey(format!(
+                    "user secret key has no partial key for {user_attr:?}"
+                )));
+            }
+        }
+        for (auth, partial_usk) in self.partial_keys.iter() {
+            let partial_mpk = mpk
+                .get_partial_key(auth)
+                .ok_or_else(|| Abe4Error::MissingAuthorityKey(auth.clone()))?;
+            partial_usk.verify(partial_mpk, gid, user_attrs)?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: Curve> PartialUSK<C> {
+    /// # Description
+    ///
+    /// Checks that the partial user secret key is well-formed for `gid` and
+    /// `user_attrs`, using only the public key of the authority that issued
+    /// it.
+    ///
+    /// Each component of the key is checked with a pairing equation. For
+    /// `k_4[i] = h * r_i`, the key must hold
+    /// `e(k_1_1[i], h) = e(g, a) * e(H(gid), b) * e(b', k_4[i])`, and for each
+    /// attribute `lbl:x` with index `i`,
+    /// `e(k_1_2[lbl:x], h) = e(H(lbl, 0) + H(x) * H(lbl, 1), k_4[i])`. The
+    /// negated components `k_2`, `k_3` and `k_5` are checked likewise.
+    ///
+    /// # Arguments
+    ///
+    /// * `mpk`: The partial master public key of the authority.
+    /// * `gid`: The global identifier the key is expected to be issued for.
+    /// * `user_attrs`: The attributes the key is expected to hold, in the order
+    ///   they were given to `keygen_partial`. Attributes of other authorities
+    ///   are ignored.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the key is well-formed. Otherwise,
+    /// `Abe4Error::GidMismatch` if the key was issued for another GID, or
+    /// `Abe4Error::InvalidKey` naming the first component that does not
+    /// verify. The GID of a legacy key (see `PartialUSK`) is only checked by
+    /// the pairing equations.
+    pub fn verify(
+        &self,
+        mpk: &PartialMPK<C>,
+        gid: &str,
+        user_attrs: &[UserAttribute],
+    ) -> Result<()> {
+        let invalid = |reason: String| Abe4Error::InvalidKey(format!("{}: {reason}", self.auth));
+        if mpk.auth != self.auth {
+            return Err(invalid(format!(
+                "key was issued by another authority than '{}'",
+                mpk.auth
+            )));
+        }
+        if !self.gid.is_empty() && self.gid != gid {
+            return Err(Abe4Error::GidMismatch {
+                expected: gid.to_string(),
+                found: self.gid.clone(),
+            });
+        }
+
+        // The key must hold exactly the expected attributes, and the indices
+        // that `keygen_partial` gave them.
+        let user_attrs: Vec<UserAttribute> = user_attrs
+            .iter()
+            .filter(|ua| ua.authority() == self.auth)
+            .cloned()
+            .collect();
+        let expected: HashSet<(&str, &str)> = user_attrs
+            .iter()
+            .map(|ua| (ua.label(), ua.attribute()))
+            .collect();
+        let found: HashSet<(&str, &str)> = self
+            .k_1_2_map
+            .keys()
+            .map(|(lbl, attr)| (lbl.as_str(), attr.as_str()))
+            .collect();
+        if expected != found {
+            return Err(invalid(format!(
+                "key holds {} attributes, but {} were expected",
+                found.len(),
+                expected.len()
+            )));
+        }
+        let labels: HashSet<&str> = user_attrs.iter().map(|ua| ua.label()).collect();
+        if self.k_3_map.len() != found.len()
+            || self.k_2_map.len() != labels.len()
+            || labels.iter().any(|lbl| !self.k_2_map.contains_key(*lbl))
+        {
+            return Err(invalid(String::from(
+                "negated components do not match the attributes",
+            )));
+        }
+        let iota = Iota::new(&user_attrs);
+        let n = self.k_1_1_vec.len();
+        if n <= iota.get_max() || self.k_4_vec.len() != n || self.k_5_vec.len() != n {
+            return Err(invalid(format!(
+                "key has {n} k_1_1, {} k_4 and {} k_5 components",
+                self.k_4_vec.len(),
+                self.k_5_vec.len()
+            )));
+        }
+
+        let hashing = HashingContext::<C>::new();
+        let g = C::G::generator();
+        let h = C::H::generator();
+        let gid_hashed: C::G = hashing.hash_gid(gid).into();
+        for (i, (k_1_1, k_4)) in self.k_1_1_vec.iter().zip(&self.k_4_vec).enumerate() {
+            let check = C::multi_pairing(
+                vec![k_1_1.neg(), g, gid_hashed, mpk.b_prime],
+                vec![h, mpk.a, mpk.b, *k_4],
+            );
+            if !check.is_one() {
+                return Err(invalid(format!("k_1_1[{i}] does not verify")));
+            }
+        }
+
+        let mut k_5_by_lbl: HashMap<&str, C::H> = HashMap::new();
+        for ((lbl, attr), k_1_2) in self.k_1_2_map.iter() {
+            let i = iota.get(&self.auth, lbl, attr)?;
+            let x_attr = hashing.hash_attr(attr);
+            let lbl_pos_0: C::G = hashing.hash_lbl(&self.auth, lbl, Pos, 0).into();
+            let lbl_pos_1: C::G = hashing.hash_lbl(&self.auth, lbl, Pos, 1).into();
+            let check = C::multi_pairing(
+                vec![k_1_2.neg(), lbl_pos_0 + lbl_pos_1 * x_attr],
+                vec![h, self.k_4_vec[i]],
+            );
+            if !check.is_one() {
+                return Err(invalid(format!("k_1_2[{lbl}:{attr}] does not verify")));
+            }
+
+            let k_3 = self
+                .k_3_map
+                .get(&(lbl.clone(), attr.clone()))
+                .ok_or_else(|| invalid(format!("key has no k_3 component for {lbl}:{attr}")))?;
+            let lbl_neg_0: C::G = hashing.hash_lbl(&self.auth, lbl, Neg, 0).into();
+            let lbl_neg_1: C::G = hashing.hash_lbl(&self.auth, lbl, Neg, 1).into();
+            let check = C::multi_pairing(
+                vec![k_3.neg(), lbl_neg_0 + lbl_neg_1 * x_attr],
+                vec![h, self.k_5_vec[i]],
+            );
+            if !check.is_one() {
+                return Err(invalid(format!("k_3[{lbl}:{attr}] does not verify")));
+            }
+            *k_5_by_lbl.entry(lbl).or_default() += self.k_5_vec[i];
+        }
+
+        // `k_2[lbl]` is randomised by the sum of the `r_not` of all the
+        // attributes of the label.
+        for (lbl, k_5) in k_5_by_lbl {
+            let lbl_neg_1: C::G = hashing.hash_lbl(&self.auth, lbl, Neg, 1).into();
+            let check = C::multi_pairing(
+                vec![self.k_2_map[lbl].neg(), g, gid_hashed, lbl_neg_1],
+                vec![h, mpk.a, mpk.b_not, k_5],
+            );
+            if !check.is_one() {
+                return Err(invalid(format!("k_2[{lbl}] does not verify")));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::scheme::{iota::Iota, keygen, setup};
+    use crate::{
+        curve::ScalarField,
+        scheme::{iota::Iota, keygen, keygen_partial, setup},
+    };
     use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
     use ark_std::test_rng;
 
@@ -598,7 +1087,8 @@ mod tests {
             let mut key_bytes = vec![0; key_len as usize];
             reader.read_exact(&mut key_bytes).unwrap();
 
-            let partial_key = PartialMPK::deserialize_compressed(&key_bytes[..]).unwrap();
+            let partial_key: PartialMPK =
+                PartialMPK::deserialize_compressed(&key_bytes[..]).unwrap();
             assert_eq!(auth, partial_key.get_authority());
         }
     }
@@ -613,7 +1103,7 @@ mod tests {
             UserAttribute::new("B", "L2", "A2"),
         ];
         let iota = Iota::new(&user_attrs);
-        let usk = keygen(&mut rng, "gid", &msk, &user_attrs, &iota);
+        let usk = keygen(&mut rng, "gid", &msk, &user_attrs, &iota).unwrap();
 
         let mut usk_bytes = Vec::new();
         usk.serialize_compressed(&mut usk_bytes).unwrap();
@@ -622,4 +1112,122 @@ mod tests {
 
         assert_eq!(usk, usk_deserialized);
     }
+
+    #[test]
+    fn test_secret_keys_zeroize() {
+        let mut rng = test_rng();
+        let auths = vec!["A"];
+        let (mut msk, _mpk) = setup(&mut rng, &auths);
+        let user_attrs = vec![UserAttribute::new("A", "L1", "A1")];
+        let iota = Iota::new(&user_attrs);
+        let mut usk = keygen(&mut rng, "gid", &msk, &user_attrs, &iota).unwrap();
+
+        // Debug output must not leak any key material.
+        let msk_a = msk.get_partial_key("A").unwrap();
+        let msk_debug = format!("{msk:?}");
+        assert!(msk_debug.contains("PartialMSK"));
+        assert!(!msk_debug.contains(&format!("{:?}", msk_a.beta)));
+        let usk_a = usk.get_partial_key("A").unwrap();
+        let usk_debug = format!("{usk:?}");
+        assert!(usk_debug.contains("PartialUSK"));
+        assert!(!usk_debug.contains(&format!("{:?}", usk_a.k_4_vec[0])));
+
+        let msk_a = msk.partial_keys.get_mut("A").unwrap();
+        msk_a.zeroize();
+        assert_eq!(msk_a.beta, ScalarField::from(0u64));
+        assert_eq!(msk_a.b_prime, ScalarField::from(0u64));
+
+        let usk_a = usk.partial_keys.get_mut("A").unwrap();
+        usk_a.zeroize();
+        assert!(usk_a.k_1_1_vec.is_empty());
+        assert!(usk_a.k_1_2_map.is_empty());
+
+        msk.zeroize();
+        usk.zeroize();
+        assert!(msk.partial_keys.is_empty());
+        assert!(usk.partial_keys.is_empty());
+    }
+
+    #[test]
+    fn test_usk_verify() {
+        let mut rng = test_rng();
+        let auths = vec!["A", "B"];
+        let (msk, mpk) = setup(&mut rng, &auths);
+        let user_attrs = vec![
+            UserAttribute::new("A", "L1", "A1"),
+            UserAttribute::new("A", "L1", "A2"),
+            UserAttribute::new("A", "L2", "A3"),
+            UserAttribute::new("B", "L1", "A1"),
+        ];
+        let iota = Iota::new(&user_attrs);
+        let mut usk = keygen(&mut rng, "gid", &msk, &user_attrs, &iota).unwrap();
+        usk.verify(&mpk, "gid", &user_attrs).unwrap();
+        let partial_mpk = mpk.get_partial_key("A").unwrap();
+        let partial_usk = usk.get_partial_key("A").unwrap();
+        partial_usk.verify(partial_mpk, "gid", &user_attrs).unwrap();
+
+        assert!(matches!(
+            usk.verify(&mpk, "other", &user_attrs),
+            Err(Abe4Error::GidMismatch { .. })
+        ));
+        // The key does not hold the attributes it is checked against.
+        assert!(matches!(
+            partial_usk.verify(partial_mpk, "gid", &user_attrs[..2]),
+            Err(Abe4Error::InvalidKey(_))
+        ));
+        assert!(matches!(
+            usk.verify(&mpk, "gid", &[UserAttribute::new("C", "L1", "A1")]),
+            Err(Abe4Error::InvalidKey(_))
+        ));
+        // The key was not issued by the authority of the MPK.
+        let (_, other_mpk) = setup(&mut rng, &auths);
+        assert!(matches!(
+            usk.verify(&other_mpk, "gid", &user_attrs),
+            Err(Abe4Error::InvalidKey(_))
+        ));
+
+        // Tampering with any component is caught.
+        let usk_a = usk.partial_keys.get_mut("A").unwrap();
+        let key = (String::from("L1"), String::from("A2"));
+        let k_3 = usk_a.k_3_map[&key];
+        *usk_a.k_3_map.get_mut(&key).unwrap() = k_3.double();
+        assert!(matches!(
+            usk.verify(&mpk, "gid", &user_attrs),
+            Err(Abe4Error::InvalidKey(reason)) if reason.contains("k_3[L1:A2]")
+        ));
+        let usk_a = usk.partial_keys.get_mut("A").unwrap();
+        *usk_a.k_3_map.get_mut(&key).unwrap() = k_3;
+        usk_a.k_4_vec.swap(0, 1);
+        assert!(usk.verify(&mpk, "gid", &user_attrs).is_err());
+
+        // Partial keys issued for different GIDs do not form a valid key.
+        let mut mixed = USK::new();
+        let ua_a = [&user_attrs[0]];
+        let ua_b = [&user_attrs[3]];
+        mixed.add_partial_key(
+            keygen_partial(
+                &mut rng,
+                "gid",
+                msk.get_partial_key("A").unwrap(),
+                &ua_a,
+                &iota,
+            )
+            .unwrap(),
+        );
+        mixed.add_partial_key(
+            keygen_partial(
+                &mut rng,
+                "other",
+                msk.get_partial_key("B").unwrap(),
+                &ua_b,
+                &iota,
+            )
+            .unwrap(),
+        );
+        let mixed_attrs = [user_attrs[0].clone(), user_attrs[3].clone()];
+        assert!(matches!(
+            mixed.verify(&mpk, "gid", &mixed_attrs),
+            Err(Abe4Error::GidMismatch { .. })
+        ));
+    }
 }
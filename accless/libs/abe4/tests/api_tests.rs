@@ -1,10 +1,36 @@
 use abe4::{
-    Gt, Policy, UserAttribute, decrypt, decrypt_hybrid, encrypt, encrypt_hybrid, iota::Iota,
-    keygen, setup, tau::Tau,
+    Abe4Error, CipherSuite, Curve, DefaultCurve, Gt, HybridContainer, HybridDecryptReader,
+    HybridEncryptWriter, Policy, PreparedMpk, PreparedPolicy, PreparedUsk, STREAM_SEGMENT_SIZE,
+    UserAttribute,
+    curve::{Bls12_377, Bls12_381Flipped},
+    decrypt, decrypt_abe4, decrypt_hybrid, decrypt_hybrid_container, decrypt_hybrid_many,
+    decrypt_hybrid_transformed, decrypt_many, decrypt_prepared, decrypt_transformed, delegate,
+    delegate_usk_abe4, encrypt, encrypt_abe4, encrypt_hybrid, encrypt_hybrid_container,
+    encrypt_hybrid_many, encrypt_hybrid_with_suite, encrypt_hybrid_wrapped, encrypt_many,
+    encrypt_prepared, epoch_gid,
+    error::Result,
+    free_string,
+    iota::Iota,
+    keygen, keygen_abe4, keygen_with_epoch, reencapsulate_hybrid,
+    scheme::{
+        self,
+        types::{Ciphertext, MPK, PartialUSK, TransformKey, TransformedCiphertext, USK},
+    },
+    setup, setup_abe4, setup_with_curve,
+    tau::Tau,
+    threshold::{
+        DkgCommitment, DkgParams, DkgShare, ThresholdMpk, combine_usk_shares, dkg_deal,
+        dkg_finalize, keygen_share,
+    },
+    transform, transform_hybrid, transform_keygen,
 };
-use anyhow::Result;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::rand::{SeedableRng, rngs::StdRng};
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    ffi::{CStr, CString, c_char},
+    io::{Read, Write},
+};
 
 const USER_ID: &str = "TEST_USER_ID";
 
@@ -29,27 +55,31 @@ fn prepare_test(user_attrs: &Vec<&str>, policy: &str) -> (Vec<String>, Vec<UserA
     (auths.into_iter().collect(), user_attrs, policy)
 }
 
-fn test_scheme(user_attrs: Vec<&str>, policy: &str) -> (Gt, Option<Gt>) {
+fn test_scheme(user_attrs: Vec<&str>, policy: &str) -> (Gt, Result<Gt>) {
+    test_scheme_with_curve::<DefaultCurve>(user_attrs, policy)
+}
+
+fn test_scheme_with_curve<C: Curve>(user_attrs: Vec<&str>, policy: &str) -> (C::Gt, Result<C::Gt>) {
     let (auths, user_attrs, policy) = prepare_test(&user_attrs, &policy);
     let mut rng = ark_std::test_rng();
     let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
     let iota = Iota::new(&user_attrs);
-    let (msk, mpk) = setup(&mut rng, &auths);
-    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota);
+    let (msk, mpk) = setup_with_curve::<C>(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
     let tau = Tau::new(&policy);
-    let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau);
+    let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
     let k_dec = decrypt(&usk, USER_ID, &iota, &tau, &policy, &ct);
     (k_enc, k_dec)
 }
 
 pub fn assert_decryption_ok(user_attrs: Vec<&str>, policy: &str) {
     let (k_enc, k_dec) = test_scheme(user_attrs, policy);
-    assert!(k_dec.is_some_and(|k| Gt::eq(&k_enc, &k)));
+    assert!(k_dec.is_ok_and(|k| Gt::eq(&k_enc, &k)));
 }
 
 pub fn assert_decryption_fail(user_attrs: Vec<&str>, policy: &str) {
     let (_, k_dec) = test_scheme(user_attrs, policy);
-    assert!(k_dec.is_none());
+    assert!(matches!(k_dec, Err(Abe4Error::UnsatisfiedPolicy { .. })));
 }
 
 fn test_hybrid_scheme(
@@ -63,7 +93,7 @@ fn test_hybrid_scheme(
     let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
     let iota = Iota::new(&user_attrs);
     let (msk, mpk) = setup(&mut rng, &auths);
-    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
 
     let hybrid_ct = encrypt_hybrid(&mut rng, &mpk, &policy, plaintext, aad)?;
     decrypt_hybrid(
@@ -87,6 +117,914 @@ fn hybrid_round_trip_ok() {
     assert_eq!(plaintext, decrypted.as_slice());
 }
 
+#[test]
+fn scheme_quoted_values_ok() {
+    assert_decryption_ok(
+        vec![r#"corp.email:"alice@example.com""#, r#"k8s.ns:"team-é""#],
+        r#"corp.email:"alice@example.com" & !k8s.ns:"team-a""#,
+    );
+    assert_decryption_fail(
+        vec![r#"corp.email:"alice@example.com""#, r#"k8s.ns:"team-a""#],
+        r#"corp.email:"alice@example.com" & !k8s.ns:"team-a""#,
+    );
+}
+
+fn decrypt_numeric(microcode: u32, epoch: u32, policy: &str) -> Result<Gt> {
+    let policy = Policy::parse(policy).unwrap();
+    let mut user_attrs = UserAttribute::numeric("snp", "microcode", microcode);
+    user_attrs.extend(UserAttribute::numeric("as", "epoch", epoch));
+    let mut rng = ark_std::test_rng();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &vec!["snp", "as"]);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+    let tau = Tau::new(&policy);
+    let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+    decrypt(&usk, USER_ID, &iota, &tau, &policy, &ct).inspect(|k_dec| assert_eq!(&k_enc, k_dec))
+}
+
+#[test]
+fn scheme_numeric_comparisons() {
+    let policy = "snp.microcode >= 0xd1 & as.epoch > 42";
+    assert!(decrypt_numeric(0xd1, 43, policy).is_ok());
+    assert!(decrypt_numeric(0xff, 1000, policy).is_ok());
+    assert!(matches!(
+        decrypt_numeric(0xd0, 43, policy),
+        Err(Abe4Error::UnsatisfiedPolicy { .. })
+    ));
+    assert!(matches!(
+        decrypt_numeric(0xd1, 42, policy),
+        Err(Abe4Error::UnsatisfiedPolicy { .. })
+    ));
+
+    // Ranges.
+    let policy = "as.epoch >= 10 & as.epoch < 20 & snp.microcode <= 0xd1";
+    assert!(decrypt_numeric(0xd1, 10, policy).is_ok());
+    assert!(decrypt_numeric(0, 19, policy).is_ok());
+    assert!(decrypt_numeric(0xd1, 20, policy).is_err());
+    assert!(decrypt_numeric(0xd2, 15, policy).is_err());
+}
+
+#[test]
+fn scheme_numeric_one_value_per_key() {
+    // The bits of 3 (011) and 4 (100) together would satisfy any comparison
+    // over 0..7, such as `snp.microcode == 5`.
+    let mut user_attrs = UserAttribute::numeric("snp", "microcode", 3);
+    user_attrs.extend(UserAttribute::numeric("snp", "microcode", 4));
+    let mut rng = ark_std::test_rng();
+    let iota = Iota::new(&user_attrs);
+    let (msk, _) = setup(&mut rng, &vec!["snp"]);
+    assert!(matches!(
+        keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota),
+        Err(Abe4Error::InvalidInput(_))
+    ));
+
+    // Repeating the same value is harmless.
+    let mut user_attrs = UserAttribute::numeric("snp", "microcode", 3);
+    user_attrs.extend(UserAttribute::numeric("snp", "microcode", 3));
+    let iota = Iota::new(&user_attrs);
+    assert!(keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).is_ok());
+}
+
+#[test]
+fn scheme_verify_partial_usk() {
+    let mut rng = ark_std::test_rng();
+    let (partial_msk, partial_mpk) = scheme::setup_partial(&mut rng, "A");
+    let user_attrs = vec![
+        UserAttribute::new("A", "wf", "foo"),
+        UserAttribute::new("A", "node", "n0"),
+        UserAttribute::new("A", "node", "n1"),
+    ];
+    let user_attrs_refs: Vec<&UserAttribute> = user_attrs.iter().collect();
+    let iota = Iota::new(&user_attrs);
+    let partial_usk =
+        scheme::keygen_partial(&mut rng, USER_ID, &partial_msk, &user_attrs_refs, &iota).unwrap();
+
+    // The recipient only holds the serialized key and the authority's MPK.
+    let mut partial_usk_bytes = Vec::new();
+    partial_usk
+        .serialize_compressed(&mut partial_usk_bytes)
+        .unwrap();
+    let partial_usk = PartialUSK::deserialize_compressed(&partial_usk_bytes[..]).unwrap();
+    partial_usk
+        .verify(&partial_mpk, USER_ID, &user_attrs)
+        .unwrap();
+    assert!(matches!(
+        partial_usk.verify(&partial_mpk, "other", &user_attrs),
+        Err(Abe4Error::GidMismatch { .. })
+    ));
+    let (_, other_mpk) = scheme::setup_partial(&mut rng, "A");
+    assert!(matches!(
+        partial_usk.verify(&other_mpk, USER_ID, &user_attrs),
+        Err(Abe4Error::InvalidKey(_))
+    ));
+
+    // A key that verifies decrypts.
+    let mut usk = USK::new();
+    usk.add_partial_key(partial_usk);
+    let mut mpk = MPK::new();
+    mpk.add_partial_key(partial_mpk);
+    usk.verify(&mpk, USER_ID, &user_attrs).unwrap();
+    let policy = Policy::parse("A.wf:foo & A.node:n1").unwrap();
+    let tau = Tau::new(&policy);
+    let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+    let k_dec = decrypt(&usk, USER_ID, &iota, &tau, &policy, &ct).unwrap();
+    assert_eq!(k_enc, k_dec);
+}
+
+#[test]
+fn scheme_partial_usk_legacy_format() {
+    let mut rng = ark_std::test_rng();
+    let (partial_msk, partial_mpk) = scheme::setup_partial(&mut rng, "A");
+    let user_attrs = vec![UserAttribute::new("A", "wf", "foo")];
+    let user_attrs_refs: Vec<&UserAttribute> = user_attrs.iter().collect();
+    let iota = Iota::new(&user_attrs);
+    let partial_usk =
+        scheme::keygen_partial(&mut rng, USER_ID, &partial_msk, &user_attrs_refs, &iota).unwrap();
+    let mut bytes = Vec::new();
+    partial_usk.serialize_compressed(&mut bytes).unwrap();
+
+    // The legacy format has no version marker, version byte or GID.
+    let auth_end = 9 + 8 + "A".len();
+    let gid_end = auth_end + 8 + USER_ID.len();
+    let mut legacy = bytes[9..auth_end].to_vec();
+    legacy.extend_from_slice(&bytes[gid_end..]);
+    let legacy_usk = PartialUSK::deserialize_compressed(&legacy[..]).unwrap();
+    assert_eq!(legacy_usk.auth, "A");
+    assert_eq!(legacy_usk.gid, "");
+    legacy_usk
+        .verify(&partial_mpk, USER_ID, &user_attrs)
+        .unwrap();
+    assert!(matches!(
+        legacy_usk.verify(&partial_mpk, "other", &user_attrs),
+        Err(Abe4Error::InvalidKey(_))
+    ));
+
+    let mut usk = USK::new();
+    usk.add_partial_key(legacy_usk);
+    let mut mpk = MPK::new();
+    mpk.add_partial_key(partial_mpk);
+    let policy = Policy::parse("A.wf:foo").unwrap();
+    let tau = Tau::new(&policy);
+    let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+    let k_dec = decrypt(&usk, USER_ID, &iota, &tau, &policy, &ct).unwrap();
+    assert_eq!(k_enc, k_dec);
+
+    // Unknown versions are rejected.
+    let mut bad_version = bytes.clone();
+    bad_version[8] = 2;
+    assert!(PartialUSK::<DefaultCurve>::deserialize_compressed(&bad_version[..]).is_err());
+}
+
+#[test]
+fn scheme_threshold_keygen() {
+    let mut rng = ark_std::test_rng();
+    let params = DkgParams::new("A", 2, 3).unwrap();
+    assert!(DkgParams::new("A", 4, 3).is_err());
+
+    // Every replica deals, and the messages go through the wire.
+    let mut commitments: Vec<DkgCommitment> = Vec::new();
+    let mut shares: Vec<Vec<DkgShare>> = (0..3).map(|_| Vec::new()).collect();
+    for dealer in 1..=3 {
+        let (commitment, dealt): (DkgCommitment, Vec<DkgShare>) =
+            dkg_deal(&mut rng, &params, dealer).unwrap();
+        let mut bytes = Vec::new();
+        commitment.serialize_compressed(&mut bytes).unwrap();
+        commitments.push(DkgCommitment::deserialize_compressed(&bytes[..]).unwrap());
+        for share in dealt {
+            let mut bytes = Vec::new();
+            share.serialize_compressed(&mut bytes).unwrap();
+            shares[share.recipient as usize - 1]
+                .push(DkgShare::deserialize_compressed(&bytes[..]).unwrap());
+        }
+    }
+    let (msk_shares, tmpks): (Vec<_>, Vec<ThresholdMpk>) = (1..=3)
+        .map(|index| {
+            dkg_finalize(&params, index, &commitments, &shares[index as usize - 1]).unwrap()
+        })
+        .unzip();
+    assert!(tmpks.iter().all(|tmpk| *tmpk == tmpks[0]));
+    let tmpk = &tmpks[0];
+
+    // A share that does not match its commitment is detected.
+    shares[1].swap(0, 1);
+    shares[1][0].dealer = 1;
+    shares[1][1].dealer = 2;
+    assert!(matches!(
+        dkg_finalize(&params, 2, &commitments, &shares[1]),
+        Err(Abe4Error::InvalidKey(_))
+    ));
+
+    // Any two replicas issue a key that works with the MPK of the authority,
+    // alongside the keys of regular authorities.
+    let user_attrs = vec![
+        UserAttribute::new("A", "wf", "foo"),
+        UserAttribute::new("A", "node", "n0"),
+        UserAttribute::new("B", "c", "0"),
+    ];
+    let iota = Iota::new(&user_attrs);
+    let a_attrs: Vec<&UserAttribute> = user_attrs[..2].iter().collect();
+    let (b_msk, b_mpk) = scheme::setup_partial(&mut rng, "B");
+    let mut mpk = MPK::new();
+    mpk.add_partial_key(tmpk.mpk());
+    mpk.add_partial_key(b_mpk);
+    let policy = Policy::parse("A.wf:foo & !A.node:n1 & B.c:0").unwrap();
+    let tau = Tau::new(&policy);
+    let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+    for replicas in [[0, 1], [0, 2], [1, 2]] {
+        let usk_shares: Vec<_> = replicas
+            .iter()
+            .map(|r| {
+                keygen_share(&mut rng, USER_ID, tmpk, &msk_shares[*r], &a_attrs, &iota).unwrap()
+            })
+            .collect();
+        for share in &usk_shares {
+            share
+                .usk
+                .verify(&tmpk.replica_mpk(share.index), USER_ID, &user_attrs)
+                .unwrap();
+        }
+        let partial_usk = combine_usk_shares(tmpk, &usk_shares).unwrap();
+        partial_usk
+            .verify(&tmpk.mpk(), USER_ID, &user_attrs)
+            .unwrap();
+
+        let mut usk = USK::new();
+        usk.add_partial_key(partial_usk);
+        let b_attrs: Vec<&UserAttribute> = user_attrs[2..].iter().collect();
+        usk.add_partial_key(
+            scheme::keygen_partial(&mut rng, USER_ID, &b_msk, &b_attrs, &iota).unwrap(),
+        );
+        let k_dec = decrypt(&usk, USER_ID, &iota, &tau, &policy, &ct).unwrap();
+        assert_eq!(k_enc, k_dec);
+    }
+
+    // The outcome of a DKG needs commitments, all for the same authority.
+    let empty: Result<ThresholdMpk> = ThresholdMpk::new(Vec::new());
+    assert!(matches!(empty, Err(Abe4Error::InvalidInput(_))));
+    let (_, c_mpk) = scheme::setup_partial(&mut rng, "C");
+    assert!(matches!(
+        ThresholdMpk::new(vec![tmpk.mpk(), c_mpk]),
+        Err(Abe4Error::InvalidInput(_))
+    ));
+
+    // One replica is not enough.
+    let usk_share = keygen_share(&mut rng, USER_ID, tmpk, &msk_shares[0], &a_attrs, &iota).unwrap();
+    assert!(matches!(
+        combine_usk_shares(tmpk, &[usk_share]),
+        Err(Abe4Error::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn scheme_prepared_keys_match_unprepared() {
+    let (auths, user_attrs, _) = prepare_test(&vec!["A.a:0", "A.b:1", "B.c:0"], "A.a:0");
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let mut rng = ark_std::test_rng();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+    let mpk = PreparedMpk::new(mpk);
+    let usk = PreparedUsk::new(usk, USER_ID).unwrap();
+
+    // Encrypt several times under the same labels, so that later encryptions
+    // hit the memoised points.
+    for policy in [
+        "A.a:0 & B.c:0",
+        "A.a:0 & !A.b:0",
+        "B.c:0 | A.a:1",
+        "2of(A.a:0, A.b:1, B.c:1)",
+    ] {
+        let policy = Policy::parse(policy).unwrap();
+        let tau = Tau::new(&policy);
+        let (k_enc, ct) = encrypt_prepared(StdRng::seed_from_u64(7), &mpk, &policy, &tau).unwrap();
+        let (k_ref, ct_ref) = encrypt(StdRng::seed_from_u64(7), mpk.mpk(), &policy, &tau).unwrap();
+        assert_eq!(k_enc, k_ref);
+        assert_eq!(ct, ct_ref);
+
+        let k_dec = decrypt_prepared(&usk, &iota, &tau, &policy, &ct).unwrap();
+        assert_eq!(k_dec, k_enc);
+        let k_ref = decrypt(usk.usk(), USER_ID, &iota, &tau, &policy, &ct).unwrap();
+        assert_eq!(k_dec, k_ref);
+    }
+
+    let policy = Policy::parse("A.a:1").unwrap();
+    let tau = Tau::new(&policy);
+    let (_, ct) = encrypt_prepared(&mut rng, &mpk, &policy, &tau).unwrap();
+    assert!(matches!(
+        decrypt_prepared(&usk, &iota, &tau, &policy, &ct),
+        Err(Abe4Error::UnsatisfiedPolicy { .. })
+    ));
+
+    // Keys are bound to the GID they were issued for.
+    assert!(matches!(
+        PreparedUsk::new(usk.into_inner(), "OTHER_USER_ID"),
+        Err(Abe4Error::GidMismatch { .. })
+    ));
+}
+
+#[test]
+fn scheme_many_matches_one_by_one() {
+    let user_attrs = vec!["A.a:0", "A.b:1", "B.c:0"];
+    let policy = "A.a:0 & (!A.b:0 | B.c:1)";
+    let (auths, user_attrs, policy) = prepare_test(&user_attrs, policy);
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let mut rng = ark_std::test_rng();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+    let mpk = PreparedMpk::new(mpk);
+    let usk = PreparedUsk::new(usk, USER_ID).unwrap();
+    let tau = Tau::new(&policy);
+    let policy = PreparedPolicy::new(&mpk, policy).unwrap();
+
+    // A batch draws the same randomness as one-by-one encryptions.
+    let many = encrypt_many(StdRng::seed_from_u64(3), &mpk, &policy, 3).unwrap();
+    let mut rng = StdRng::seed_from_u64(3);
+    for (k, ct) in many.iter() {
+        let (k_ref, ct_ref) = encrypt(&mut rng, mpk.mpk(), policy.policy(), &tau).unwrap();
+        assert_eq!(*k, k_ref);
+        assert_eq!(*ct, ct_ref);
+    }
+
+    let cts: Vec<Ciphertext> = many.iter().map(|(_, ct)| ct.clone()).collect();
+    let keys = decrypt_many(&usk, &policy, &cts).unwrap();
+    assert_eq!(keys.len(), 3);
+    for ((k_enc, ct), k_dec) in many.iter().zip(keys) {
+        assert_eq!(*k_enc, k_dec);
+        let k_ref = decrypt(usk.usk(), USER_ID, usk.iota(), &tau, policy.policy(), ct).unwrap();
+        assert_eq!(k_dec, k_ref);
+    }
+    assert!(decrypt_many(&usk, &policy, &[]).unwrap().is_empty());
+}
+
+#[test]
+fn hybrid_many_round_trip() {
+    let (auths, user_attrs, policy) = prepare_test(&vec!["A.a:0", "B.b:1"], "A.a:0 & B.b:1");
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let mut rng = StdRng::seed_from_u64(0);
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+    let other_usk = keygen(&mut rng, USER_ID, &msk, &user_attrs[..1], &iota).unwrap();
+    let mpk = PreparedMpk::new(mpk);
+    let policy = PreparedPolicy::new(&mpk, policy).unwrap();
+
+    let plaintexts: Vec<Vec<u8>> = (0..8).map(|i| format!("record {i}").into_bytes()).collect();
+    let plaintexts: Vec<&[u8]> = plaintexts.iter().map(|p| p.as_slice()).collect();
+    let cts = encrypt_hybrid_many(
+        &mut rng,
+        &mpk,
+        &policy,
+        CipherSuite::ChaCha20Poly1305,
+        &plaintexts,
+        b"aad",
+    )
+    .unwrap();
+    assert_eq!(cts.len(), plaintexts.len());
+
+    // Batch ciphertexts are regular hybrid ciphertexts.
+    let decrypted = decrypt_hybrid(
+        &usk,
+        USER_ID,
+        policy.policy(),
+        &cts[5].abe_ct,
+        &cts[5].sym_ct,
+        b"aad",
+    )
+    .unwrap();
+    assert_eq!(decrypted, plaintexts[5]);
+
+    let usk = PreparedUsk::new(usk, USER_ID).unwrap();
+    let decrypted = decrypt_hybrid_many(&usk, &policy, &cts, b"aad").unwrap();
+    assert_eq!(decrypted, plaintexts);
+    assert!(decrypt_hybrid_many(&usk, &policy, &cts, b"other aad").is_err());
+
+    let other_usk = PreparedUsk::new(other_usk, USER_ID).unwrap();
+    assert!(matches!(
+        decrypt_hybrid_many(&other_usk, &policy, &cts, b"aad"),
+        Err(Abe4Error::UnsatisfiedPolicy { .. })
+    ));
+}
+
+#[test]
+fn hybrid_epoch_revocation() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let (msk, mpk) = setup(&mut rng, &vec!["A"]);
+    let attrs = vec![
+        UserAttribute::new("A", "wf", "w1"),
+        UserAttribute::new("A", "node", "n1"),
+    ];
+    let node_usk = keygen_with_epoch(&mut rng, USER_ID, &msk, &attrs, 1).unwrap();
+    let node_gid = epoch_gid(USER_ID, 1);
+    node_usk
+        .verify(&mpk, &node_gid, &UserAttribute::with_epoch(&attrs, 1))
+        .unwrap();
+    let base_policy = "A.wf:w1 & A.node:n1";
+    let policy = Policy::parse(base_policy).unwrap().require_epoch(1);
+
+    let suite = CipherSuite::Aes256Gcm;
+    let ct = encrypt_hybrid_wrapped(&mut rng, &mpk, &policy, suite, b"payload", b"aad").unwrap();
+    assert_eq!(ct.suite().unwrap(), suite);
+    let decrypted = decrypt_hybrid(
+        &node_usk, &node_gid, &policy, &ct.abe_ct, &ct.sym_ct, b"aad",
+    )
+    .unwrap();
+    assert_eq!(decrypted, b"payload");
+
+    // The node's key is compromised: the owner moves the ciphertext to epoch 2.
+    let owner_usk = keygen_with_epoch(&mut rng, "owner", &msk, &attrs, 2).unwrap();
+    let owner_usk = PreparedUsk::new(owner_usk, &epoch_gid("owner", 2)).unwrap();
+    let new_policy = Policy::parse(base_policy).unwrap().require_epoch(2);
+    let new_ct = reencapsulate_hybrid(
+        &mut rng,
+        &owner_usk,
+        &mpk,
+        &policy,
+        &ct,
+        &new_policy,
+        b"aad",
+    )
+    .unwrap();
+
+    // Only the wrapped key changes: suite | version | nonce | AES-256 key | tag.
+    let header_len = 2 + 12 + 32 + 16;
+    assert_ne!(ct.sym_ct[..header_len], new_ct.sym_ct[..header_len]);
+    assert_eq!(ct.sym_ct[header_len..], new_ct.sym_ct[header_len..]);
+
+    assert!(matches!(
+        decrypt_hybrid(
+            &node_usk,
+            &node_gid,
+            &new_policy,
+            &new_ct.abe_ct,
+            &new_ct.sym_ct,
+            b"aad"
+        ),
+        Err(Abe4Error::UnsatisfiedPolicy { .. })
+    ));
+    let node_usk = keygen_with_epoch(&mut rng, USER_ID, &msk, &attrs, 2).unwrap();
+    let node_gid = epoch_gid(USER_ID, 2);
+    let decrypted = decrypt_hybrid(
+        &node_usk,
+        &node_gid,
+        &new_policy,
+        &new_ct.abe_ct,
+        &new_ct.sym_ct,
+        b"aad",
+    )
+    .unwrap();
+    assert_eq!(decrypted, b"payload");
+
+    // The AAD stays bound to the re-encapsulated ciphertext.
+    assert!(
+        decrypt_hybrid(
+            &node_usk,
+            &node_gid,
+            &new_policy,
+            &new_ct.abe_ct,
+            &new_ct.sym_ct,
+            b""
+        )
+        .is_err()
+    );
+    // Ciphertexts in the default layout are migrated to the key-wrapped one.
+    let ct = encrypt_hybrid(&mut rng, &mpk, &policy, b"payload", b"aad").unwrap();
+    let new_ct = reencapsulate_hybrid(
+        &mut rng,
+        &owner_usk,
+        &mpk,
+        &policy,
+        &ct,
+        &new_policy,
+        b"aad",
+    )
+    .unwrap();
+    assert_eq!(new_ct.sym_ct[0], ct.suite().unwrap().id() | 0x80);
+    let decrypted = decrypt_hybrid(
+        &node_usk,
+        &node_gid,
+        &new_policy,
+        &new_ct.abe_ct,
+        &new_ct.sym_ct,
+        b"aad",
+    )
+    .unwrap();
+    assert_eq!(decrypted, b"payload");
+}
+
+#[test]
+fn scheme_epoch_keys_do_not_combine() {
+    let mut rng = ark_std::test_rng();
+    let (partial_msk, partial_mpk) = scheme::setup_partial(&mut rng, "A");
+    let mut mpk = MPK::new();
+    mpk.add_partial_key(partial_mpk);
+
+    // The user held n1 and n2 at epoch 1, but only n1 from epoch 2 on.
+    let old_attrs = vec![
+        UserAttribute::new("A", "node", "n1"),
+        UserAttribute::new("A", "node", "n2"),
+    ];
+    let new_attrs = vec![UserAttribute::new("A", "node", "n1")];
+    let policy = Policy::parse("A.node:n2").unwrap().require_epoch(2);
+    let tau = Tau::new(&policy);
+    let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+
+    // Combines the row of index 1 (`A.node:n2`) of the old key with the new
+    // key, whose rows of index 0 hold the epoch.
+    let combine = |old: &PartialUSK, new: &PartialUSK| {
+        let mut mixed = PartialUSK {
+            auth: new.auth.clone(),
+            gid: new.gid.clone(),
+            k_1_1_vec: new.k_1_1_vec.clone(),
+            k_1_2_map: new.k_1_2_map.clone(),
+            k_2_map: new.k_2_map.clone(),
+            k_3_map: new.k_3_map.clone(),
+            k_4_vec: new.k_4_vec.clone(),
+            k_5_vec: new.k_5_vec.clone(),
+        };
+        mixed.k_1_1_vec.push(old.k_1_1_vec[1]);
+        mixed.k_4_vec.push(old.k_4_vec[1]);
+        mixed.k_5_vec.push(old.k_5_vec[1]);
+        let key = (String::from("node"), String::from("n2"));
+        mixed.k_1_2_map.insert(key.clone(), old.k_1_2_map[&key]);
+        let mut usk = USK::new();
+        usk.add_partial_key(mixed);
+        usk
+    };
+    let mut mixed_attrs = UserAttribute::with_epoch(&new_attrs, 2);
+    mixed_attrs.push(UserAttribute::new("A", "node", "n2"));
+    let iota = Iota::new(&mixed_attrs);
+    let issue = |gid: &str, attrs: &[UserAttribute], epoch: u32| {
+        let stamped = UserAttribute::with_epoch(attrs, epoch);
+        let stamped_refs: Vec<&UserAttribute> = stamped.iter().collect();
+        scheme::keygen_partial(
+            ark_std::test_rng(),
+            gid,
+            &partial_msk,
+            &stamped_refs,
+            &Iota::new(&stamped),
+        )
+        .unwrap()
+    };
+
+    // Keys stamped with an epoch but issued for the same GID combine.
+    let usk = combine(
+        &issue(USER_ID, &old_attrs, 1),
+        &issue(USER_ID, &new_attrs, 2),
+    );
+    let k_dec = decrypt(&usk, USER_ID, &iota, &tau, &policy, &ct).unwrap();
+    assert_eq!(k_dec, k_enc);
+
+    // Keys from `keygen_partial_with_epoch` are bound to their epoch.
+    let old_refs: Vec<&UserAttribute> = old_attrs.iter().collect();
+    let new_refs: Vec<&UserAttribute> = new_attrs.iter().collect();
+    let old =
+        scheme::keygen_partial_with_epoch(&mut rng, USER_ID, &partial_msk, &old_refs, 1).unwrap();
+    let new =
+        scheme::keygen_partial_with_epoch(&mut rng, USER_ID, &partial_msk, &new_refs, 2).unwrap();
+    let usk = combine(&old, &new);
+    let k_dec = decrypt(&usk, &epoch_gid(USER_ID, 2), &iota, &tau, &policy, &ct).unwrap();
+    assert_ne!(k_dec, k_enc);
+}
+
+#[test]
+fn hybrid_reencapsulate_new_policy() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let (msk, mpk) = setup(&mut rng, &vec!["A", "B"]);
+    let policy = Policy::parse("A.wf:w1 & A.node:n1").unwrap();
+    let suite = CipherSuite::default();
+    let ct = encrypt_hybrid_wrapped(&mut rng, &mpk, &policy, suite, b"state", b"aad").unwrap();
+    let n1_attrs = vec![
+        UserAttribute::new("A", "wf", "w1"),
+        UserAttribute::new("A", "node", "n1"),
+    ];
+    let n1_usk = keygen(&mut rng, USER_ID, &msk, &n1_attrs, &Iota::new(&n1_attrs)).unwrap();
+    let decrypted =
+        decrypt_hybrid(&n1_usk, USER_ID, &policy, &ct.abe_ct, &ct.sym_ct, b"aad").unwrap();
+    assert_eq!(decrypted, b"state");
+
+    // The template graph changes: n2 becomes the only child of n1's output.
+    // A holder of a key that satisfies the current policy re-encapsulates it.
+    let holder_usk = keygen(&mut rng, "holder", &msk, &n1_attrs, &Iota::new(&n1_attrs)).unwrap();
+    let holder_usk = PreparedUsk::new(holder_usk, "holder").unwrap();
+    let new_policy = Policy::parse("A.wf:w1 & A.node:n2 & B.anc:n1").unwrap();
+    let new_ct = reencapsulate_hybrid(
+        &mut rng,
+        &holder_usk,
+        &mpk,
+        &policy,
+        &ct,
+        &new_policy,
+        b"aad",
+    )
+    .unwrap();
+
+    // The payload is copied as is: suite | version | nonce | AES-128 key | tag.
+    let header_len = 2 + 12 + 16 + 16;
+    assert_eq!(ct.sym_ct[header_len..], new_ct.sym_ct[header_len..]);
+
+    let n2_attrs = vec![
+        UserAttribute::new("A", "wf", "w1"),
+        UserAttribute::new("A", "node", "n2"),
+        UserAttribute::new("B", "anc", "n1"),
+    ];
+    let n2_usk = keygen(&mut rng, USER_ID, &msk, &n2_attrs, &Iota::new(&n2_attrs)).unwrap();
+    let decrypted = decrypt_hybrid(
+        &n2_usk,
+        USER_ID,
+        &new_policy,
+        &new_ct.abe_ct,
+        &new_ct.sym_ct,
+        b"aad",
+    )
+    .unwrap();
+    assert_eq!(decrypted, b"state");
+    assert!(matches!(
+        decrypt_hybrid(
+            &n1_usk,
+            USER_ID,
+            &new_policy,
+            &new_ct.abe_ct,
+            &new_ct.sym_ct,
+            b"aad"
+        ),
+        Err(Abe4Error::UnsatisfiedPolicy { .. })
+    ));
+    assert!(
+        decrypt_hybrid(
+            &n2_usk,
+            USER_ID,
+            &new_policy,
+            &new_ct.abe_ct,
+            &new_ct.sym_ct,
+            b""
+        )
+        .is_err()
+    );
+
+    // The new policy can not be paired with the old symmetric ciphertext.
+    assert!(
+        decrypt_hybrid(
+            &n2_usk,
+            USER_ID,
+            &new_policy,
+            &new_ct.abe_ct,
+            &ct.sym_ct,
+            b"aad"
+        )
+        .is_err()
+    );
+
+    // Only a key that satisfies the current policy can re-encapsulate it.
+    let other = keygen(&mut rng, "holder", &msk, &n2_attrs, &Iota::new(&n2_attrs)).unwrap();
+    let other = PreparedUsk::new(other, "holder").unwrap();
+    assert!(matches!(
+        reencapsulate_hybrid(&mut rng, &other, &mpk, &policy, &ct, &new_policy, b"aad"),
+        Err(Abe4Error::UnsatisfiedPolicy { .. })
+    ));
+}
+
+/// Takes ownership of a string returned over FFI.
+fn ffi_string(s: *mut c_char) -> String {
+    assert!(!s.is_null());
+    let owned = unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+    unsafe { free_string(s) };
+    owned
+}
+
+#[test]
+fn ffi_multi_valued_label_round_trip() {
+    let auths = CString::new(r#"["A"]"#).unwrap();
+    let setup_json: serde_json::Value =
+        serde_json::from_str(&ffi_string(unsafe { setup_abe4(auths.as_ptr()) })).unwrap();
+    let msk = CString::new(setup_json["msk"].as_str().unwrap()).unwrap();
+    let mpk = CString::new(setup_json["mpk"].as_str().unwrap()).unwrap();
+
+    // The values of `A.node` are not in sorted order, so the key is only
+    // usable if decryption numbers them the same way as key generation.
+    let gid = CString::new(USER_ID).unwrap();
+    let user_attrs = CString::new(
+        r#"[
+            {"authority": "A", "label": "node", "attribute": "n3"},
+            {"authority": "A", "label": "wf", "attribute": "w1"},
+            {"authority": "A", "label": "node", "attribute": "n1"},
+            {"authority": "A", "label": "node", "attribute": "n2"}
+        ]"#,
+    )
+    .unwrap();
+    let usk = CString::new(ffi_string(unsafe {
+        keygen_abe4(gid.as_ptr(), msk.as_ptr(), user_attrs.as_ptr())
+    }))
+    .unwrap();
+    let kept_attrs = CString::new(
+        r#"[
+            {"authority": "A", "label": "node", "attribute": "n2"},
+            {"authority": "A", "label": "node", "attribute": "n3"},
+            {"authority": "A", "label": "node", "attribute": "n1"}
+        ]"#,
+    )
+    .unwrap();
+    let delegated = CString::new(ffi_string(unsafe {
+        delegate_usk_abe4(
+            gid.as_ptr(),
+            mpk.as_ptr(),
+            usk.as_ptr(),
+            kept_attrs.as_ptr(),
+        )
+    }))
+    .unwrap();
+
+    for (policy, delegated_ok) in [
+        ("A.node:n1 & A.wf:w1", false),
+        ("A.node:n2", true),
+        ("A.node:n3", true),
+        ("A.node:n1 & A.node:n3", true),
+    ] {
+        let policy = CString::new(policy).unwrap();
+        let encrypt_json: serde_json::Value = serde_json::from_str(&ffi_string(unsafe {
+            encrypt_abe4(mpk.as_ptr(), policy.as_ptr())
+        }))
+        .unwrap();
+        let ct = CString::new(encrypt_json["ciphertext"].as_str().unwrap()).unwrap();
+        let gt = encrypt_json["gt"].as_str().unwrap();
+
+        let decrypted = ffi_string(unsafe {
+            decrypt_abe4(usk.as_ptr(), gid.as_ptr(), policy.as_ptr(), ct.as_ptr())
+        });
+        assert_eq!(decrypted, gt);
+
+        let decrypted = unsafe {
+            decrypt_abe4(
+                delegated.as_ptr(),
+                gid.as_ptr(),
+                policy.as_ptr(),
+                ct.as_ptr(),
+            )
+        };
+        if delegated_ok {
+            assert_eq!(ffi_string(decrypted), gt);
+        } else {
+            assert!(decrypted.is_null());
+        }
+    }
+}
+
+#[test]
+fn scheme_outsourced_decrypt() {
+    let mut rng = ark_std::test_rng();
+    let (_, user_attrs, _) = prepare_test(&vec!["A.a:0", "A.b:1", "B.c:0"], "A.a:0");
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &vec!["A", "B"]);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+    let (tk, rk) = transform_keygen(&mut rng, &usk);
+
+    // The helper only holds the serialized transformation key.
+    let mut tk_bytes = Vec::new();
+    tk.serialize_compressed(&mut tk_bytes).unwrap();
+    let tk = TransformKey::deserialize_compressed(&tk_bytes[..]).unwrap();
+
+    for policy in [
+        "A.a:0",
+        "A.a:0 & (!A.b:0 | B.c:1)",
+        "!B.c:1 & (A.b:1 | A.a:1)",
+    ] {
+        let policy = Policy::parse(policy).unwrap();
+        let tau = Tau::new(&policy);
+        let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+        let transformed = transform(&tk, USER_ID, &iota, &tau, &policy, &ct).unwrap();
+        assert_eq!(decrypt_transformed(&rk, &transformed), k_enc);
+        // Neither the helper's result nor its key give away the symmetric key.
+        assert_ne!(transformed.c_0 * transformed.c_1, k_enc);
+        let k_tk = decrypt(&tk.usk, USER_ID, &iota, &tau, &policy, &ct).unwrap();
+        assert_ne!(k_tk, k_enc);
+    }
+
+    // The helper fails where decryption would.
+    let policy = Policy::parse("B.c:1").unwrap();
+    let tau = Tau::new(&policy);
+    let (_, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+    assert!(matches!(
+        transform(&tk, USER_ID, &iota, &tau, &policy, &ct),
+        Err(Abe4Error::UnsatisfiedPolicy { .. })
+    ));
+    assert!(matches!(
+        transform(&tk, "other", &iota, &tau, &policy, &ct),
+        Err(Abe4Error::GidMismatch { .. })
+    ));
+}
+
+#[test]
+fn scheme_delegate() {
+    let mut rng = ark_std::test_rng();
+    let user_attrs = vec![
+        UserAttribute::new("A", "wf", "foo"),
+        UserAttribute::new("A", "node", "n0"),
+        UserAttribute::new("A", "node", "n1"),
+        UserAttribute::new("B", "c", "0"),
+    ];
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &vec!["A", "B"]);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+
+    let subset = user_attrs[1..].to_vec();
+    let delegated = delegate(&mut rng, &mpk, &usk, USER_ID, &iota, &subset).unwrap();
+    delegated.verify(&mpk, USER_ID, &subset).unwrap();
+    let delegated_attrs = delegated.get_user_attributes();
+    assert_eq!(delegated_attrs.len(), subset.len());
+    assert!(subset.iter().all(|ua| delegated_attrs.contains(ua)));
+    // The delegated key is re-randomised.
+    let key = (String::from("node"), String::from("n0"));
+    assert_ne!(
+        delegated.get_partial_key("A").unwrap().k_1_2_map[&key],
+        usk.get_partial_key("A").unwrap().k_1_2_map[&key]
+    );
+
+    for policy in ["A.node:n1 & B.c:0", "A.node:n0 & !B.c:1", "!A.node:n2"] {
+        let policy = Policy::parse(policy).unwrap();
+        let tau = Tau::new(&policy);
+        let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+        let k_dec = decrypt(&delegated, USER_ID, &iota, &tau, &policy, &ct).unwrap();
+        assert_eq!(k_enc, k_dec);
+    }
+    let policy = Policy::parse("A.wf:foo").unwrap();
+    let tau = Tau::new(&policy);
+    let (_, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+    assert!(matches!(
+        decrypt(&delegated, USER_ID, &iota, &tau, &policy, &ct),
+        Err(Abe4Error::UnsatisfiedPolicy { .. })
+    ));
+
+    // Delegated keys can be delegated further, but not widened.
+    let subset = vec![UserAttribute::new("B", "c", "0")];
+    let delegated = delegate(&mut rng, &mpk, &delegated, USER_ID, &iota, &subset).unwrap();
+    delegated.verify(&mpk, USER_ID, &subset).unwrap();
+    for subset in [
+        vec![UserAttribute::new("A", "wf", "foo")],
+        vec![UserAttribute::new("A", "wf", "bar")],
+    ] {
+        assert!(matches!(
+            delegate(&mut rng, &mpk, &delegated, USER_ID, &iota, &subset),
+            Err(Abe4Error::InvalidInput(_))
+        ));
+    }
+
+    // Labels cannot be split, and the GID must match.
+    let subset = vec![UserAttribute::new("A", "node", "n0")];
+    assert!(matches!(
+        delegate(&mut rng, &mpk, &usk, USER_ID, &iota, &subset),
+        Err(Abe4Error::InvalidInput(_))
+    ));
+    assert!(matches!(
+        delegate(&mut rng, &mpk, &usk, "other", &iota, &user_attrs),
+        Err(Abe4Error::GidMismatch { .. })
+    ));
+}
+
+#[test]
+fn hybrid_outsourced_decrypt() {
+    let (auths, user_attrs, policy) = prepare_test(&vec!["A.a:0", "B.b:1"], "A.a:0 & !B.b:0");
+    let mut rng = StdRng::seed_from_u64(0);
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+    let ct = encrypt_hybrid(&mut rng, &mpk, &policy, b"payload", b"aad").unwrap();
+    let (tk, rk) = transform_keygen(&mut rng, &usk);
+
+    // The helper sends the transformed ciphertext to the user.
+    let transformed = transform_hybrid(&tk, USER_ID, &policy, &ct.abe_ct).unwrap();
+    let mut transformed_bytes = Vec::new();
+    transformed
+        .serialize_compressed(&mut transformed_bytes)
+        .unwrap();
+    let transformed =
+        TransformedCiphertext::deserialize_compressed(&transformed_bytes[..]).unwrap();
+    let decrypted =
+        decrypt_hybrid_transformed(&rk, &transformed, &policy, &ct.abe_ct, &ct.sym_ct, b"aad")
+            .unwrap();
+    assert_eq!(decrypted, b"payload");
+
+    // A wrong retrieval key, or a dishonest helper, is caught by the AEAD.
+    let (_, other_rk) = transform_keygen(&mut rng, &usk);
+    assert!(
+        decrypt_hybrid_transformed(
+            &other_rk,
+            &transformed,
+            &policy,
+            &ct.abe_ct,
+            &ct.sym_ct,
+            b"aad"
+        )
+        .is_err()
+    );
+    let mut tampered = transformed.clone();
+    tampered.c_0 *= transformed.c_1;
+    assert!(
+        decrypt_hybrid_transformed(&rk, &tampered, &policy, &ct.abe_ct, &ct.sym_ct, b"aad")
+            .is_err()
+    );
+}
+
 #[test]
 fn hybrid_decrypt_fails_for_unauthorized_user() {
     let user_attrs = vec![];
@@ -110,7 +1048,7 @@ fn hybrid_rejects_modified_aad() {
     let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
     let iota = Iota::new(&user_attrs);
     let (msk, mpk) = setup(&mut rng, &auths);
-    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
 
     let hybrid_ct =
         encrypt_hybrid(&mut rng, &mpk, &policy, plaintext, aad).expect("encrypt_hybrid failed");
@@ -136,6 +1074,472 @@ fn hybrid_rejects_modified_aad() {
     assert!(tampered.is_err());
 }
 
+#[test]
+fn hybrid_round_trip_all_suites_ok() {
+    let user_attrs = vec!["A.a:0"];
+    let policy = "A.a:0";
+    let plaintext = b"hybrid plaintext payload";
+    let aad = b"hybrid aad data";
+
+    let (auths, user_attrs, policy) = prepare_test(&user_attrs, policy);
+    let mut rng = StdRng::seed_from_u64(6);
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+
+    for suite in CipherSuite::ALL {
+        let hybrid_ct = encrypt_hybrid_with_suite(&mut rng, &mpk, &policy, suite, plaintext, aad)
+            .expect("encrypt_hybrid_with_suite failed");
+        assert_eq!(hybrid_ct.suite().unwrap(), suite);
+
+        // Decryption picks up the suite from the ciphertext.
+        let recovered = decrypt_hybrid(
+            &usk,
+            USER_ID,
+            &policy,
+            &hybrid_ct.abe_ct,
+            &hybrid_ct.sym_ct,
+            aad,
+        )
+        .expect("decrypt_hybrid failed");
+        assert_eq!(plaintext, recovered.as_slice());
+
+        // Decrypting with a different suite fails.
+        let mut sym_ct = hybrid_ct.sym_ct.clone();
+        sym_ct[0] = match suite {
+            CipherSuite::Aes128Gcm => CipherSuite::Aes256Gcm.id(),
+            _ => CipherSuite::Aes128Gcm.id(),
+        };
+        let tampered = decrypt_hybrid(&usk, USER_ID, &policy, &hybrid_ct.abe_ct, &sym_ct, aad);
+        assert!(tampered.is_err());
+    }
+}
+
+#[test]
+fn hybrid_rejects_mauled_abe_ct() {
+    let user_attrs = vec!["A.a:0"];
+    let policy = "A.a:0 | B.b:0";
+    let plaintext = b"hybrid plaintext payload";
+    let aad = b"hybrid aad data";
+
+    let (auths, user_attrs, policy) = prepare_test(&user_attrs, policy);
+    let mut rng = StdRng::seed_from_u64(7);
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+
+    let hybrid_ct =
+        encrypt_hybrid(&mut rng, &mpk, &policy, plaintext, aad).expect("encrypt_hybrid failed");
+    let other_ct =
+        encrypt_hybrid(&mut rng, &mpk, &policy, plaintext, aad).expect("encrypt_hybrid failed");
+
+    // The user only needs the first row of the policy to decapsulate, so
+    // replacing the second row of the CP-ABE ciphertext does not change the
+    // encapsulated key.
+    let mut mauled = hybrid_ct.abe_ct.clone();
+    mauled.c_1_vec[1] = other_ct.abe_ct.c_1_vec[1];
+    let tau = Tau::new(&policy);
+    assert_eq!(
+        decrypt(&usk, USER_ID, &iota, &tau, &policy, &mauled),
+        decrypt(&usk, USER_ID, &iota, &tau, &policy, &hybrid_ct.abe_ct),
+    );
+
+    // But the symmetric layer is bound to the whole CP-ABE ciphertext.
+    let result = decrypt_hybrid(&usk, USER_ID, &policy, &mauled, &hybrid_ct.sym_ct, aad);
+    assert!(result.is_err());
+
+    // And to the policy.
+    let other_policy = Policy::parse("A.a:0 | C.c:0").unwrap();
+    let result = decrypt_hybrid(
+        &usk,
+        USER_ID,
+        &other_policy,
+        &hybrid_ct.abe_ct,
+        &hybrid_ct.sym_ct,
+        aad,
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn hybrid_decrypt_reports_missing_clauses() {
+    let user_attrs = vec!["A.a:0"];
+    let policy = "A.a:0 & (B.b:0 | A.c:0)";
+    let plaintext = b"hybrid plaintext payload";
+    let aad = b"hybrid aad data";
+    let err = test_hybrid_scheme(user_attrs, policy, plaintext, aad).unwrap_err();
+    assert!(err.to_string().contains("(B.b:0 | A.c:0)"));
+}
+
+#[test]
+fn evaluate_matches_decryption() {
+    let cases = [
+        (vec!["A.a:0", "B.b:1"], "A.a:0 & !B.b:0"),
+        (vec!["A.a:0", "B.b:0"], "A.a:0 & !B.b:0"),
+        (vec!["A.a:0", "A.c:0"], "2of(A.a:0, A.b:0, A.c:0)"),
+        (vec!["A.c:0"], "2of(A.a:0, A.b:0, A.c:0)"),
+        (vec![], "!A.a:0"),
+    ];
+    for (user_attrs, policy) in cases {
+        let (_, parsed_user_attrs, parsed_policy) = prepare_test(&user_attrs, policy);
+        let evaluation = parsed_policy.evaluate(&parsed_user_attrs);
+        let (k_enc, k_dec) = test_scheme(user_attrs, policy);
+        assert_eq!(
+            evaluation.is_satisfied(),
+            k_dec.is_ok_and(|k| Gt::eq(&k_enc, &k)),
+            "{policy}"
+        );
+    }
+}
+
+#[test]
+fn hybrid_container_round_trip_ok() {
+    let user_attrs = vec!["A.a:0", "B.b:1"];
+    let policy = "A.a:0 & (!B.b:0 | C.c:0)";
+    let plaintext = b"hybrid plaintext payload";
+    let aad = b"hybrid aad data";
+
+    let (auths, user_attrs, policy) = prepare_test(&user_attrs, policy);
+    let mut rng = StdRng::seed_from_u64(2);
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+
+    for suite in CipherSuite::ALL {
+        let container = encrypt_hybrid_container(&mut rng, &mpk, &policy, suite, plaintext, aad)
+            .expect("encrypt_hybrid_container failed");
+        let bytes = container.to_bytes().expect("to_bytes failed");
+        let container = HybridContainer::from_bytes(&bytes).expect("from_bytes failed");
+        assert_eq!(container.suite, suite);
+        assert_eq!(container.policy().unwrap(), policy);
+        assert_eq!(container.authorities, vec!["A", "B", "C"]);
+        assert_eq!(container.aad, aad);
+
+        let recovered = decrypt_hybrid_container(&usk, USER_ID, &container)
+            .expect("decrypt_hybrid_container failed");
+        assert_eq!(plaintext, recovered.as_slice());
+
+        // Serialization is deterministic.
+        assert_eq!(container.to_bytes().unwrap(), bytes);
+    }
+}
+
+#[test]
+fn hybrid_container_rejects_malformed_bytes() {
+    let user_attrs = vec!["A.a:0"];
+    let policy = "A.a:0 | B.b:0";
+    let (auths, _, policy) = prepare_test(&user_attrs, policy);
+    let mut rng = StdRng::seed_from_u64(3);
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let (_, mpk) = setup(&mut rng, &auths);
+    let container = encrypt_hybrid_container(
+        &mut rng,
+        &mpk,
+        &policy,
+        CipherSuite::default(),
+        b"payload",
+        b"aad",
+    )
+    .expect("encrypt_hybrid_container failed");
+    let bytes = container.to_bytes().unwrap();
+
+    // Bad magic number.
+    let mut bad = bytes.clone();
+    bad[0] ^= 1;
+    assert!(HybridContainer::from_bytes(&bad).is_err());
+
+    // Unsupported version.
+    let mut bad = bytes.clone();
+    bad[4] = 0xff;
+    assert!(HybridContainer::from_bytes(&bad).is_err());
+
+    // Unsupported cipher suite.
+    let mut bad = bytes.clone();
+    bad[5] = 0xff;
+    assert!(HybridContainer::from_bytes(&bad).is_err());
+
+    // Truncated container.
+    for len in [0, 5, bytes.len() / 2, bytes.len() - 1] {
+        assert!(HybridContainer::from_bytes(&bytes[..len]).is_err());
+    }
+
+    // Trailing bytes.
+    let mut bad = bytes.clone();
+    bad.push(0);
+    assert!(HybridContainer::from_bytes(&bad).is_err());
+
+    // Cipher suite that does not match the symmetric ciphertext.
+    let mut bad = bytes.clone();
+    bad[5] = CipherSuite::ChaCha20Poly1305.id();
+    assert!(HybridContainer::from_bytes(&bad).is_err());
+
+    // Authorities that do not match the policy.
+    let mut bad = container.clone();
+    bad.authorities.pop();
+    assert!(HybridContainer::from_bytes(&bad.to_bytes().unwrap()).is_err());
+
+    // Policy that does not decode: the policy follows the magic number, the
+    // version, the suite and its length.
+    let mut bad = bytes.clone();
+    bad[10] = 0;
+    assert!(HybridContainer::from_bytes(&bad).is_err());
+
+    // Policy that does not parse.
+    let mut bad = container.clone();
+    bad.policy = String::from("A.a:0 |");
+    assert!(bad.to_bytes().is_err());
+}
+
+#[test]
+fn hybrid_container_rejects_tampered_aad() {
+    let user_attrs = vec!["A.a:0"];
+    let policy = "A.a:0";
+    let (auths, user_attrs, policy) = prepare_test(&user_attrs, policy);
+    let mut rng = StdRng::seed_from_u64(4);
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+
+    let mut container = encrypt_hybrid_container(
+        &mut rng,
+        &mpk,
+        &policy,
+        CipherSuite::default(),
+        b"payload",
+        b"aad",
+    )
+    .expect("encrypt_hybrid_container failed");
+    container.aad = b"tampered aad".to_vec();
+    let container = HybridContainer::from_bytes(&container.to_bytes().unwrap()).unwrap();
+    assert!(decrypt_hybrid_container(&usk, USER_ID, &container).is_err());
+}
+
+fn test_hybrid_stream(
+    user_attrs: Vec<&str>,
+    policy: &str,
+    suite: CipherSuite,
+    plaintext: &[u8],
+    aad: &[u8],
+    tamper: impl Fn(&mut Vec<u8>),
+) -> Result<Vec<u8>> {
+    let (auths, user_attrs, policy) = prepare_test(&user_attrs, policy);
+    let mut rng = StdRng::seed_from_u64(5);
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+
+    // Write in uneven chunks to exercise the segment buffering.
+    let mut writer = HybridEncryptWriter::new(&mut rng, &mpk, &policy, suite, aad, Vec::new())?;
+    for chunk in plaintext.chunks(1000) {
+        writer.write_all(chunk)?;
+    }
+    let mut stream = writer.finish()?;
+    tamper(&mut stream);
+
+    let mut reader = HybridDecryptReader::new(&usk, USER_ID, aad, &stream[..])?;
+    assert_eq!(*reader.policy(), policy);
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted)?;
+    Ok(decrypted)
+}
+
+#[test]
+fn hybrid_stream_round_trip_ok() {
+    for len in [
+        0,
+        1,
+        STREAM_SEGMENT_SIZE - 1,
+        STREAM_SEGMENT_SIZE,
+        STREAM_SEGMENT_SIZE + 1,
+        3 * STREAM_SEGMENT_SIZE + 17,
+    ] {
+        let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        let decrypted = test_hybrid_stream(
+            vec!["A.a:0", "B.b:1"],
+            "A.a:0 & !B.b:0",
+            CipherSuite::default(),
+            &plaintext,
+            b"stream aad",
+            |_| {},
+        )
+        .expect("hybrid stream round trip failed");
+        assert_eq!(plaintext, decrypted, "len={len}");
+    }
+}
+
+#[test]
+fn hybrid_stream_round_trip_all_suites_ok() {
+    let plaintext: Vec<u8> = (0..STREAM_SEGMENT_SIZE + 1).map(|i| i as u8).collect();
+    for suite in CipherSuite::ALL {
+        let decrypted = test_hybrid_stream(
+            vec!["A.a:0"],
+            "A.a:0",
+            suite,
+            &plaintext,
+            b"stream aad",
+            |_| {},
+        )
+        .expect("hybrid stream round trip failed");
+        assert_eq!(plaintext, decrypted, "suite={suite:?}");
+    }
+}
+
+#[test]
+fn hybrid_stream_decrypt_fails_for_unauthorized_user() {
+    let result = test_hybrid_stream(
+        vec!["A.a:1"],
+        "A.a:0",
+        CipherSuite::default(),
+        b"payload",
+        b"aad",
+        |_| {},
+    );
+    assert!(result.is_err());
+}
+
+#[test]
+fn hybrid_stream_rejects_tampering() {
+    // Size of a full encrypted segment, and of the last one (5 bytes + tag).
+    const SEGMENT: usize = STREAM_SEGMENT_SIZE + 16;
+    const LAST: usize = 5 + 16;
+
+    let plaintext: Vec<u8> = (0..2 * STREAM_SEGMENT_SIZE + 5).map(|i| i as u8).collect();
+    let tampered: [fn(&mut Vec<u8>); 5] = [
+        // Flip a bit in the last segment.
+        |stream| *stream.last_mut().unwrap() ^= 1,
+        // Drop the last segment.
+        |stream| stream.truncate(stream.len() - LAST),
+        // Drop the last byte.
+        |stream| {
+            stream.pop();
+        },
+        // Append a byte.
+        |stream| stream.push(0),
+        // Swap the first two segments.
+        |stream| {
+            let start = stream.len() - LAST - 2 * SEGMENT;
+            let (first, second) = stream[start..].split_at_mut(SEGMENT);
+            first.swap_with_slice(&mut second[..SEGMENT]);
+        },
+    ];
+    for tamper in tampered {
+        let result = test_hybrid_stream(
+            vec!["A.a:0"],
+            "A.a:0",
+            CipherSuite::default(),
+            &plaintext,
+            b"stream aad",
+            tamper,
+        );
+        assert!(result.is_err());
+    }
+}
+
+fn assert_curve_round_trips<C: Curve>() {
+    let cases = [
+        (vec!["A.a:0", "B.b:1"], "A.a:0 & B.b:1", true),
+        (vec!["A.a:0", "A.c:1"], "A.a:0 & !A.c:0", true),
+        (vec!["A.a:0", "B.b:1"], "2of(A.a:0, B.b:0, B.b:1)", true),
+        (vec!["A.a:0"], "A.a:0 & B.b:1", false),
+        (vec!["A.a:0", "A.c:0"], "A.a:0 & !A.c:0", false),
+    ];
+    for (user_attrs, policy, ok) in cases {
+        let (k_enc, k_dec) = test_scheme_with_curve::<C>(user_attrs, policy);
+        if ok {
+            assert_eq!(k_dec, Ok(k_enc), "policy: {policy}");
+        } else {
+            assert!(
+                matches!(k_dec, Err(Abe4Error::UnsatisfiedPolicy { .. })),
+                "policy: {policy}"
+            );
+        }
+    }
+}
+
+#[test]
+fn scheme_round_trip_flipped_groups_ok() {
+    assert_curve_round_trips::<Bls12_381Flipped>();
+}
+
+#[test]
+fn scheme_round_trip_bls12_377_ok() {
+    assert_curve_round_trips::<Bls12_377>();
+}
+
+#[test]
+fn scheme_serialization_other_curve_ok() {
+    let (auths, user_attrs, policy) = prepare_test(&vec!["A.a:0"], "A.a:0");
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let mut rng = StdRng::seed_from_u64(8);
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup_with_curve::<Bls12_381Flipped>(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+    let tau = Tau::new(&policy);
+    let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+
+    let mut usk_bytes = Vec::new();
+    usk.serialize_compressed(&mut usk_bytes).unwrap();
+    let mut ct_bytes = Vec::new();
+    ct.serialize_compressed(&mut ct_bytes).unwrap();
+    let usk = USK::<Bls12_381Flipped>::deserialize_compressed(&usk_bytes[..]).unwrap();
+    let ct = Ciphertext::<Bls12_381Flipped>::deserialize_compressed(&ct_bytes[..]).unwrap();
+    assert_eq!(decrypt(&usk, USER_ID, &iota, &tau, &policy, &ct), Ok(k_enc));
+
+    // Elements of one curve do not deserialize as elements of another.
+    assert!(Ciphertext::<DefaultCurve>::deserialize_compressed(&ct_bytes[..]).is_err());
+}
+
+#[test]
+fn scheme_errors_are_typed() {
+    let (auths, user_attrs, policy) = prepare_test(&vec!["A.a:0"], "A.a:0");
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let mut rng = StdRng::seed_from_u64(9);
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+    let tau = Tau::new(&policy);
+    let (_, ct) = encrypt(&mut rng, &mpk, &policy, &tau).unwrap();
+
+    // The key is bound to the GID it was issued for.
+    assert_eq!(
+        decrypt(&usk, "other-user", &iota, &tau, &policy, &ct),
+        Err(Abe4Error::GidMismatch {
+            expected: String::from("other-user"),
+            found: String::from(USER_ID),
+        })
+    );
+
+    // Neither keys nor ciphertexts can be made for unknown authorities.
+    let other_attrs = vec![UserAttribute::new("B", "b", "0")];
+    assert_eq!(
+        keygen(
+            &mut rng,
+            USER_ID,
+            &msk,
+            &other_attrs,
+            &Iota::new(&other_attrs)
+        )
+        .err(),
+        Some(Abe4Error::MissingAuthorityKey(String::from("B")))
+    );
+    let other_policy = Policy::parse("B.b:0").unwrap();
+    assert_eq!(
+        encrypt(&mut rng, &mpk, &other_policy, &Tau::new(&other_policy)).err(),
+        Some(Abe4Error::MissingAuthorityKey(String::from("B")))
+    );
+
+    // Policy syntax errors are reported as such.
+    assert!(matches!(
+        Policy::parse("A.a:0 &"),
+        Err(Abe4Error::PolicySyntax(_))
+    ));
+}
+
 // Handcrafted test cases (single auth)
 
 #[test]
@@ -420,6 +1824,193 @@ fn multi_auth_complex_fail() {
     assert_decryption_fail(user_attrs, policy);
 }
 
+// Handcrafted test cases (threshold gates)
+
+#[test]
+fn threshold_2_of_3_ok() {
+    let user_attrs = vec!["A.a:0", "A.c:0"];
+    let policy = "2of(A.a:0, A.b:0, A.c:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn threshold_2_of_3_all_ok() {
+    let user_attrs = vec!["A.a:0", "A.b:0", "A.c:0"];
+    let policy = "2of(A.a:0, A.b:0, A.c:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn threshold_2_of_3_fail() {
+    let user_attrs = vec!["A.b:0", "A.c:1"];
+    let policy = "2of(A.a:0, A.b:0, A.c:0)";
+    assert_decryption_fail(user_attrs, policy);
+}
+
+#[test]
+fn threshold_3_of_5_ok() {
+    let user_attrs = vec!["A.a:0", "A.d:0", "A.e:0"];
+    let policy = "3of(A.a:0, A.b:0, A.c:0, A.d:0, A.e:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn threshold_3_of_5_fail() {
+    let user_attrs = vec!["A.a:0", "A.e:0"];
+    let policy = "3of(A.a:0, A.b:0, A.c:0, A.d:0, A.e:0)";
+    assert_decryption_fail(user_attrs, policy);
+}
+
+#[test]
+fn threshold_nested_ok() {
+    let user_attrs = vec!["A.a:0", "A.b:0", "A.d:0"];
+    let policy = "A.a:0 & 2of(A.b:0 & A.c:1 | A.b:0, A.c:0, A.d:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn threshold_nested_fail() {
+    let user_attrs = vec!["A.b:0", "A.d:0"];
+    let policy = "A.a:0 & 2of(A.b:0 & A.c:1 | A.b:0, A.c:0, A.d:0)";
+    assert_decryption_fail(user_attrs, policy);
+}
+
+/// `!2of(...)` over 20 labels, whose dual `19of(...)` has coefficients up to
+/// 20^18, which do not fit in an `i64`.
+fn large_negated_threshold(num_others: usize) -> (Vec<String>, String) {
+    let labels: Vec<char> = ('a'..='t').collect();
+    let lits: Vec<String> = labels.iter().map(|l| format!("A.{l}:0")).collect();
+    let user_attrs = labels[..num_others]
+        .iter()
+        .map(|l| format!("A.{l}:1"))
+        .collect();
+    (user_attrs, format!("!2of({})", lits.join(", ")))
+}
+
+#[test]
+fn threshold_large_negated_ok() {
+    let (user_attrs, policy) = large_negated_threshold(19);
+    assert_decryption_ok(user_attrs.iter().map(String::as_str).collect(), &policy);
+}
+
+#[test]
+fn threshold_large_negated_fail() {
+    let (user_attrs, policy) = large_negated_threshold(18);
+    assert_decryption_fail(user_attrs.iter().map(String::as_str).collect(), &policy);
+}
+
+#[test]
+fn threshold_with_negations_ok() {
+    let user_attrs = vec!["A.a:1", "A.b:0"];
+    let policy = "2of(!A.a:0, A.b:0, A.c:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn threshold_negated_ok() {
+    // !2of(a, b, c) is equivalent to 2of(!a, !b, !c).
+    let user_attrs = vec!["A.a:0", "A.b:1", "A.c:1"];
+    let policy = "!2of(A.a:0, A.b:0, A.c:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn threshold_negated_fail() {
+    let user_attrs = vec!["A.a:0", "A.b:0", "A.c:1"];
+    let policy = "!2of(A.a:0, A.b:0, A.c:0)";
+    assert_decryption_fail(user_attrs, policy);
+}
+
+#[test]
+fn threshold_multi_auth_ok() {
+    let user_attrs = vec!["B.a:0", "C.a:0"];
+    let policy = "2of(A.a:0, B.a:0, C.a:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn threshold_multi_auth_fail() {
+    let user_attrs = vec!["B.a:0", "C.a:1"];
+    let policy = "2of(A.a:0, B.a:0, C.a:0)";
+    assert_decryption_fail(user_attrs, policy);
+}
+
+#[test]
+fn hybrid_threshold_round_trip_ok() {
+    let user_attrs = vec!["A.a:0", "B.b:0"];
+    let policy = "2of(A.a:0, A.b:0, B.b:0)";
+    let plaintext = b"hybrid plaintext payload";
+    let aad = b"hybrid aad data";
+    let decrypted = test_hybrid_scheme(user_attrs, policy, plaintext, aad)
+        .expect("hybrid decrypt_hybrid failed");
+    assert_eq!(plaintext, decrypted.as_slice());
+}
+
+// Handcrafted test cases (negated sub-expressions)
+
+#[test]
+fn negated_conjunction_ok() {
+    let user_attrs = vec!["A.a:0", "B.b:1"];
+    let policy = "!(A.a:0 & B.b:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn negated_conjunction_fail() {
+    let user_attrs = vec!["A.a:0", "B.b:0"];
+    let policy = "!(A.a:0 & B.b:0)";
+    assert_decryption_fail(user_attrs, policy);
+}
+
+#[test]
+fn negated_disjunction_ok() {
+    let user_attrs = vec!["A.a:1", "B.b:1"];
+    let policy = "!(A.a:0 | B.b:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn negated_disjunction_fail() {
+    let user_attrs = vec!["A.a:1", "B.b:0"];
+    let policy = "!(A.a:0 | B.b:0)";
+    assert_decryption_fail(user_attrs, policy);
+}
+
+#[test]
+fn nested_negations_ok() {
+    let user_attrs = vec!["A.a:0", "A.b:0", "B.c:1"];
+    let policy = "A.a:0 & !(!A.b:0 | B.c:0)";
+    assert_decryption_ok(user_attrs, policy);
+}
+
+#[test]
+fn nested_negations_fail() {
+    let user_attrs = vec!["A.a:0", "A.b:1", "B.c:1"];
+    let policy = "A.a:0 & !(!A.b:0 | B.c:0)";
+    assert_decryption_fail(user_attrs, policy);
+}
+
+#[test]
+fn negated_sub_expression_matches_de_morgan() {
+    // A ciphertext encrypted under the negated form must be decryptable with
+    // the (Tau, Policy) pair derived from the normalised form, and vice versa.
+    let negated = "!(A.a:0 & (B.b:0 | !A.c:0))";
+    let normalised = "!A.a:0 | (!B.b:0 & A.c:0)";
+    let (auths, user_attrs, policy) = prepare_test(&vec!["A.a:1", "B.b:1", "A.c:0"], negated);
+    let normalised_policy = Policy::parse(normalised).unwrap();
+    assert!(policy == normalised_policy);
+
+    let mut rng = ark_std::test_rng();
+    let auths: Vec<&str> = auths.iter().map(|s| s as &str).collect();
+    let iota = Iota::new(&user_attrs);
+    let (msk, mpk) = setup(&mut rng, &auths);
+    let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota).unwrap();
+    let (k_enc, ct) = encrypt(&mut rng, &mpk, &policy, &Tau::new(&policy)).unwrap();
+    let tau = Tau::new(&normalised_policy);
+    let k_dec = decrypt(&usk, USER_ID, &iota, &tau, &normalised_policy, &ct);
+    assert!(k_dec.is_ok_and(|k| Gt::eq(&k_enc, &k)));
+}
+
 // Auto-generated test cases
 
 #[test]
@@ -201,7 +201,7 @@ fn run_shape_with_flavor(
         let (msk, mpk) = setup(&mut rng, &auths_ref);
 
         let iota = Iota::new(&user_attrs);
-        let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota);
+        let usk = keygen(&mut rng, USER_ID, &msk, &user_attrs, &iota)?;
         let policy = build_policy(&authorities, shape)?;
 
         for _ in 0..args.num_warmup_runs {
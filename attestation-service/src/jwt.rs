@@ -98,8 +98,13 @@ impl JwtClaims {
 
         let user_attribute_refs: Vec<&UserAttribute> = user_attributes.iter().collect();
         let iota = abe4::scheme::iota::Iota::new(&user_attributes);
-        let partial_usk: PartialUSK =
-            abe4::scheme::keygen_partial(rng, gid, &state.partial_msk, &user_attribute_refs, &iota);
+        let partial_usk: PartialUSK = abe4::scheme::keygen_partial(
+            rng,
+            gid,
+            &state.partial_msk,
+            &user_attribute_refs,
+            &iota,
+        )?;
         let mut partial_usk_bytes: Vec<u8> = Vec::new();
         partial_usk.serialize_compressed(&mut partial_usk_bytes)?;
 
@@ -7,7 +7,10 @@
 
 pub mod policy_compiler;
 
-use abe4::policy::Policy;
+use abe4::{
+    Abe4Error,
+    policy::{Policy, PolicyDiagnostic},
+};
 use log::error;
 use serde::{Deserialize, de::Error};
 use std::path::PathBuf;
@@ -112,9 +115,9 @@ pub struct Node {
     /// An optional access control policy for the node.
     ///
     /// The policy is a string that will be parsed into a
-    /// `accless_abe4::policy::Policy`. The policy language only allows
-    /// alphanumeric characters and underscores for attribute values. Dashes
-    /// are not allowed.
+    /// `accless_abe4::policy::Policy`. Unquoted attribute values may only
+    /// contain alphanumeric characters and underscores. Values with other
+    /// characters, such as dashes, must be quoted, e.g. `k8s.ns:"team-a"`.
     #[serde(rename = "node-policy")]
     #[serde(default)]
     #[serde(deserialize_with = "deserialize_policy")]
@@ -125,29 +128,26 @@ pub struct Node {
 ///
 /// A custom deserializer for the `node_policy` field.
 ///
-/// It parses a string into a `accless_abe4::policy::Policy`. It also checks
-/// for the presence of dashes in the policy string and returns an error if
-/// they are found.
+/// It parses a string into a `accless_abe4::policy::Policy`. Syntax errors
+/// are rendered with a caret pointing at the offending token of the policy.
 fn deserialize_policy<'de, D>(deserializer: D) -> Result<Option<Policy>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
     let s: Option<String> = Option::deserialize(deserializer)?;
     match s {
-        Some(s) => {
-            if s.contains('-') {
-                return Err(serde::de::Error::custom(
-                    "Policy attributes cannot contain dashes '-'. Use underscores '_' instead.",
-                ));
-            }
-            Policy::parse(&s)
-                .map(Some)
-                .map_err(serde::de::Error::custom)
-        }
+        Some(s) => Policy::parse(&s).map(Some).map_err(|e| match e {
+            Abe4Error::PolicySyntax(diagnostic) => policy_error(*diagnostic),
+            e => serde::de::Error::custom(e),
+        }),
         None => Ok(None),
     }
 }
 
+fn policy_error<E: serde::de::Error>(diagnostic: PolicyDiagnostic) -> E {
+    E::custom(format!("invalid node policy\n{}", diagnostic.render()))
+}
+
 /// # Description
 ///
 /// Defines the output directory for the encrypted state bundles.
@@ -432,12 +432,79 @@ output:
         assert!(result.is_err());
         let error = result.err().unwrap();
         assert!(
-            error.to_string().contains(
-                "Policy attributes cannot contain dashes '-'. Use underscores '_' instead."
-            )
+            error
+                .to_string()
+                .contains("illegal character '-' outside of quotes")
         );
     }
 
+    #[test]
+    fn test_parse_template_graph_with_quoted_policy_value() {
+        let yaml_content = r#"
+version: 1
+workflow:
+  name: fraud-detector
+
+authorities:
+  user:
+    id: user_42
+    mpk_abe: base64:mpk_abe_user
+  attestation-services:
+    - id: maa
+      mpk_abe: base64:mpk_abe_maa
+  attribute-providing-services:
+    - id: finra
+      mpk_abe: base64:mpk_abe_finra
+
+nodes:
+- name: fetch_private
+  function: fetch_private_data
+  node-policy: 'finra.role:"data-fetch"'
+
+edges: []
+
+output:
+  dir: ./out-ciphertexts
+        "#;
+
+        let template_graph = TemplateGraph::from_yaml(yaml_content).unwrap();
+        let policy = template_graph.nodes[0].node_policy.as_ref().unwrap();
+        assert_eq!(policy.get(0).0.attribute(), "data-fetch");
+    }
+
+    #[test]
+    fn test_parse_template_graph_with_policy_syntax_error() {
+        let yaml_content = r#"
+version: 1
+workflow:
+  name: fraud-detector
+
+authorities:
+  user:
+    id: user_42
+    mpk_abe: base64:mpk_abe_user
+  attestation-services:
+    - id: maa
+      mpk_abe: base64:mpk_abe_maa
+
+nodes:
+- name: fetch_private
+  function: fetch_private_data
+  node-policy: 'finra.role:fetch & | finra.role:audit'
+
+edges: []
+
+output:
+  dir: ./out-ciphertexts
+        "#;
+
+        let error = TemplateGraph::from_yaml(yaml_content).unwrap_err();
+        let error = error.to_string();
+        assert!(error.contains("unexpected '|'"), "{error}");
+        assert!(error.contains("1 | finra.role:fetch & | finra.role:audit"));
+        assert!(error.contains("  |                    ^ expected '!', '(' or identifier"));
+    }
+
     #[test]
     fn test_parse_template_graph_with_unknown_policy_authority() {
         let yaml_content = r#"
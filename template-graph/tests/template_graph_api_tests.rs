@@ -138,7 +138,7 @@ output:
     // Test encryption and decryption for each node
     for node in &template_graph.nodes {
         if let Some(policy) = policies.as_ref().unwrap().get(&node.name) {
-            let (k_enc, ct) = encrypt(&mut rng, &full_mpk, &policy, &Tau::new(&policy));
+            let (k_enc, ct) = encrypt(&mut rng, &full_mpk, &policy, &Tau::new(&policy)).unwrap();
 
             // Simulate a user with the required attributes
             let mut user_attrs = Vec::new();
@@ -179,7 +179,8 @@ output:
                 &msk,
                 &user_attrs,
                 &iota,
-            );
+            )
+            .unwrap();
 
             let k_dec = decrypt(
                 &usk,
@@ -190,7 +191,7 @@ output:
                 &ct,
             );
 
-            assert!(k_dec.is_some());
+            assert!(k_dec.is_ok());
             assert_eq!(k_enc, k_dec.unwrap());
         }
     }